@@ -0,0 +1,660 @@
+use alloc::{format, string::String, vec::Vec};
+use core::{fmt::Display, net::Ipv4Addr};
+
+use crate::{
+    fs::{self, FsError},
+    input::{
+        analog::{Analog, Calibration, Curve, Stick},
+        millis_to_frames, Action, ButtonMap, Buttons, Repeat,
+    },
+    openai::{
+        proxy::{base64_decode, base64_encode, Proxy, ProxyMode},
+        relay::Relay,
+        tls::TlsVerification,
+    },
+    platform::FileSystem,
+    settings::{format_temperature, parse_temperature, Settings, MAX_TEMPERATURE},
+};
+
+/// Name of the configuration file, inside the [data directory](crate::fs::DATA_DIR).
+pub const CONFIG_FILE: &str = "config.txt";
+/// Version of the format of the configuration files written. Files without a version are of
+/// version 1.
+pub const CONFIG_VERSION: u32 = 2;
+/// The quick replies of the chat, unless some are configured.
+pub const DEFAULT_QUICK_REPLIES: [(Buttons, &str); 4] = [
+    (Buttons::TRIANGLE, "Continue."),
+    (Buttons::SQUARE, "Make it shorter."),
+    (Buttons::LTRIGGER, "Explain more."),
+    (Buttons::RTRIGGER, "Give an example."),
+];
+
+/// The settings remembered between sessions.
+///
+/// The configuration file has one `key=value` setting per line. Unknown keys and invalid values
+/// are ignored, so that a damaged file only loses the settings it damaged. Files of older
+/// versions are migrated as they are parsed, and written back in the current format.
+///
+/// # Example
+/// ```text
+/// version=2
+/// model=gpt-4o-mini
+/// temperature=0.7
+/// network_profile=1
+/// text_size=large
+/// theme=amber
+/// input_method=keyboard
+/// dns_servers=1.1.1.1,8.8.8.8
+/// dns_ttl=300
+/// host=api.openai.com 104.18.7.192
+/// proxy=192.168.1.10:8080
+/// proxy_mode=connect
+/// proxy_auth=user:password
+/// relay=192.168.1.10:8080
+/// relay_secret=correct horse battery staple
+/// tls_verify=roots
+/// tls_pin=api.der
+/// tls_pin_sha256=47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=
+/// button=confirm circle
+/// repeat_delay=300
+/// repeat_interval=60
+/// stick_dead_zone=20
+/// stick_curve=quadratic
+/// stick_speed=15
+/// stick_calibration=130,126,12,8,250,244
+/// quick_reply=triangle Continue.
+/// quick_reply=l+r Translate that into French.
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    /// The settings edited in the app.
+    pub settings: Settings,
+    /// The DNS servers to query, in order. The default one is used if there are none.
+    pub dns_servers: Vec<Ipv4Addr>,
+    /// How long, in seconds, resolved addresses are cached.
+    pub dns_ttl: Option<u32>,
+    /// Hostnames resolved to a fixed address, without querying the DNS servers.
+    pub hosts: Vec<(String, Ipv4Addr)>,
+    /// The host and port of the HTTP proxy to reach the OpenAI API through, if any.
+    pub proxy: Option<(String, u16)>,
+    pub proxy_mode: ProxyMode,
+    /// Credentials for the proxy, as `user:password`.
+    pub proxy_auth: Option<String>,
+    /// The host and port of the relay to send the requests to instead of the API, if any.
+    pub relay: Option<(String, u16)>,
+    /// The secret shared with the relay.
+    pub relay_secret: String,
+    /// How the certificate of the API host is checked, unless one is pinned.
+    pub tls_verify: TlsVerification,
+    /// The file, inside the data directory, holding the DER certificate the API host must chain
+    /// up to, if any.
+    pub tls_pin: Option<String>,
+    /// The SHA-256 digest of the public key the certificate of the API host must hold, if any.
+    /// It takes precedence over the pinned certificate.
+    pub tls_pin_sha256: Option<[u8; 32]>,
+    /// The buttons mapped to actions, instead of the default ones.
+    pub buttons: Vec<(Action, Buttons)>,
+    /// How long, in milliseconds, a button must be held before it repeats.
+    pub repeat_delay: Option<u32>,
+    /// How long, in milliseconds, there is between two repeats of a held button.
+    pub repeat_interval: Option<u32>,
+    /// The share, in percent, of the travel of the stick around the center that is ignored.
+    pub stick_dead_zone: Option<u8>,
+    pub stick_curve: Curve,
+    /// How many lines or items the stick moves through per second, at full tilt.
+    pub stick_speed: Option<u32>,
+    /// The travel of the stick, as `center,min,max` positions, each as `x,y`.
+    pub stick_calibration: Option<Calibration>,
+    /// The prompts sent from the chat with a button, without typing them.
+    pub quick_replies: Vec<(Buttons, String)>,
+}
+
+impl Config {
+    /// Load the configuration from `files`, or the default one if there is none.
+    pub fn load<F: FileSystem>(files: &F) -> Self {
+        files
+            .read_to_string(&fs::data_path(CONFIG_FILE))
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default()
+    }
+
+    /// Parse the content of a configuration file.
+    pub fn parse(content: &str) -> Self {
+        let mut config = Self::default();
+        let settings = &mut config.settings;
+        let lines = content.lines().filter_map(|line| line.split_once('='));
+        let version = lines
+            .clone()
+            .find(|(key, _)| key.trim() == "version")
+            .and_then(|(_, version)| version.trim().parse().ok())
+            .unwrap_or(1);
+
+        for (key, value) in lines {
+            let value = value.trim();
+            match migrate_key(version, key.trim()) {
+                "model" => {
+                    // an invalid model keeps the default one
+                    let _ = settings.set_model(value);
+                }
+                "temperature" => {
+                    settings.temperature = parse_temperature(value)
+                        .filter(|tenths| *tenths <= MAX_TEMPERATURE)
+                        .unwrap_or(settings.temperature)
+                }
+                "network_profile" => {
+                    settings.network_profile = value.parse().ok().filter(|profile| *profile > 0)
+                }
+                "text_size" => settings.text_size = value.parse().unwrap_or_default(),
+                "theme" => settings.theme = value.parse().unwrap_or_default(),
+                "input_method" => settings.input_method = value.parse().unwrap_or_default(),
+                "dns_servers" => {
+                    config.dns_servers = value
+                        .split(',')
+                        .filter_map(|server| server.trim().parse().ok())
+                        .collect()
+                }
+                "dns_ttl" => config.dns_ttl = value.parse().ok(),
+                "proxy" => config.proxy = parse_host_port(value),
+                "proxy_mode" => config.proxy_mode = value.parse().unwrap_or_default(),
+                "proxy_auth" => {
+                    config.proxy_auth = Some(value.into()).filter(|auth: &String| !auth.is_empty())
+                }
+                "relay" => config.relay = parse_host_port(value),
+                "relay_secret" => config.relay_secret = value.into(),
+                "tls_verify" => config.tls_verify = value.parse().unwrap_or_default(),
+                "tls_pin" => {
+                    config.tls_pin = Some(value.into()).filter(|pin: &String| !pin.is_empty())
+                }
+                "tls_pin_sha256" => {
+                    config.tls_pin_sha256 =
+                        base64_decode(value).and_then(|digest| digest.try_into().ok())
+                }
+                "host" => {
+                    let host = value
+                        .split_once(char::is_whitespace)
+                        .and_then(|(name, addr)| Some((name.into(), addr.trim().parse().ok()?)));
+                    config.hosts.extend(host);
+                }
+                "button" => {
+                    let button =
+                        value
+                            .split_once(char::is_whitespace)
+                            .and_then(|(action, buttons)| {
+                                Some((action.parse().ok()?, buttons.trim().parse().ok()?))
+                            });
+                    config.buttons.extend(button);
+                }
+                "repeat_delay" => config.repeat_delay = value.parse().ok(),
+                "repeat_interval" => config.repeat_interval = value.parse().ok(),
+                "stick_dead_zone" => {
+                    config.stick_dead_zone = value.parse().ok().filter(|zone| *zone < 100)
+                }
+                "stick_curve" => config.stick_curve = value.parse().unwrap_or_default(),
+                "stick_speed" => config.stick_speed = value.parse().ok(),
+                "stick_calibration" => config.stick_calibration = parse_calibration(value),
+                "quick_reply" => {
+                    let reply = value
+                        .split_once(char::is_whitespace)
+                        .and_then(|(buttons, prompt)| Some((buttons.parse().ok()?, prompt.trim())))
+                        .filter(|(_, prompt)| !prompt.is_empty());
+                    config
+                        .quick_replies
+                        .extend(reply.map(|(buttons, prompt)| (buttons, prompt.into())));
+                }
+                _ => (),
+            }
+        }
+
+        config
+    }
+
+    /// The HTTP proxy to reach the OpenAI API through, if any.
+    pub fn proxy(&self) -> Option<Proxy> {
+        self.proxy.as_ref().map(|(host, port)| Proxy {
+            host: host.clone(),
+            port: *port,
+            mode: self.proxy_mode,
+            credentials: self.proxy_auth.clone(),
+        })
+    }
+
+    /// The buttons of the actions: those of `system`, the mapping following the confirm button
+    /// set in the system settings, with the configured ones applied over them.
+    pub fn button_map(&self, system: ButtonMap) -> ButtonMap {
+        let mut map = system;
+        for (action, buttons) in &self.buttons {
+            map.set(*action, *buttons);
+        }
+        map
+    }
+
+    /// How held buttons repeat, with the default delay or interval unless configured.
+    pub fn repeat(&self) -> Repeat {
+        let default = Repeat::default();
+        Repeat {
+            delay: self.repeat_delay.map_or(default.delay, millis_to_frames),
+            interval: self
+                .repeat_interval
+                .map_or(default.interval, millis_to_frames),
+        }
+    }
+
+    /// How the positions of the stick turn into a tilt, with the defaults of [`Analog`] unless
+    /// configured.
+    pub fn analog(&self) -> Analog {
+        let default = Analog::default();
+        Analog {
+            calibration: self.stick_calibration.unwrap_or_default(),
+            dead_zone: self.stick_dead_zone.unwrap_or(default.dead_zone),
+            curve: self.stick_curve,
+            speed: self.stick_speed.unwrap_or(default.speed),
+        }
+    }
+
+    /// The prompts sent from the chat with a button: the configured ones, or
+    /// [`DEFAULT_QUICK_REPLIES`] if there are none.
+    pub fn quick_replies(&self) -> Vec<(Buttons, String)> {
+        if self.quick_replies.is_empty() {
+            DEFAULT_QUICK_REPLIES
+                .iter()
+                .map(|(buttons, prompt)| (*buttons, (*prompt).into()))
+                .collect()
+        } else {
+            self.quick_replies.clone()
+        }
+    }
+
+    /// The relay to send the requests to instead of the API, if any.
+    pub fn relay(&self) -> Option<Relay> {
+        self.relay.as_ref().map(|(host, port)| Relay {
+            host: host.clone(),
+            port: *port,
+            secret: self.relay_secret.clone(),
+        })
+    }
+
+    /// How the certificate of the API host is checked, with the pinned certificate read from
+    /// `files`.
+    ///
+    /// # Errors
+    /// A [`FsError`] if the pinned certificate cannot be read.
+    pub fn tls_verification<F: FileSystem>(&self, files: &F) -> Result<TlsVerification, FsError> {
+        if let Some(digest) = self.tls_pin_sha256 {
+            return Ok(TlsVerification::PinnedKey(digest));
+        }
+        match &self.tls_pin {
+            Some(pin) => files.read(&fs::data_path(pin)).map(TlsVerification::Pinned),
+            None => Ok(self.tls_verify.clone()),
+        }
+    }
+
+    /// Save the configuration to `files`.
+    ///
+    /// # Errors
+    /// A [`FsError`] if the file cannot be written.
+    pub fn save<F: FileSystem>(&self, files: &mut F) -> Result<(), FsError> {
+        let content = format!("{}", self);
+        files.write(&fs::data_path(CONFIG_FILE), content.as_bytes())
+    }
+}
+
+impl Display for Config {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(f, "version={}", CONFIG_VERSION)?;
+        let settings = &self.settings;
+        let default = Settings::default();
+        if settings.model != default.model {
+            writeln!(f, "model={}", settings.model)?;
+        }
+        if settings.temperature != default.temperature {
+            writeln!(
+                f,
+                "temperature={}",
+                format_temperature(settings.temperature)
+            )?;
+        }
+        if let Some(network_profile) = settings.network_profile {
+            writeln!(f, "network_profile={}", network_profile)?;
+        }
+        if settings.text_size != default.text_size {
+            writeln!(f, "text_size={}", settings.text_size)?;
+        }
+        if settings.theme != default.theme {
+            writeln!(f, "theme={}", settings.theme)?;
+        }
+        if settings.input_method != default.input_method {
+            writeln!(f, "input_method={}", settings.input_method)?;
+        }
+        if !self.dns_servers.is_empty() {
+            let servers: Vec<String> = self.dns_servers.iter().map(|s| format!("{}", s)).collect();
+            writeln!(f, "dns_servers={}", servers.join(","))?;
+        }
+        if let Some(dns_ttl) = self.dns_ttl {
+            writeln!(f, "dns_ttl={}", dns_ttl)?;
+        }
+        for (name, addr) in &self.hosts {
+            writeln!(f, "host={} {}", name, addr)?;
+        }
+        if let Some((host, port)) = &self.proxy {
+            writeln!(f, "proxy={}:{}", host, port)?;
+            writeln!(f, "proxy_mode={}", self.proxy_mode)?;
+        }
+        if let Some(proxy_auth) = &self.proxy_auth {
+            writeln!(f, "proxy_auth={}", proxy_auth)?;
+        }
+        if let Some((host, port)) = &self.relay {
+            writeln!(f, "relay={}:{}", host, port)?;
+            writeln!(f, "relay_secret={}", self.relay_secret)?;
+        }
+        if self.tls_verify != TlsVerification::default() {
+            writeln!(f, "tls_verify={}", self.tls_verify)?;
+        }
+        if let Some(tls_pin) = &self.tls_pin {
+            writeln!(f, "tls_pin={}", tls_pin)?;
+        }
+        if let Some(digest) = &self.tls_pin_sha256 {
+            writeln!(f, "tls_pin_sha256={}", base64_encode(digest))?;
+        }
+        for (action, buttons) in &self.buttons {
+            writeln!(f, "button={} {}", action, buttons)?;
+        }
+        if let Some(repeat_delay) = self.repeat_delay {
+            writeln!(f, "repeat_delay={}", repeat_delay)?;
+        }
+        if let Some(repeat_interval) = self.repeat_interval {
+            writeln!(f, "repeat_interval={}", repeat_interval)?;
+        }
+        if let Some(stick_dead_zone) = self.stick_dead_zone {
+            writeln!(f, "stick_dead_zone={}", stick_dead_zone)?;
+        }
+        if self.stick_curve != Curve::default() {
+            writeln!(f, "stick_curve={}", self.stick_curve)?;
+        }
+        if let Some(stick_speed) = self.stick_speed {
+            writeln!(f, "stick_speed={}", stick_speed)?;
+        }
+        if let Some(Calibration { center, min, max }) = &self.stick_calibration {
+            writeln!(
+                f,
+                "stick_calibration={},{},{},{},{},{}",
+                center.x, center.y, min.x, min.y, max.x, max.y
+            )?;
+        }
+        for (buttons, prompt) in &self.quick_replies {
+            writeln!(f, "quick_reply={} {}", buttons, prompt)?;
+        }
+        Ok(())
+    }
+}
+
+/// The key of the current format that `key`, read from a file of format `version`, stands for.
+fn migrate_key(version: u32, key: &str) -> &str {
+    match (version, key) {
+        // renamed after the settings screen, in version 2
+        (1, "access_point") => "network_profile",
+        _ => key,
+    }
+}
+
+/// Parse a `center_x,center_y,min_x,min_y,max_x,max_y` stick calibration.
+fn parse_calibration(value: &str) -> Option<Calibration> {
+    let positions: Vec<u8> = value
+        .split(',')
+        .map(|position| position.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    let [center_x, center_y, min_x, min_y, max_x, max_y] = positions[..] else {
+        return None;
+    };
+    let stick = |x, y| Stick { x, y };
+    Some(Calibration {
+        center: stick(center_x, center_y),
+        min: stick(min_x, min_y),
+        max: stick(max_x, max_y),
+    })
+}
+
+/// Parse a `host:port` value.
+fn parse_host_port(value: &str) -> Option<(String, u16)> {
+    let (host, port) = value.rsplit_once(':')?;
+    Some((host.trim().into(), port.trim().parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::ToOwned, vec};
+
+    use super::*;
+    use crate::{
+        keyboard::InputMethod,
+        layout::{TextSize, Theme},
+        platform::fake::MemoryFs,
+    };
+
+    /// The SHA-256 digest of nothing, `47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=` in base64.
+    const EMPTY_SHA256: [u8; 32] = [
+        0xe3, 0xb0, 0xc4, 0x42, 0x98, 0xfc, 0x1c, 0x14, 0x9a, 0xfb, 0xf4, 0xc8, 0x99, 0x6f, 0xb9,
+        0x24, 0x27, 0xae, 0x41, 0xe4, 0x64, 0x9b, 0x93, 0x4c, 0xa4, 0x95, 0x99, 0x1b, 0x78, 0x52,
+        0xb8, 0x55,
+    ];
+
+    const EXAMPLE: &str = "version=2
+model=gpt-4o-mini
+temperature=1.2
+network_profile=1
+text_size=large
+theme=amber
+input_method=keyboard
+dns_servers=1.1.1.1, 8.8.8.8,bogus
+dns_ttl=300
+host=api.openai.com 104.18.7.192
+proxy=192.168.1.10:8080
+proxy_mode=forward
+proxy_auth=user:password
+relay=relay.lan:9000
+relay_secret=correct horse
+tls_verify=none
+tls_pin=api.der
+tls_pin_sha256=47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=
+button=menu select
+button=confirm l+r
+button=jump cross
+repeat_delay=500
+stick_dead_zone=15
+stick_curve=cubic
+stick_calibration=130,126,12,8,250,244
+quick_reply=triangle Go on.
+quick_reply=l+r  Translate that.
+quick_reply=home Nope
+quick_reply=square
+";
+
+    #[test]
+    fn parses_every_setting() {
+        let config = Config::parse(EXAMPLE);
+
+        assert_eq!(
+            config.settings,
+            Settings {
+                model: "gpt-4o-mini".to_owned(),
+                temperature: 12,
+                network_profile: Some(1),
+                text_size: TextSize::Large,
+                theme: Theme::Amber,
+                input_method: InputMethod::Keyboard,
+            }
+        );
+        assert_eq!(
+            config.dns_servers,
+            [Ipv4Addr::new(1, 1, 1, 1), Ipv4Addr::new(8, 8, 8, 8)]
+        );
+        assert_eq!(config.dns_ttl, Some(300));
+        assert_eq!(
+            config.hosts,
+            [("api.openai.com".to_owned(), Ipv4Addr::new(104, 18, 7, 192))]
+        );
+        let proxy = config.proxy().unwrap();
+        assert_eq!((proxy.host.as_str(), proxy.port), ("192.168.1.10", 8080));
+        assert_eq!(proxy.mode, ProxyMode::Forward);
+        assert_eq!(proxy.credentials.as_deref(), Some("user:password"));
+        let relay = config.relay().unwrap();
+        assert_eq!((relay.host.as_str(), relay.port), ("relay.lan", 9000));
+        assert_eq!(relay.secret, "correct horse");
+        assert_eq!(config.tls_verify, TlsVerification::None);
+        assert_eq!(config.tls_pin.as_deref(), Some("api.der"));
+        assert_eq!(config.tls_pin_sha256, Some(EMPTY_SHA256));
+        assert_eq!(
+            config.quick_replies(),
+            [
+                (Buttons::TRIANGLE, "Go on.".to_owned()),
+                (
+                    Buttons::LTRIGGER | Buttons::RTRIGGER,
+                    "Translate that.".to_owned()
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn maps_the_configured_buttons() {
+        let config = Config::parse(EXAMPLE);
+        assert_eq!(
+            config.buttons,
+            [
+                (Action::Menu, Buttons::SELECT),
+                (Action::Confirm, Buttons::LTRIGGER | Buttons::RTRIGGER)
+            ]
+        );
+
+        let map = config.button_map(ButtonMap::CIRCLE_CONFIRMS);
+        assert_eq!(map.buttons(Action::Menu), Buttons::SELECT);
+        assert_eq!(
+            map.buttons(Action::Confirm),
+            Buttons::LTRIGGER | Buttons::RTRIGGER
+        );
+        assert_eq!(map.buttons(Action::Cancel), Buttons::CROSS);
+
+        let system = ButtonMap::CIRCLE_CONFIRMS;
+        assert_eq!(Config::default().button_map(system), system);
+    }
+
+    #[test]
+    fn converts_the_repeat() {
+        let repeat = Config::parse(EXAMPLE).repeat();
+        assert_eq!(repeat.delay, 30);
+        assert_eq!(repeat.interval, Repeat::default().interval);
+        assert_eq!(Config::default().repeat(), Repeat::default());
+    }
+
+    #[test]
+    fn configures_the_stick() {
+        let analog = Config::parse(EXAMPLE).analog();
+        assert_eq!(analog.dead_zone, 15);
+        assert_eq!(analog.curve, Curve::Cubic);
+        assert_eq!(analog.speed, Analog::default().speed);
+        assert_eq!(analog.calibration.center, Stick { x: 130, y: 126 });
+        assert_eq!(analog.calibration.max, Stick { x: 250, y: 244 });
+
+        let config = Config::parse("stick_dead_zone=100\nstick_calibration=1,2,3\n");
+        assert_eq!(config.analog(), Analog::default());
+    }
+
+    #[test]
+    fn defaults_the_quick_replies() {
+        let replies = Config::default().quick_replies();
+        assert_eq!(replies.len(), DEFAULT_QUICK_REPLIES.len());
+        assert_eq!(replies[0], (Buttons::TRIANGLE, "Continue.".to_owned()));
+    }
+
+    #[test]
+    fn ignores_damaged_lines() {
+        let config = Config::parse(
+            "garbage\nnetwork_profile=one\nproxy=nohost\nmodel=gpt 4\ntemperature=2.5\ndns_ttl=60\n",
+        );
+        assert_eq!(
+            config,
+            Config {
+                dns_ttl: Some(60),
+                ..Config::default()
+            }
+        );
+    }
+
+    #[test]
+    fn saves_what_it_loads() {
+        let mut files = MemoryFs::default();
+        let config = Config::parse(EXAMPLE);
+
+        config.save(&mut files).unwrap();
+
+        assert_eq!(Config::load(&files), config);
+        assert!(files.file(&fs::data_path(CONFIG_FILE)).is_some());
+    }
+
+    #[test]
+    fn defaults_without_a_file() {
+        assert_eq!(Config::load(&MemoryFs::default()), Config::default());
+        assert_eq!(Config::default().to_string(), "version=2\n");
+    }
+
+    #[test]
+    fn remembers_the_input_method() {
+        let mut config = Config::default();
+        config.settings.input_method = InputMethod::Keyboard;
+        assert_eq!(config.to_string(), "version=2\ninput_method=keyboard\n");
+        assert_eq!(Config::parse(&config.to_string()), config);
+
+        // files written before it could be chosen use the system OSK
+        let config = Config::parse("version=2\ntheme=amber\n");
+        assert_eq!(config.settings.input_method, InputMethod::Osk);
+    }
+
+    #[test]
+    fn migrates_older_files() {
+        let config = Config::parse("access_point=3\ndns_ttl=60\n");
+        assert_eq!(config.settings.network_profile, Some(3));
+        assert_eq!(
+            config.to_string(),
+            "version=2\nnetwork_profile=3\ndns_ttl=60\n"
+        );
+
+        // only the keys of older versions are migrated
+        let config = Config::parse("version=2\naccess_point=3\n");
+        assert_eq!(config.settings.network_profile, None);
+    }
+
+    #[test]
+    fn reads_the_pinned_certificate() {
+        let files = MemoryFs::default().with_file(&fs::data_path("api.der"), &[0x30, 0x82]);
+        let mut config = Config::parse("tls_pin=api.der");
+        assert_eq!(
+            config.tls_verification(&files),
+            Ok(TlsVerification::Pinned(vec![0x30, 0x82]))
+        );
+
+        config.tls_pin = Some("missing.der".to_owned());
+        assert!(matches!(
+            config.tls_verification(&files),
+            Err(FsError::Open(_, _))
+        ));
+        config.tls_pin = None;
+        assert_eq!(config.tls_verification(&files), Ok(TlsVerification::Roots));
+    }
+
+    #[test]
+    fn pinned_keys_take_precedence() {
+        let files = MemoryFs::default().with_file(&fs::data_path("api.der"), &[0x30, 0x82]);
+        let config = Config::parse(
+            "tls_pin=api.der\ntls_pin_sha256=47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=\n",
+        );
+        assert_eq!(
+            config.tls_verification(&files),
+            Ok(TlsVerification::PinnedKey(EMPTY_SHA256))
+        );
+
+        // digests of another length, or not base64, are ignored
+        for value in ["AAAA", "not base64!", ""] {
+            let config = Config::parse(&format!("tls_pin_sha256={}", value));
+            assert_eq!(config.tls_pin_sha256, None);
+        }
+    }
+}
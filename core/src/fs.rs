@@ -0,0 +1,34 @@
+use alloc::{format, string::String};
+use core::fmt::Display;
+
+/// Directory on the Memory Stick where the application keeps its data files.
+pub const DATA_DIR: &str = "ms0:/PSP/COMMON/ChatGPSP";
+
+/// An error that can occur while accessing the Memory Stick.
+///
+/// Each variant carries the path of the file and, where available, the error code returned by
+/// the firmware.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsError {
+    Open(String, i32),
+    Read(String, i32),
+    Write(String, i32),
+    InvalidUtf8(String),
+}
+
+impl Display for FsError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FsError::Open(path, code) => write!(f, "cannot open {} ({:#x})", path, code),
+            FsError::Read(path, code) => write!(f, "cannot read {} ({:#x})", path, code),
+            FsError::Write(path, code) => write!(f, "cannot write {} ({:#x})", path, code),
+            FsError::InvalidUtf8(path) => write!(f, "{} is not valid UTF-8", path),
+        }
+    }
+}
+
+#[inline]
+/// Get the path of a file inside [`DATA_DIR`].
+pub fn data_path(file_name: &str) -> String {
+    format!("{}/{}", DATA_DIR, file_name)
+}
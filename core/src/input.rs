@@ -0,0 +1,743 @@
+use alloc::{string::String, vec::Vec};
+use core::{
+    fmt::Display,
+    ops::{BitOr, BitOrAssign},
+    str::FromStr,
+};
+
+use crate::{
+    input::analog::{Analog, Stick, FULL_TILT},
+    platform::Input,
+};
+
+pub mod analog;
+
+/// The buttons with a name, in the order of their bits.
+const NAMED: [(Buttons, &str, &str); 12] = [
+    (Buttons::SELECT, "select", "SELECT"),
+    (Buttons::START, "start", "START"),
+    (Buttons::UP, "up", "Up"),
+    (Buttons::RIGHT, "right", "Right"),
+    (Buttons::DOWN, "down", "Down"),
+    (Buttons::LEFT, "left", "Left"),
+    (Buttons::LTRIGGER, "l", "L"),
+    (Buttons::RTRIGGER, "r", "R"),
+    (Buttons::TRIANGLE, "triangle", "/\\"),
+    (Buttons::CIRCLE, "circle", "O"),
+    (Buttons::CROSS, "cross", "X"),
+    (Buttons::SQUARE, "square", "[]"),
+];
+
+/// A set of buttons of the PSP, with the bit values of the controller data of the firmware.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Buttons(u32);
+
+impl Buttons {
+    pub const SELECT: Self = Self(0x0001);
+    pub const START: Self = Self(0x0008);
+    pub const UP: Self = Self(0x0010);
+    pub const RIGHT: Self = Self(0x0020);
+    pub const DOWN: Self = Self(0x0040);
+    pub const LEFT: Self = Self(0x0080);
+    pub const LTRIGGER: Self = Self(0x0100);
+    pub const RTRIGGER: Self = Self(0x0200);
+    pub const TRIANGLE: Self = Self(0x1000);
+    pub const CIRCLE: Self = Self(0x2000);
+    pub const CROSS: Self = Self(0x4000);
+    pub const SQUARE: Self = Self(0x8000);
+
+    #[inline]
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Every button above.
+    #[inline]
+    pub const fn all() -> Self {
+        Self(0xf3f9)
+    }
+
+    /// The buttons set in `bits`, ignoring the bits of the other controls, e.g. the Home button
+    /// or the Wi-Fi switch.
+    #[inline]
+    pub const fn from_bits_truncate(bits: u32) -> Self {
+        Self(bits & Self::all().0)
+    }
+
+    #[inline]
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    #[inline]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Whether any of `other` is in the set.
+    #[inline]
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Whether all of `other` is in the set.
+    #[inline]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// The buttons of the set that are not in `other`.
+    #[inline]
+    pub const fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    /// The short name of the buttons shown in hints, e.g. `X` or `L+R`.
+    pub fn label(self) -> String {
+        let labels: Vec<&str> = NAMED
+            .iter()
+            .filter(|(button, _, _)| self.contains(*button))
+            .map(|(_, _, label)| *label)
+            .collect();
+        labels.join("+")
+    }
+}
+
+/// Parse the name of a button, e.g. `cross`, or of several joined by `+`, e.g. `l+r`.
+impl FromStr for Buttons {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let mut buttons = Self::empty();
+        for name in s.split('+') {
+            let name = name.trim();
+            let (button, _, _) = NAMED
+                .iter()
+                .find(|(_, known, _)| known.eq_ignore_ascii_case(name))
+                .ok_or(())?;
+            buttons |= *button;
+        }
+        Ok(buttons)
+    }
+}
+
+impl Display for Buttons {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let names: Vec<&str> = NAMED
+            .iter()
+            .filter(|(button, _, _)| self.contains(*button))
+            .map(|(_, name, _)| *name)
+            .collect();
+        write!(f, "{}", names.join("+"))
+    }
+}
+
+impl BitOr for Buttons {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Buttons {
+    #[inline]
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// What the user does with the buttons, whatever they are mapped to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Choose the selected item, or go on.
+    Confirm,
+    /// Go back, or cancel what is in progress.
+    Cancel,
+    /// Move the selection or the text up.
+    Up,
+    /// Move the selection or the text down.
+    Down,
+    /// The secondary command of the screen, e.g. sending the draft of the composer, or the text
+    /// of the keyboard.
+    Menu,
+    /// Delete the last character typed with the keyboard.
+    Delete,
+    /// Type a space with the keyboard.
+    Space,
+    /// Switch the keyboard to its next layer of characters.
+    Layer,
+    /// Insert the selected suggestion of the keyboard.
+    Complete,
+    /// Close the keyboard, discarding the text.
+    Discard,
+}
+
+impl Action {
+    pub const ALL: [Action; 10] = [
+        Action::Confirm,
+        Action::Cancel,
+        Action::Up,
+        Action::Down,
+        Action::Menu,
+        Action::Delete,
+        Action::Space,
+        Action::Layer,
+        Action::Complete,
+        Action::Discard,
+    ];
+}
+
+impl FromStr for Action {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "confirm" => Ok(Action::Confirm),
+            "cancel" => Ok(Action::Cancel),
+            "up" => Ok(Action::Up),
+            "down" => Ok(Action::Down),
+            "menu" => Ok(Action::Menu),
+            "delete" => Ok(Action::Delete),
+            "space" => Ok(Action::Space),
+            "layer" => Ok(Action::Layer),
+            "complete" => Ok(Action::Complete),
+            "discard" => Ok(Action::Discard),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Action::Confirm => write!(f, "confirm"),
+            Action::Cancel => write!(f, "cancel"),
+            Action::Up => write!(f, "up"),
+            Action::Down => write!(f, "down"),
+            Action::Menu => write!(f, "menu"),
+            Action::Delete => write!(f, "delete"),
+            Action::Space => write!(f, "space"),
+            Action::Layer => write!(f, "layer"),
+            Action::Complete => write!(f, "complete"),
+            Action::Discard => write!(f, "discard"),
+        }
+    }
+}
+
+/// The buttons each [`Action`] is mapped to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ButtonMap {
+    confirm: Buttons,
+    cancel: Buttons,
+    up: Buttons,
+    down: Buttons,
+    menu: Buttons,
+    delete: Buttons,
+    space: Buttons,
+    layer: Buttons,
+    complete: Buttons,
+    discard: Buttons,
+}
+
+impl ButtonMap {
+    /// Cross confirms and Circle cancels, as on Western consoles.
+    pub const CROSS_CONFIRMS: Self = Self {
+        confirm: Buttons::CROSS,
+        cancel: Buttons::CIRCLE,
+        up: Buttons::UP,
+        down: Buttons::DOWN,
+        menu: Buttons::START,
+        delete: Buttons::LEFT,
+        space: Buttons::RIGHT,
+        layer: Buttons::LTRIGGER,
+        complete: Buttons::RTRIGGER,
+        discard: Buttons::SELECT,
+    };
+    /// Circle confirms and Cross cancels, as on Japanese consoles.
+    pub const CIRCLE_CONFIRMS: Self = Self {
+        confirm: Buttons::CIRCLE,
+        cancel: Buttons::CROSS,
+        ..Self::CROSS_CONFIRMS
+    };
+
+    /// The buttons `action` is mapped to.
+    pub fn buttons(&self, action: Action) -> Buttons {
+        match action {
+            Action::Confirm => self.confirm,
+            Action::Cancel => self.cancel,
+            Action::Up => self.up,
+            Action::Down => self.down,
+            Action::Menu => self.menu,
+            Action::Delete => self.delete,
+            Action::Space => self.space,
+            Action::Layer => self.layer,
+            Action::Complete => self.complete,
+            Action::Discard => self.discard,
+        }
+    }
+
+    fn buttons_mut(&mut self, action: Action) -> &mut Buttons {
+        match action {
+            Action::Confirm => &mut self.confirm,
+            Action::Cancel => &mut self.cancel,
+            Action::Up => &mut self.up,
+            Action::Down => &mut self.down,
+            Action::Menu => &mut self.menu,
+            Action::Delete => &mut self.delete,
+            Action::Space => &mut self.space,
+            Action::Layer => &mut self.layer,
+            Action::Complete => &mut self.complete,
+            Action::Discard => &mut self.discard,
+        }
+    }
+
+    /// Map `action` to `buttons`. The action they were mapped to, if any, takes the previous
+    /// buttons of `action`, so that every action keeps a button.
+    pub fn set(&mut self, action: Action, buttons: Buttons) {
+        let previous = self.buttons(action);
+        if let Some(other) = Action::ALL
+            .into_iter()
+            .find(|other| *other != action && self.buttons(*other) == buttons)
+        {
+            *self.buttons_mut(other) = previous;
+        }
+        *self.buttons_mut(action) = buttons;
+    }
+}
+
+impl Default for ButtonMap {
+    #[inline]
+    fn default() -> Self {
+        Self::CROSS_CONFIRMS
+    }
+}
+
+/// Number of frames the PSP shows per second.
+pub const FRAMES_PER_SECOND: u32 = 60;
+
+/// The number of frames lasting `ms` milliseconds, rounded up, and at least one.
+pub fn millis_to_frames(ms: u32) -> u32 {
+    (ms.saturating_mul(FRAMES_PER_SECOND)).div_ceil(1000).max(1)
+}
+
+/// How a held button repeats, e.g. to keep scrolling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Repeat {
+    /// Frames a button must be held before it starts repeating.
+    pub delay: u32,
+    /// Frames between two repeats.
+    pub interval: u32,
+}
+
+impl Repeat {
+    /// Repeat after `delay` milliseconds, then every `interval` milliseconds, rounded to whole
+    /// frames.
+    pub fn from_millis(delay: u32, interval: u32) -> Self {
+        Self {
+            delay: millis_to_frames(delay),
+            interval: millis_to_frames(interval),
+        }
+    }
+}
+
+impl Default for Repeat {
+    /// About a third of a second, then 15 times per second.
+    #[inline]
+    fn default() -> Self {
+        Self {
+            delay: 20,
+            interval: 4,
+        }
+    }
+}
+
+/// Handles user input
+///
+/// It tracks the buttons down in the current and previous frames, to report presses and releases
+/// once, and how long each has been held, to repeat them. The tilt of the analog stick moves
+/// through lines and items at a proportional pace, in whole [steps](Self::stick_steps).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InputHandler {
+    current: Buttons,
+    previous: Buttons,
+    /// Buttons left down when the handler was [resynced](Self::resync), ignored until released.
+    ignored: Buttons,
+    /// For each bit of the buttons, the number of frames it has been down.
+    held_frames: [u32; 16],
+    map: ButtonMap,
+    repeat: Repeat,
+    analog: Analog,
+    stick: Stick,
+    tilt: (i32, i32),
+    /// The vertical motion of the stick not yet turned into steps, in thousandths of a step per
+    /// frame.
+    motion: i32,
+    steps: i32,
+}
+
+impl InputHandler {
+    /// Sample the buttons of `input` for the current frame.
+    ///
+    /// Call once per frame, before checking the buttons with [`Self::is_pressed`] or
+    /// [`Self::is_held`].
+    pub fn update<I: Input>(&mut self, input: &mut I) {
+        let buttons = input.buttons();
+        self.ignored = Buttons(self.ignored.0 & buttons.0);
+        self.previous = self.current;
+        self.current = buttons.difference(self.ignored);
+
+        for (bit, frames) in self.held_frames.iter_mut().enumerate() {
+            if self.current.0 & (1 << bit) != 0 {
+                *frames = frames.saturating_add(1);
+            } else {
+                *frames = 0;
+            }
+        }
+
+        self.stick = input.stick();
+        self.tilt = self.analog.tilt(self.stick);
+        if self.tilt.1 == 0 {
+            // the stick let go does not finish a step
+            self.motion = 0;
+        }
+        self.motion += self.tilt.1 * self.analog.speed as i32;
+        let step = FULL_TILT * FRAMES_PER_SECOND as i32;
+        self.steps = self.motion / step;
+        self.motion %= step;
+    }
+
+    /// Sample `input` again after the application stopped updating the handler, e.g. while a
+    /// system dialog was shown, ignoring the buttons still down until they are released.
+    ///
+    /// This keeps the button that closed the dialog from also acting on the screen below it.
+    pub fn resync<I: Input>(&mut self, input: &mut I) {
+        self.ignored = input.buttons();
+        self.previous = Buttons::empty();
+        self.current = Buttons::empty();
+        self.held_frames = [0; 16];
+        self.motion = 0;
+        self.steps = 0;
+    }
+
+    /// Whether any of `buttons` was pressed in the current frame, i.e. it is down now but was not
+    /// in the previous frame.
+    #[inline]
+    pub fn is_pressed(&self, buttons: Buttons) -> bool {
+        self.current.difference(self.previous).intersects(buttons)
+    }
+
+    /// Whether any of `buttons` was released in the current frame, i.e. it was down in the
+    /// previous frame but is not anymore.
+    #[inline]
+    pub fn is_released(&self, buttons: Buttons) -> bool {
+        self.previous.difference(self.current).intersects(buttons)
+    }
+
+    /// Whether any of `buttons` was pressed in the current frame, or has been held long enough to
+    /// repeat in it.
+    pub fn is_repeated(&self, buttons: Buttons) -> bool {
+        let Repeat { delay, interval } = self.repeat;
+        self.held_frames
+            .iter()
+            .enumerate()
+            .filter(|(bit, _)| buttons.0 & (1 << bit) != 0)
+            .any(|(_, &frames)| {
+                frames == 1 || (frames > delay && (frames - delay - 1) % interval.max(1) == 0)
+            })
+    }
+
+    /// Whether the buttons of `action` were pressed in the current frame.
+    #[inline]
+    pub fn is_action_pressed(&self, action: Action) -> bool {
+        self.is_pressed(self.map.buttons(action))
+    }
+
+    /// Whether the buttons of `action` were pressed or repeated in the current frame.
+    #[inline]
+    pub fn is_action_repeated(&self, action: Action) -> bool {
+        self.is_repeated(self.map.buttons(action))
+    }
+
+    /// Map the actions to the buttons of `map`.
+    #[inline]
+    pub fn set_map(&mut self, map: ButtonMap) {
+        self.map = map;
+    }
+
+    #[inline]
+    pub fn map(&self) -> &ButtonMap {
+        &self.map
+    }
+
+    /// Repeat the held buttons following `repeat`.
+    #[inline]
+    pub fn set_repeat(&mut self, repeat: Repeat) {
+        self.repeat = repeat;
+    }
+
+    /// Turn the positions of the stick into a tilt following `analog`.
+    #[inline]
+    pub fn set_analog(&mut self, analog: Analog) {
+        self.analog = analog;
+    }
+
+    #[inline]
+    pub fn analog(&self) -> &Analog {
+        &self.analog
+    }
+
+    /// The position of the stick in the current frame, as sampled.
+    #[inline]
+    pub fn stick(&self) -> Stick {
+        self.stick
+    }
+
+    /// The tilt of the stick in the current frame, see [`Analog::tilt`].
+    #[inline]
+    pub fn tilt(&self) -> (i32, i32) {
+        self.tilt
+    }
+
+    /// The number of lines or items to move through in the current frame with the stick,
+    /// negative upwards.
+    #[inline]
+    pub fn stick_steps(&self) -> i32 {
+        self.steps
+    }
+
+    /// The short name of the buttons of `action`, for hints, e.g. `X`.
+    #[inline]
+    pub fn label(&self, action: Action) -> String {
+        self.map.buttons(action).label()
+    }
+
+    /// Whether all of `buttons` are down in the current frame, and one of them was pressed in it,
+    /// e.g. for chords like L+R.
+    #[inline]
+    pub fn is_chord_pressed(&self, buttons: Buttons) -> bool {
+        self.current.contains(buttons) && self.is_pressed(buttons)
+    }
+
+    /// Whether any of `buttons` is down in the current frame.
+    #[inline]
+    pub fn is_held(&self, buttons: Buttons) -> bool {
+        self.current.intersects(buttons)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec::Vec};
+
+    use super::*;
+    use crate::platform::fake::FakeInput;
+
+    #[test]
+    fn pressed_only_on_the_first_frame() {
+        let mut input = FakeInput::new([
+            Buttons::CROSS,
+            Buttons::CROSS,
+            Buttons::empty(),
+            Buttons::CROSS | Buttons::UP,
+        ]);
+        let mut handler = InputHandler::default();
+
+        handler.update(&mut input);
+        assert!(handler.is_pressed(Buttons::CROSS));
+        handler.update(&mut input);
+        assert!(!handler.is_pressed(Buttons::CROSS));
+        assert!(handler.is_held(Buttons::CROSS));
+        handler.update(&mut input);
+        assert!(!handler.is_held(Buttons::all()));
+        handler.update(&mut input);
+        assert!(handler.is_pressed(Buttons::UP));
+        assert!(handler.is_pressed(Buttons::CROSS | Buttons::CIRCLE));
+        assert!(!handler.is_pressed(Buttons::CIRCLE));
+    }
+
+    /// Feed `recording` to a handler, and collect the frames where `check` holds.
+    fn frames_where<F>(recording: &str, mut handler: InputHandler, check: F) -> Vec<usize>
+    where
+        F: Fn(&InputHandler) -> bool,
+    {
+        let mut input = FakeInput::recorded(recording);
+        let frames = recording
+            .split_whitespace()
+            .map(|frame| frame.split_once('*').map_or(1, |(_, n)| n.parse().unwrap()))
+            .sum();
+        (0..frames)
+            .filter(|_| {
+                handler.update(&mut input);
+                check(&handler)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reports_releases_once() {
+        let released = frames_where("cross*3 - - cross -", InputHandler::default(), |h| {
+            h.is_released(Buttons::CROSS)
+        });
+        assert_eq!(released, [3, 6]);
+    }
+
+    #[test]
+    fn repeats_held_buttons() {
+        let mut handler = InputHandler::default();
+        handler.set_repeat(Repeat {
+            delay: 5,
+            interval: 2,
+        });
+
+        let repeated = frames_where("down*10 - down", handler, |h| {
+            h.is_action_repeated(Action::Down)
+        });
+        // pressed, then after the delay every other frame
+        assert_eq!(repeated, [0, 5, 7, 9, 11]);
+    }
+
+    #[test]
+    fn converts_the_repeat_to_frames() {
+        assert_eq!(
+            Repeat::from_millis(500, 50),
+            Repeat {
+                delay: 30,
+                interval: 3
+            }
+        );
+        assert_eq!(Repeat::from_millis(0, 0).interval, 1);
+    }
+
+    #[test]
+    fn ignores_the_buttons_held_across_a_resync() {
+        let mut handler = InputHandler::default();
+        handler.resync(&mut FakeInput::new([Buttons::CROSS | Buttons::UP]));
+
+        // cross closed the dialog and is still held, up is released then pressed again
+        let mut input = FakeInput::recorded("cross+up cross cross+up - cross");
+        handler.update(&mut input);
+        assert!(!handler.is_pressed(Buttons::CROSS | Buttons::UP));
+        assert!(!handler.is_held(Buttons::CROSS));
+        handler.update(&mut input);
+        handler.update(&mut input);
+        assert!(handler.is_pressed(Buttons::UP));
+        assert!(!handler.is_held(Buttons::CROSS));
+        handler.update(&mut input);
+        handler.update(&mut input);
+        assert!(handler.is_pressed(Buttons::CROSS));
+    }
+
+    #[test]
+    fn steps_with_the_tilt() {
+        let mut handler = InputHandler::default();
+        handler.set_analog(Analog {
+            dead_zone: 0,
+            curve: analog::Curve::Linear,
+            speed: 30,
+            ..Analog::default()
+        });
+        let down = Stick { x: 128, y: 255 };
+        let up = Stick { x: 128, y: 0 };
+        let mut input = FakeInput::new([]).with_sticks(
+            core::iter::repeat_n(down, 60)
+                .chain([Stick::CENTER])
+                .chain(core::iter::repeat_n(up, 4)),
+        );
+
+        let mut steps = 0;
+        for _ in 0..60 {
+            handler.update(&mut input);
+            assert!((0..=1).contains(&handler.stick_steps()));
+            steps += handler.stick_steps();
+        }
+        // 30 steps per second at full tilt
+        assert_eq!(steps, 30);
+
+        handler.update(&mut input);
+        assert_eq!(handler.tilt(), (0, 0));
+        let steps: i32 = (0..4)
+            .map(|_| {
+                handler.update(&mut input);
+                handler.stick_steps()
+            })
+            .sum();
+        assert_eq!(steps, -2);
+    }
+
+    #[test]
+    fn chords_need_every_button() {
+        let chord = Buttons::LTRIGGER | Buttons::RTRIGGER;
+        let mut input = FakeInput::new([Buttons::LTRIGGER, chord, chord]);
+        let mut handler = InputHandler::default();
+
+        handler.update(&mut input);
+        assert!(!handler.is_chord_pressed(chord));
+        assert!(handler.is_chord_pressed(Buttons::LTRIGGER));
+        handler.update(&mut input);
+        assert!(handler.is_chord_pressed(chord));
+        handler.update(&mut input);
+        assert!(!handler.is_chord_pressed(chord));
+    }
+
+    #[test]
+    fn maps_the_actions() {
+        let mut input = FakeInput::new([Buttons::CIRCLE, Buttons::empty(), Buttons::CROSS]);
+        let mut handler = InputHandler::default();
+        handler.set_map(ButtonMap::CIRCLE_CONFIRMS);
+
+        handler.update(&mut input);
+        assert!(handler.is_action_pressed(Action::Confirm));
+        assert!(!handler.is_action_pressed(Action::Cancel));
+        handler.update(&mut input);
+        handler.update(&mut input);
+        assert!(handler.is_action_pressed(Action::Cancel));
+        assert_eq!(handler.label(Action::Confirm), "O");
+    }
+
+    #[test]
+    fn remapping_swaps_the_buttons_taken() {
+        let mut map = ButtonMap::default();
+        map.set(Action::Confirm, Buttons::CIRCLE);
+        assert_eq!(map, ButtonMap::CIRCLE_CONFIRMS);
+
+        map.set(Action::Menu, Buttons::SELECT);
+        assert_eq!(map.buttons(Action::Menu), Buttons::SELECT);
+        assert_eq!(map.buttons(Action::Discard), Buttons::START);
+        assert_eq!(map.buttons(Action::Up), Buttons::UP);
+    }
+
+    #[test]
+    fn names_the_actions() {
+        for action in Action::ALL {
+            assert_eq!(action.to_string().parse(), Ok(action));
+        }
+        assert_eq!("back".parse::<Action>(), Err(()));
+    }
+
+    #[test]
+    fn names_the_buttons() {
+        assert_eq!("cross".parse(), Ok(Buttons::CROSS));
+        assert_eq!(
+            "L + Triangle".parse(),
+            Ok(Buttons::LTRIGGER | Buttons::TRIANGLE)
+        );
+        assert_eq!("home".parse::<Buttons>(), Err(()));
+        assert_eq!("".parse::<Buttons>(), Err(()));
+
+        let buttons = Buttons::RTRIGGER | Buttons::SQUARE;
+        assert_eq!(buttons.to_string(), "r+square");
+        assert_eq!(buttons.to_string().parse(), Ok(buttons));
+        assert_eq!(buttons.label(), "R+[]");
+        assert_eq!(Buttons::CIRCLE.label(), "O");
+    }
+
+    #[test]
+    fn other_controls_are_ignored() {
+        // the Home button and the hold switch
+        let buttons = Buttons::from_bits_truncate(0x1_0000 | 0x2_0000 | 0x4000);
+        assert_eq!(buttons, Buttons::CROSS);
+        assert!(Buttons::all().contains(Buttons::SELECT | Buttons::SQUARE));
+    }
+}
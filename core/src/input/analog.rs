@@ -0,0 +1,286 @@
+use core::{fmt::Display, str::FromStr};
+
+/// The tilt of the stick on an axis pushed to its edge, in thousandths.
+pub const FULL_TILT: i32 = 1000;
+/// Smallest travel, on each side of the center, a calibration must have measured to be used.
+const MIN_TRAVEL: u8 = 32;
+
+/// A position of the analog stick, as sampled: from 0 to 255 on each axis, about 128 at rest.
+/// `y` grows downwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stick {
+    pub x: u8,
+    pub y: u8,
+}
+
+impl Stick {
+    /// The position of a stick at rest, in theory.
+    pub const CENTER: Self = Self { x: 128, y: 128 };
+}
+
+impl Default for Stick {
+    #[inline]
+    fn default() -> Self {
+        Self::CENTER
+    }
+}
+
+/// How the tilt grows with the travel of the stick past the dead zone. The steeper the curve, the
+/// finer the control near the center.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Curve {
+    Linear,
+    #[default]
+    Quadratic,
+    Cubic,
+}
+
+impl Curve {
+    /// Apply the curve to `travel`, from 0 to [`FULL_TILT`].
+    fn apply(self, travel: i32) -> i32 {
+        match self {
+            Curve::Linear => travel,
+            Curve::Quadratic => travel * travel / FULL_TILT,
+            Curve::Cubic => travel * travel / FULL_TILT * travel / FULL_TILT,
+        }
+    }
+}
+
+impl FromStr for Curve {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "linear" => Ok(Curve::Linear),
+            "quadratic" => Ok(Curve::Quadratic),
+            "cubic" => Ok(Curve::Cubic),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for Curve {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Curve::Linear => write!(f, "linear"),
+            Curve::Quadratic => write!(f, "quadratic"),
+            Curve::Cubic => write!(f, "cubic"),
+        }
+    }
+}
+
+/// The travel of the stick of a PSP, measured by a [`Calibrator`]. Worn sticks neither rest at
+/// the center nor reach the edges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Calibration {
+    /// The position at rest.
+    pub center: Stick,
+    /// The smallest position reached on each axis.
+    pub min: Stick,
+    /// The largest position reached on each axis.
+    pub max: Stick,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            center: Stick::CENTER,
+            min: Stick { x: 0, y: 0 },
+            max: Stick { x: 255, y: 255 },
+        }
+    }
+}
+
+/// How positions of the stick turn into a tilt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Analog {
+    pub calibration: Calibration,
+    /// The share, in percent, of the travel around the center that is ignored.
+    pub dead_zone: u8,
+    pub curve: Curve,
+    /// How many lines or items the stick moves through per second, at full tilt.
+    pub speed: u32,
+}
+
+impl Default for Analog {
+    fn default() -> Self {
+        Self {
+            calibration: Calibration::default(),
+            dead_zone: 20,
+            curve: Curve::default(),
+            speed: 15,
+        }
+    }
+}
+
+impl Analog {
+    /// The tilt of the stick at `stick` on each axis, from -[`FULL_TILT`] to [`FULL_TILT`], and 0
+    /// within the dead zone.
+    pub fn tilt(&self, stick: Stick) -> (i32, i32) {
+        let Calibration { center, min, max } = self.calibration;
+        (
+            self.axis(stick.x, center.x, min.x, max.x),
+            self.axis(stick.y, center.y, min.y, max.y),
+        )
+    }
+
+    fn axis(&self, position: u8, center: u8, min: u8, max: u8) -> i32 {
+        let (offset, travel) = if position >= center {
+            (
+                i32::from(position - center),
+                i32::from(max.saturating_sub(center)),
+            )
+        } else {
+            (
+                -i32::from(center - position),
+                i32::from(center.saturating_sub(min)),
+            )
+        };
+        let travel = (offset.abs() * FULL_TILT / travel.max(1)).min(FULL_TILT);
+
+        let dead_zone = i32::from(self.dead_zone.min(99)) * FULL_TILT / 100;
+        if travel <= dead_zone {
+            return 0;
+        }
+        let travel = (travel - dead_zone) * FULL_TILT / (FULL_TILT - dead_zone);
+        self.curve.apply(travel) * offset.signum()
+    }
+}
+
+/// Measures the [`Calibration`] of the stick: first at rest, then swept to its edges.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Calibrator {
+    rest_sum: (u32, u32),
+    rest_samples: u32,
+    /// The smallest and largest positions reached while sweeping.
+    range: Option<(Stick, Stick)>,
+}
+
+impl Calibrator {
+    /// Record a position of the stick left at rest.
+    pub fn sample_rest(&mut self, stick: Stick) {
+        self.rest_sum.0 += u32::from(stick.x);
+        self.rest_sum.1 += u32::from(stick.y);
+        self.rest_samples += 1;
+    }
+
+    /// Record a position of the stick swept around its edges.
+    pub fn sample_sweep(&mut self, stick: Stick) {
+        let (min, max) = self.range.get_or_insert((stick, stick));
+        min.x = min.x.min(stick.x);
+        min.y = min.y.min(stick.y);
+        max.x = max.x.max(stick.x);
+        max.y = max.y.max(stick.y);
+    }
+
+    /// The position at rest, averaged over the samples, if any.
+    pub fn center(&self) -> Option<Stick> {
+        let samples = self.rest_samples;
+        (samples > 0).then(|| Stick {
+            x: (self.rest_sum.0 / samples) as u8,
+            y: (self.rest_sum.1 / samples) as u8,
+        })
+    }
+
+    /// The calibration measured.
+    ///
+    /// # Returns
+    /// `None` until the stick has been sampled at rest, and swept far enough on each side of the
+    /// center.
+    pub fn calibration(&self) -> Option<Calibration> {
+        let center = self.center()?;
+        let (min, max) = self.range?;
+        let far_enough = |min: u8, center: u8, max: u8| {
+            center.saturating_sub(min) >= MIN_TRAVEL && max.saturating_sub(center) >= MIN_TRAVEL
+        };
+        (far_enough(min.x, center.x, max.x) && far_enough(min.y, center.y, max.y))
+            .then_some(Calibration { center, min, max })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stick(x: u8, y: u8) -> Stick {
+        Stick { x, y }
+    }
+
+    #[test]
+    fn ignores_the_dead_zone() {
+        let analog = Analog {
+            curve: Curve::Linear,
+            ..Analog::default()
+        };
+
+        assert_eq!(analog.tilt(Stick::CENTER), (0, 0));
+        // 20 of 127 is less than 20%
+        assert_eq!(analog.tilt(stick(148, 108)), (0, 0));
+        assert_eq!(analog.tilt(stick(255, 0)), (FULL_TILT, -FULL_TILT));
+        // halfway past the dead zone
+        let (x, _) = analog.tilt(stick(128 + 76, 128));
+        assert!((490..=510).contains(&x), "{}", x);
+    }
+
+    #[test]
+    fn curves_the_tilt() {
+        let tilt = |curve| {
+            let analog = Analog {
+                dead_zone: 0,
+                curve,
+                ..Analog::default()
+            };
+            analog.tilt(stick(128 + 64, 128)).0
+        };
+
+        assert_eq!(tilt(Curve::Linear), 503);
+        assert_eq!(tilt(Curve::Quadratic), 253);
+        assert_eq!(tilt(Curve::Cubic), 127);
+        assert_eq!("cubic".parse(), Ok(Curve::Cubic));
+        assert_eq!(Curve::Quadratic.to_string(), "quadratic");
+    }
+
+    #[test]
+    fn follows_the_calibration() {
+        let analog = Analog {
+            calibration: Calibration {
+                center: stick(140, 120),
+                min: stick(40, 20),
+                max: stick(200, 220),
+            },
+            dead_zone: 10,
+            curve: Curve::Linear,
+            ..Analog::default()
+        };
+
+        assert_eq!(analog.tilt(stick(140, 120)), (0, 0));
+        assert_eq!(analog.tilt(stick(200, 20)), (FULL_TILT, -FULL_TILT));
+        assert_eq!(analog.tilt(stick(255, 0)), (FULL_TILT, -FULL_TILT));
+    }
+
+    #[test]
+    fn measures_the_calibration() {
+        let mut calibrator = Calibrator::default();
+        assert_eq!(calibrator.calibration(), None);
+
+        calibrator.sample_rest(stick(130, 120));
+        calibrator.sample_rest(stick(132, 122));
+        assert_eq!(calibrator.center(), Some(stick(131, 121)));
+
+        // not swept far enough yet
+        calibrator.sample_sweep(stick(131, 121));
+        calibrator.sample_sweep(stick(200, 121));
+        assert_eq!(calibrator.calibration(), None);
+
+        calibrator.sample_sweep(stick(10, 5));
+        calibrator.sample_sweep(stick(131, 250));
+        assert_eq!(
+            calibrator.calibration(),
+            Some(Calibration {
+                center: stick(131, 121),
+                min: stick(10, 5),
+                max: stick(200, 250),
+            })
+        );
+    }
+}
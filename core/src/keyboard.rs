@@ -0,0 +1,392 @@
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::str::FromStr;
+
+use crate::{
+    fs,
+    input::{Action, Buttons, InputHandler},
+    keyboard::{
+        layout::{cell_from_tilt, LayerKind, Slot, CENTER_CELL},
+        predict::{
+            current_word, default_phrases, parse_phrases, Dictionary, DICTIONARY_FILE, PHRASES_FILE,
+        },
+    },
+    platform::FileSystem,
+};
+
+pub mod layout;
+pub mod predict;
+
+/// Maximum number of completions suggested at once.
+pub const MAX_SUGGESTIONS: usize = 4;
+
+/// The face buttons, typing the character of the selected cell in their position.
+const SLOTS: [(Buttons, Slot); 4] = [
+    (Buttons::TRIANGLE, Slot::Up),
+    (Buttons::SQUARE, Slot::Left),
+    (Buttons::CIRCLE, Slot::Right),
+    (Buttons::CROSS, Slot::Down),
+];
+
+/// The way the user types text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InputMethod {
+    /// The system OSK (On-Screen Keyboard).
+    #[default]
+    Osk,
+    /// The in-app [`Keyboard`], with word completion and frequent phrases.
+    Keyboard,
+}
+
+impl InputMethod {
+    /// Every input method, in the order they are offered.
+    pub const ALL: [InputMethod; 2] = [InputMethod::Osk, InputMethod::Keyboard];
+}
+
+impl FromStr for InputMethod {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "osk" => Ok(InputMethod::Osk),
+            "keyboard" => Ok(InputMethod::Keyboard),
+            _ => Err(()),
+        }
+    }
+}
+
+impl core::fmt::Display for InputMethod {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            InputMethod::Osk => write!(f, "osk"),
+            InputMethod::Keyboard => write!(f, "keyboard"),
+        }
+    }
+}
+
+/// Where the user is with the keyboard after the input of a frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyboardStatus {
+    /// Still typing.
+    Typing,
+    /// Done, with the text typed.
+    Done(String),
+    /// Cancelled.
+    Cancelled,
+}
+
+/// The state of the in-app keyboard, an alternative to the system OSK, updated once per frame
+/// with the input of the application.
+///
+/// Characters are arranged in a 3x3 grid of cells: the analog stick selects a cell, and the face
+/// buttons type one of its 4 characters. While typing, the keyboard suggests completions for
+/// the current word, taken from a dictionary on the Memory Stick; when no word is being typed,
+/// it suggests frequent phrases instead.
+///
+/// # Controls
+/// - Analog stick: select a cell
+/// - Triangle, Square, Circle, Cross: type the character of the selected cell in their position,
+///   whatever the actions they are mapped to
+/// - Layer (L by default): switch between lowercase, uppercase and symbols
+/// - Delete (Left by default): delete the last character
+/// - Space (Right by default): type a space
+/// - Up, Down: select a suggestion
+/// - Complete (R by default): insert the selected suggestion
+/// - Menu (Start by default): done
+/// - Discard (Select by default): cancel
+///
+/// Delete, Space, Up and Down repeat while held.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keyboard {
+    dictionary: Dictionary,
+    phrases: Vec<String>,
+    text: String,
+    max_length: usize,
+    layer: LayerKind,
+    cell: usize,
+    suggestion: usize,
+}
+
+impl Keyboard {
+    /// Create a keyboard accepting up to `max_length` characters, completing the words of
+    /// `dictionary` and suggesting `phrases`.
+    pub fn new(dictionary: Dictionary, phrases: Vec<String>, max_length: usize) -> Self {
+        Self {
+            dictionary,
+            phrases,
+            text: String::new(),
+            max_length,
+            layer: LayerKind::default(),
+            cell: CENTER_CELL,
+            suggestion: 0,
+        }
+    }
+
+    /// Create a keyboard accepting up to `max_length` characters, with the dictionary and the
+    /// frequent phrases of the [data directory](crate::fs::DATA_DIR) of `files`.
+    ///
+    /// If the dictionary is missing, no completions are suggested; if the phrases are missing,
+    /// a default set is used.
+    pub fn load<F: FileSystem>(files: &F, max_length: usize) -> Self {
+        let dictionary = files
+            .read_to_string(&fs::data_path(DICTIONARY_FILE))
+            .map(|content| Dictionary::parse(&content))
+            .unwrap_or_default();
+        let phrases = files
+            .read_to_string(&fs::data_path(PHRASES_FILE))
+            .map(|content| parse_phrases(&content))
+            .unwrap_or_else(|_| default_phrases());
+
+        Self::new(dictionary, phrases, max_length)
+    }
+
+    /// Start typing a new text. The layer and the learned words are kept.
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.suggestion = 0;
+    }
+
+    /// Handle the input of the current frame.
+    ///
+    /// # Returns
+    /// [`KeyboardStatus::Done`] with the text, which is taken out of the keyboard, once the user
+    /// is done.
+    pub fn update(&mut self, input: &InputHandler) -> KeyboardStatus {
+        self.cell = cell_from_tilt(input.tilt());
+
+        if input.is_action_pressed(Action::Menu) {
+            self.learn_current_word();
+            self.suggestion = 0;
+            return KeyboardStatus::Done(core::mem::take(&mut self.text));
+        }
+        if input.is_action_pressed(Action::Discard) {
+            return KeyboardStatus::Cancelled;
+        }
+
+        for (button, slot) in SLOTS {
+            if input.is_pressed(button) {
+                self.push(self.layer.char_at(self.cell, slot));
+            }
+        }
+
+        if input.is_action_pressed(Action::Layer) {
+            self.layer = self.layer.next();
+        }
+        if input.is_action_repeated(Action::Delete) {
+            self.text.pop();
+            self.suggestion = 0;
+        }
+        if input.is_action_repeated(Action::Space) {
+            self.learn_current_word();
+            self.push(' ');
+        }
+
+        let suggestions = self.suggestions();
+        if input.is_action_repeated(Action::Down) && !suggestions.is_empty() {
+            self.suggestion = (self.suggestion + 1) % suggestions.len();
+        }
+        if input.is_action_repeated(Action::Up) && !suggestions.is_empty() {
+            self.suggestion = (self.suggestion + suggestions.len() - 1) % suggestions.len();
+        }
+        if input.is_action_pressed(Action::Complete) {
+            if let Some(suggestion) = suggestions.get(self.suggestion) {
+                self.insert_suggestion(suggestion);
+            }
+        }
+
+        KeyboardStatus::Typing
+    }
+
+    /// The text typed so far.
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    #[inline]
+    pub fn layer(&self) -> LayerKind {
+        self.layer
+    }
+
+    /// The cell selected with the stick, see [`cell_from_tilt`].
+    #[inline]
+    pub fn cell(&self) -> usize {
+        self.cell
+    }
+
+    /// The index of the selected suggestion, among [`Self::suggestions`].
+    #[inline]
+    pub fn suggestion(&self) -> usize {
+        self.suggestion
+    }
+
+    /// The current suggestions: completions of the word being typed, or frequent phrases if
+    /// no word is being typed.
+    pub fn suggestions(&self) -> Vec<String> {
+        let word = current_word(&self.text);
+        if word.is_empty() {
+            self.phrases.clone()
+        } else {
+            self.dictionary.complete(word, MAX_SUGGESTIONS)
+        }
+    }
+
+    fn push(&mut self, c: char) {
+        if self.text.chars().count() < self.max_length {
+            self.text.push(c);
+            self.suggestion = 0;
+        }
+    }
+
+    /// Insert `suggestion`, replacing the word being typed if it completes it.
+    fn insert_suggestion(&mut self, suggestion: &str) {
+        let word_len = current_word(&self.text).len();
+        let is_completion = word_len > 0;
+        self.text.truncate(self.text.len() - word_len);
+
+        for c in suggestion.chars() {
+            self.push(c);
+        }
+        if is_completion {
+            self.dictionary.learn(suggestion);
+            self.push(' ');
+        }
+    }
+
+    fn learn_current_word(&mut self) {
+        let word = current_word(&self.text).to_string();
+        self.dictionary.learn(&word);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::ToOwned, vec};
+
+    use super::*;
+    use crate::{
+        input::{analog::Stick, Repeat},
+        platform::fake::{FakeInput, MemoryFs},
+    };
+
+    #[test]
+    fn names_the_input_methods() {
+        for method in InputMethod::ALL {
+            assert_eq!(method.to_string().parse(), Ok(method));
+        }
+        assert_eq!("danzeff".parse::<InputMethod>(), Err(()));
+    }
+
+    fn keyboard() -> Keyboard {
+        Keyboard::new(
+            Dictionary::parse("explain\t2\nexample\t2"),
+            vec!["Summarize: ".to_owned(), "Translate: ".to_owned()],
+            32,
+        )
+    }
+
+    /// Run `keyboard` with `input` until it is done, or the input runs out.
+    fn type_with(keyboard: &mut Keyboard, mut input: FakeInput) -> KeyboardStatus {
+        let mut handler = InputHandler::default();
+        handler.set_repeat(Repeat {
+            delay: 2,
+            interval: 1,
+        });
+        let mut status = KeyboardStatus::Typing;
+        for _ in 0..input.remaining() {
+            handler.update(&mut input);
+            status = keyboard.update(&handler);
+            if status != KeyboardStatus::Typing {
+                break;
+            }
+        }
+        status
+    }
+
+    #[test]
+    fn types_the_characters_of_the_selected_cell() {
+        let mut keyboard = keyboard();
+        // the center cell at rest, then the top left one
+        let input = FakeInput::recorded("triangle - cross - square")
+            .with_sticks([Stick::CENTER; 4].into_iter().chain([Stick { x: 0, y: 0 }]));
+        assert_eq!(type_with(&mut keyboard, input), KeyboardStatus::Typing);
+        assert_eq!(keyboard.text(), "eoc");
+        assert_eq!(keyboard.cell(), 0);
+
+        let input = FakeInput::recorded("l - circle - l - l - start");
+        assert_eq!(
+            type_with(&mut keyboard, input),
+            KeyboardStatus::Done("eocA".to_owned())
+        );
+        assert_eq!(keyboard.text(), "");
+        assert_eq!(keyboard.layer(), LayerKind::Lowercase);
+    }
+
+    #[test]
+    fn deletes_and_types_spaces_while_held() {
+        let mut keyboard = keyboard();
+        let input = FakeInput::recorded("triangle - right*4 - left");
+        type_with(&mut keyboard, input);
+        // pressed, then repeated after the delay of 2 frames
+        assert_eq!(keyboard.text(), "e  ");
+
+        let input = FakeInput::recorded("left*8");
+        type_with(&mut keyboard, input);
+        assert_eq!(keyboard.text(), "");
+    }
+
+    #[test]
+    fn stops_at_the_maximum_length() {
+        let mut keyboard = keyboard();
+        type_with(&mut keyboard, FakeInput::recorded("right*40"));
+        assert_eq!(keyboard.text().len(), 32);
+    }
+
+    #[test]
+    fn completes_the_word_being_typed() {
+        let mut keyboard = keyboard();
+        assert_eq!(keyboard.suggestions(), ["Summarize: ", "Translate: "]);
+        keyboard.text = "an ex".to_owned();
+        assert_eq!(keyboard.suggestions(), ["explain", "example"]);
+
+        type_with(&mut keyboard, FakeInput::recorded("down - r"));
+        assert_eq!(keyboard.text(), "an example ");
+        // the inserted word was learned, and comes first from now on
+        keyboard.text = "ex".to_owned();
+        assert_eq!(keyboard.suggestions(), ["example", "explain"]);
+
+        // with no word being typed, the phrase is added as is
+        keyboard.clear();
+        type_with(&mut keyboard, FakeInput::recorded("up - r"));
+        assert_eq!(keyboard.text(), "Translate: ");
+    }
+
+    #[test]
+    fn follows_the_button_map() {
+        let mut keyboard = keyboard();
+        let mut handler = InputHandler::default();
+        let mut map = *handler.map();
+        map.set(Action::Discard, Buttons::START);
+        handler.set_map(map);
+
+        let mut input = FakeInput::recorded("start");
+        handler.update(&mut input);
+        assert_eq!(keyboard.update(&handler), KeyboardStatus::Cancelled);
+    }
+
+    #[test]
+    fn loads_the_words_and_the_phrases() {
+        let files = MemoryFs::default()
+            .with_file(&fs::data_path(DICTIONARY_FILE), b"zebra\t3\nzero\t5")
+            .with_file(&fs::data_path(PHRASES_FILE), b"Hello ");
+        let mut keyboard = Keyboard::load(&files, 32);
+        assert_eq!(keyboard.suggestions(), ["Hello "]);
+        keyboard.text = "z".to_owned();
+        assert_eq!(keyboard.suggestions(), ["zero", "zebra"]);
+
+        let keyboard = Keyboard::load(&MemoryFs::default(), 32);
+        assert_eq!(keyboard.suggestions(), default_phrases());
+    }
+}
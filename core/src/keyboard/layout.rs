@@ -0,0 +1,155 @@
+/// Number of cells of a layer, arranged in a 3x3 grid.
+pub const CELLS: usize = 9;
+/// Index of the cell selected when the analog stick is at rest.
+pub const CENTER_CELL: usize = 4;
+
+/// The position of a character inside a cell, matching the face button that types it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slot {
+    /// Triangle
+    Up = 0,
+    /// Square
+    Left = 1,
+    /// Circle
+    Right = 2,
+    /// Cross
+    Down = 3,
+}
+
+/// A layer of the keyboard: 9 cells of 4 characters each, in [`Slot`] order.
+pub type Layer = [[char; 4]; CELLS];
+
+/// The most frequent letters sit in the center cell, which is selected with the stick at rest.
+const LOWERCASE: Layer = [
+    ['b', 'c', 'd', 'f'],
+    ['g', 'h', 'i', 'j'],
+    ['k', 'l', 'm', 'n'],
+    ['p', 'q', 'r', 's'],
+    ['e', 't', 'a', 'o'],
+    ['u', 'v', 'w', 'x'],
+    ['y', 'z', '.', ','],
+    ['?', '!', '\'', '-'],
+    [':', ';', '(', ')'],
+];
+
+const UPPERCASE: Layer = [
+    ['B', 'C', 'D', 'F'],
+    ['G', 'H', 'I', 'J'],
+    ['K', 'L', 'M', 'N'],
+    ['P', 'Q', 'R', 'S'],
+    ['E', 'T', 'A', 'O'],
+    ['U', 'V', 'W', 'X'],
+    ['Y', 'Z', '.', ','],
+    ['?', '!', '\'', '-'],
+    [':', ';', '(', ')'],
+];
+
+const SYMBOLS: Layer = [
+    ['1', '2', '3', '4'],
+    ['5', '6', '7', '8'],
+    ['9', '0', '+', '-'],
+    ['*', '/', '=', '%'],
+    ['@', '#', '&', '_'],
+    ['"', '\'', '`', '~'],
+    ['<', '>', '[', ']'],
+    ['{', '}', '|', '\\'],
+    ['$', '^', '.', ','],
+];
+
+/// The layers of the keyboard, cycled through with the L trigger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LayerKind {
+    #[default]
+    Lowercase,
+    Uppercase,
+    Symbols,
+}
+
+impl LayerKind {
+    /// The layer that follows this one.
+    pub fn next(self) -> Self {
+        match self {
+            LayerKind::Lowercase => LayerKind::Uppercase,
+            LayerKind::Uppercase => LayerKind::Symbols,
+            LayerKind::Symbols => LayerKind::Lowercase,
+        }
+    }
+
+    /// The characters of this layer.
+    pub fn layer(self) -> &'static Layer {
+        match self {
+            LayerKind::Lowercase => &LOWERCASE,
+            LayerKind::Uppercase => &UPPERCASE,
+            LayerKind::Symbols => &SYMBOLS,
+        }
+    }
+
+    /// A short name for the layer, to show on screen.
+    pub fn name(self) -> &'static str {
+        match self {
+            LayerKind::Lowercase => "abc",
+            LayerKind::Uppercase => "ABC",
+            LayerKind::Symbols => "123",
+        }
+    }
+
+    /// The character typed by pressing the button of `slot` with `cell` selected.
+    #[inline]
+    pub fn char_at(self, cell: usize, slot: Slot) -> char {
+        self.layer()[cell][slot as usize]
+    }
+}
+
+/// Get the cell selected by the tilt (`x`, `y`) of the stick, see
+/// [`InputHandler::tilt`](crate::input::InputHandler::tilt).
+///
+/// Cells are numbered left to right, top to bottom. The stick within the dead zone of its
+/// calibration, on an axis, selects the middle of it, so that the stick at rest selects
+/// [`CENTER_CELL`] even when it drifts.
+pub fn cell_from_tilt((x, y): (i32, i32)) -> usize {
+    fn axis(tilt: i32) -> usize {
+        (tilt.signum() + 1) as usize
+    }
+
+    axis(y) * 3 + axis(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::analog::{Analog, Calibration, Stick};
+
+    #[test]
+    fn selects_the_cell_the_stick_points_to() {
+        assert_eq!(cell_from_tilt((0, 0)), CENTER_CELL);
+        assert_eq!(cell_from_tilt((-1000, -1000)), 0);
+        assert_eq!(cell_from_tilt((400, 0)), 5);
+        assert_eq!(cell_from_tilt((0, 1000)), 7);
+        assert_eq!(cell_from_tilt((1000, 1000)), 8);
+    }
+
+    #[test]
+    fn drifting_sticks_rest_in_the_center() {
+        // a worn stick resting left of the center, and not reaching the right edge
+        let analog = Analog {
+            calibration: Calibration {
+                center: Stick { x: 90, y: 128 },
+                min: Stick { x: 10, y: 0 },
+                max: Stick { x: 200, y: 255 },
+            },
+            ..Analog::default()
+        };
+        assert_eq!(
+            cell_from_tilt(analog.tilt(Stick { x: 90, y: 128 })),
+            CENTER_CELL
+        );
+        assert_eq!(cell_from_tilt(analog.tilt(Stick { x: 200, y: 128 })), 5);
+        assert_eq!(cell_from_tilt(analog.tilt(Stick { x: 10, y: 128 })), 3);
+        // uncalibrated, its rest position would select the left column
+        let uncalibrated = Analog::default();
+        assert_eq!(
+            cell_from_tilt(uncalibrated.tilt(Stick { x: 90, y: 128 })),
+            3
+        );
+    }
+}
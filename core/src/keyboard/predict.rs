@@ -0,0 +1,211 @@
+use core::cmp::Reverse;
+
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// Name of the dictionary file, inside the [data directory](crate::fs::DATA_DIR).
+pub const DICTIONARY_FILE: &str = "dictionary.txt";
+/// Name of the frequent phrases file, inside the [data directory](crate::fs::DATA_DIR).
+pub const PHRASES_FILE: &str = "phrases.txt";
+
+/// Phrases offered when no phrases file is found.
+const DEFAULT_PHRASES: [&str; 5] = [
+    "Explain like I'm five: ",
+    "Translate to English: ",
+    "Summarize: ",
+    "Give me an example of ",
+    "What is the difference between ",
+];
+
+/// A word known to the [`Dictionary`], with how often it is used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    word: String,
+    frequency: u32,
+}
+
+/// A list of words used for word completion.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Dictionary {
+    entries: Vec<Entry>,
+}
+
+impl Dictionary {
+    /// Parse a dictionary.
+    ///
+    /// The dictionary has one word per line, optionally followed by a tab and its frequency.
+    /// Words without a frequency get a frequency of 1. Empty lines, and lines starting with `#`,
+    /// are ignored.
+    ///
+    /// # Example
+    /// ```
+    /// # use chat_gpsp_core::keyboard::predict::Dictionary;
+    /// let dictionary = Dictionary::parse("the\t1000\nexplain\t20\nexample");
+    /// ```
+    pub fn parse(content: &str) -> Self {
+        let mut entries: Vec<Entry> = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let mut parts = line.split('\t');
+                let word = parts.next().unwrap_or_default().trim().to_owned();
+                let frequency = parts
+                    .next()
+                    .and_then(|f| f.trim().parse().ok())
+                    .unwrap_or(1);
+                Entry { word, frequency }
+            })
+            .collect();
+        entries.sort_by_key(|e| Reverse(e.frequency));
+
+        Self { entries }
+    }
+
+    /// Get up to `max` words starting with `prefix`, most frequent first.
+    ///
+    /// Matching ignores ASCII case, and the completions keep the case of `prefix`. Words equal
+    /// to `prefix` are not returned. An empty `prefix` has no completions.
+    pub fn complete(&self, prefix: &str, max: usize) -> Vec<String> {
+        if prefix.is_empty() {
+            return Vec::new();
+        }
+
+        self.entries
+            .iter()
+            .filter(|e| e.word.len() > prefix.len())
+            .filter(|e| {
+                e.word
+                    .get(..prefix.len())
+                    .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+            })
+            .take(max)
+            .map(|e| {
+                let mut word = prefix.to_owned();
+                word.push_str(&e.word[prefix.len()..]);
+                word
+            })
+            .collect()
+    }
+
+    /// Record a use of `word`, so that it is suggested earlier from now on.
+    pub fn learn(&mut self, word: &str) {
+        if word.is_empty() {
+            return;
+        }
+
+        match self
+            .entries
+            .iter_mut()
+            .position(|e| e.word.eq_ignore_ascii_case(word))
+        {
+            Some(i) => self.entries[i].frequency += 1,
+            None => self.entries.push(Entry {
+                word: word.to_owned(),
+                frequency: 1,
+            }),
+        }
+        self.entries.sort_by_key(|e| Reverse(e.frequency));
+    }
+}
+
+/// Parse a list of frequent phrases, one per line.
+///
+/// Empty lines and lines starting with `#` are ignored. Trailing spaces are kept, so that a
+/// phrase can end with a space.
+pub fn parse_phrases(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|line| line.trim_end_matches('\r').to_string())
+        .collect()
+}
+
+/// The phrases offered when no phrases file is found.
+pub fn default_phrases() -> Vec<String> {
+    DEFAULT_PHRASES.iter().map(|p| p.to_string()).collect()
+}
+
+/// Get the word being typed at the end of `text`, i.e. the trailing run of alphanumeric
+/// characters and apostrophes.
+pub fn current_word(text: &str) -> &str {
+    let start = text
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '\'')
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(text.len());
+
+    &text[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn parses_words_by_frequency() {
+        let dictionary =
+            Dictionary::parse("# comment\nexample\n\nthe\t1000\nexplain\t 20\nbad\tx\n");
+        let words: Vec<(&str, u32)> = dictionary
+            .entries
+            .iter()
+            .map(|e| (e.word.as_str(), e.frequency))
+            .collect();
+        assert_eq!(
+            words,
+            [("the", 1000), ("explain", 20), ("example", 1), ("bad", 1)]
+        );
+    }
+
+    #[test]
+    fn completes_the_most_frequent_words() {
+        let dictionary = Dictionary::parse("ex\t50\nexample\t5\nexplain\t20\nexit\t10\nthe\t1000");
+        assert_eq!(
+            dictionary.complete("ex", 2),
+            vec!["explain".to_owned(), "exit".to_owned()]
+        );
+        // the case of the prefix is kept
+        assert_eq!(dictionary.complete("EXA", 4), vec!["EXAmple".to_owned()]);
+        assert!(dictionary.complete("", 4).is_empty());
+        assert!(dictionary.complete("zebra", 4).is_empty());
+    }
+
+    #[test]
+    fn learned_words_come_first() {
+        let mut dictionary = Dictionary::parse("explain\t2\nexample\t1");
+        dictionary.learn("Example");
+        dictionary.learn("example");
+        assert_eq!(dictionary.complete("ex", 1), vec!["example".to_owned()]);
+
+        dictionary.learn("exotic");
+        dictionary.learn("");
+        assert_eq!(dictionary.complete("exo", 4), vec!["exotic".to_owned()]);
+        assert_eq!(dictionary.entries.len(), 3);
+    }
+
+    #[test]
+    fn parses_phrases_keeping_trailing_spaces() {
+        assert_eq!(
+            parse_phrases("Summarize: \r\n# comment\n   \nTranslate: "),
+            vec!["Summarize: ".to_owned(), "Translate: ".to_owned()]
+        );
+        assert_eq!(default_phrases().len(), DEFAULT_PHRASES.len());
+    }
+
+    #[test]
+    fn finds_the_word_being_typed() {
+        assert_eq!(current_word("hello wor"), "wor");
+        assert_eq!(current_word("don't"), "don't");
+        assert_eq!(current_word("héllo"), "héllo");
+        assert_eq!(current_word("hello "), "");
+        assert_eq!(current_word("what?"), "");
+        assert_eq!(current_word(""), "");
+    }
+}
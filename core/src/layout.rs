@@ -0,0 +1,510 @@
+use alloc::{format, string::String, vec::Vec};
+use core::str::FromStr;
+
+use crate::{
+    input::{Action, InputHandler},
+    platform::Display,
+};
+
+/// Colors, in the `0xAABBGGRR` format used by the GU.
+pub mod color {
+    pub const BLACK: u32 = 0xff_00_00_00;
+    pub const WHITE: u32 = 0xff_ff_ff_ff;
+    pub const GRAY: u32 = 0xff_80_80_80;
+    pub const DARK_GRAY: u32 = 0xff_30_30_30;
+    pub const HIGHLIGHT: u32 = 0xff_c0_80_30;
+    pub const YELLOW: u32 = 0xff_00_e0_ff;
+}
+
+/// A set of colors the screens are drawn with, in place of those of [`color`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Theme {
+    /// The colors of [`color`], light text on black.
+    #[default]
+    Dark,
+    /// Dark text on white.
+    Light,
+    /// Shades of amber on black, like old terminals.
+    Amber,
+}
+
+impl Theme {
+    /// Every theme, in the order they are offered.
+    pub const ALL: [Theme; 3] = [Theme::Dark, Theme::Light, Theme::Amber];
+
+    /// The color of the theme standing for `color`, one of [`color`]. Other colors are kept.
+    pub fn color(self, color: u32) -> u32 {
+        match (self, color) {
+            (Theme::Dark, _) => color,
+            (Theme::Light, color::BLACK) => color::WHITE,
+            (Theme::Light, color::WHITE) => color::BLACK,
+            (Theme::Light, color::GRAY) => 0xff_60_60_60,
+            (Theme::Light, color::DARK_GRAY) => 0xff_d0_d0_d0,
+            (Theme::Light, color::HIGHLIGHT) => 0xff_f0_c8_a0,
+            (Theme::Light, color::YELLOW) => 0xff_00_60_c0,
+            (Theme::Amber, color::WHITE) => 0xff_00_b0_ff,
+            (Theme::Amber, color::GRAY) => 0xff_00_68_98,
+            (Theme::Amber, color::DARK_GRAY) => 0xff_00_20_30,
+            (Theme::Amber, color::HIGHLIGHT) => 0xff_00_48_70,
+            (Theme::Amber, color::YELLOW) => 0xff_a0_f0_ff,
+            (_, color) => color,
+        }
+    }
+}
+
+impl FromStr for Theme {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "dark" => Ok(Theme::Dark),
+            "light" => Ok(Theme::Light),
+            "amber" => Ok(Theme::Amber),
+            _ => Err(()),
+        }
+    }
+}
+
+impl core::fmt::Display for Theme {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Theme::Dark => write!(f, "dark"),
+            Theme::Light => write!(f, "light"),
+            Theme::Amber => write!(f, "amber"),
+        }
+    }
+}
+
+/// The size of the text of the conversations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextSize {
+    /// The size of the font, as everywhere else.
+    #[default]
+    Normal,
+    /// Twice the size of the font.
+    Large,
+}
+
+impl TextSize {
+    /// Every size, in the order they are offered.
+    pub const ALL: [TextSize; 2] = [TextSize::Normal, TextSize::Large];
+
+    /// How many times larger than the font the glyphs are.
+    #[inline]
+    pub fn scale(self) -> i16 {
+        match self {
+            TextSize::Normal => 1,
+            TextSize::Large => 2,
+        }
+    }
+
+    /// Number of characters that fit on a screen-wide line.
+    #[inline]
+    pub fn columns(self) -> usize {
+        SCREEN_COLUMNS / self.scale() as usize
+    }
+
+    /// Number of lines that fit between the title and the hint line.
+    #[inline]
+    pub fn lines(self) -> usize {
+        CONTENT_LINES / self.scale() as usize
+    }
+}
+
+impl FromStr for TextSize {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "normal" => Ok(TextSize::Normal),
+            "large" => Ok(TextSize::Large),
+            _ => Err(()),
+        }
+    }
+}
+
+impl core::fmt::Display for TextSize {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TextSize::Normal => write!(f, "normal"),
+            TextSize::Large => write!(f, "large"),
+        }
+    }
+}
+
+/// Width of the screen, in pixels.
+pub const SCREEN_WIDTH: i16 = 480;
+/// Height of the screen, in pixels.
+pub const SCREEN_HEIGHT: i16 = 272;
+/// Size, in pixels, of the side of a glyph.
+pub const GLYPH_SIZE: usize = 8;
+/// Height, in pixels, of a line of text, including the spacing between lines.
+pub const LINE_HEIGHT: i16 = 10;
+/// Number of characters that fit on a screen-wide line of text.
+pub const SCREEN_COLUMNS: usize = SCREEN_WIDTH as usize / GLYPH_SIZE;
+
+/// Y coordinate of the first line of content, below the title.
+pub const CONTENT_Y: i16 = 24;
+/// Y coordinate of the hint line, at the bottom of the screen.
+pub const HINT_Y: i16 = SCREEN_HEIGHT - 12;
+/// Number of lines of text that fit between the title and the hint line.
+pub const CONTENT_LINES: usize = ((HINT_Y - CONTENT_Y) / LINE_HEIGHT) as usize;
+
+/// The width, in pixels, `text` takes when drawn on a single line.
+#[inline]
+pub fn text_width(text: &str) -> usize {
+    text.chars().count() * GLYPH_SIZE
+}
+
+/// Split `text` into lines of at most `columns` characters.
+///
+/// Lines are broken at spaces when possible, and at newlines. Words longer than `columns` are
+/// split across lines.
+pub fn wrap_text(text: &str, columns: usize) -> Vec<String> {
+    let columns = columns.max(1);
+    let mut lines = Vec::new();
+
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        let mut line_len = 0;
+
+        for word in paragraph.split(' ') {
+            let word_len = word.chars().count();
+            if line_len > 0 && line_len + 1 + word_len > columns {
+                lines.push(core::mem::take(&mut line));
+                line_len = 0;
+            }
+            if line_len > 0 {
+                line.push(' ');
+                line_len += 1;
+            }
+
+            for c in word.chars() {
+                if line_len == columns {
+                    lines.push(core::mem::take(&mut line));
+                    line_len = 0;
+                }
+                line.push(c);
+                line_len += 1;
+            }
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Draw the title bar of a screen.
+pub fn draw_title<D: Display>(display: &mut D, title: &str) {
+    display.fill_rect(0, 0, SCREEN_WIDTH, 16, color::DARK_GRAY);
+    display.draw_text(8, 4, title, color::WHITE);
+}
+
+/// Draw the hint line at the bottom of a screen, usually describing the controls.
+pub fn draw_hint<D: Display>(display: &mut D, hint: &str) {
+    display.draw_text(8, HINT_Y, hint, color::GRAY);
+}
+
+/// The hint telling what `actions` do, with the buttons they are mapped to, e.g.
+/// `X: choose  O: back`.
+pub fn action_hint(input: &InputHandler, actions: &[(Action, &str)]) -> String {
+    let entries: Vec<String> = actions
+        .iter()
+        .map(|(action, what)| format!("{}: {}", input.label(*action), what))
+        .collect();
+    entries.join("  ")
+}
+
+/// The hint telling how to scroll, e.g. `Up/Down: scroll`.
+pub fn scroll_hint(input: &InputHandler) -> String {
+    format!(
+        "{}/{}: scroll",
+        input.label(Action::Up),
+        input.label(Action::Down)
+    )
+}
+
+/// Draw `lines` starting from line `first`, filling the content area of a screen.
+#[inline]
+pub fn draw_lines<D: Display, S: AsRef<str>>(display: &mut D, lines: &[S], first: usize) {
+    draw_lines_at_size(display, lines, first, TextSize::Normal);
+}
+
+/// Draw `lines` at `size` starting from line `first`, filling the content area of a screen.
+pub fn draw_lines_at_size<D: Display, S: AsRef<str>>(
+    display: &mut D,
+    lines: &[S],
+    first: usize,
+    size: TextSize,
+) {
+    let scale = size.scale();
+    for (i, line) in lines.iter().skip(first).take(size.lines()).enumerate() {
+        display.draw_text_scaled(
+            8,
+            CONTENT_Y + i as i16 * LINE_HEIGHT * scale,
+            line.as_ref(),
+            color::WHITE,
+            scale,
+        );
+    }
+}
+
+/// Scroll `first` line by line with Up and Down, repeating while they are held, or at the pace of
+/// the tilt of the stick, keeping a full page of `len` lines visible.
+#[inline]
+pub fn scroll_lines(input: &InputHandler, first: &mut usize, len: usize) {
+    scroll_lines_at_size(input, first, len, TextSize::Normal);
+}
+
+/// Scroll `first` like [`scroll_lines`], with pages of lines at `size`.
+pub fn scroll_lines_at_size(input: &InputHandler, first: &mut usize, len: usize, size: TextSize) {
+    let max_first = len.saturating_sub(size.lines());
+    if input.is_action_repeated(Action::Up) {
+        *first = first.saturating_sub(1);
+    }
+    if input.is_action_repeated(Action::Down) {
+        *first = (*first + 1).min(max_first);
+    }
+
+    let steps = input.stick_steps();
+    *first = first.saturating_add_signed(steps as isize).min(max_first);
+}
+
+/// A vertical list of items, one of which is selected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Menu<T> {
+    items: Vec<T>,
+    selected: usize,
+}
+
+impl<T> Menu<T> {
+    /// Create a menu with the first item selected.
+    pub fn new(items: Vec<T>) -> Self {
+        Self { items, selected: 0 }
+    }
+
+    #[inline]
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    /// The selected item, if the menu is not empty.
+    #[inline]
+    pub fn selected(&self) -> Option<&T> {
+        self.items.get(self.selected)
+    }
+
+    /// Select the first item matching `predicate`, if any. The selection is unchanged otherwise.
+    pub fn select_first<P>(&mut self, predicate: P)
+    where
+        P: Fn(&T) -> bool,
+    {
+        if let Some(index) = self.items.iter().position(predicate) {
+            self.selected = index;
+        }
+    }
+
+    /// Select the next item, wrapping around to the first one.
+    pub fn next(&mut self) {
+        if !self.items.is_empty() {
+            self.selected = (self.selected + 1) % self.items.len();
+        }
+    }
+
+    /// Select the previous item, wrapping around to the last one.
+    pub fn previous(&mut self) {
+        if !self.items.is_empty() {
+            self.selected = (self.selected + self.items.len() - 1) % self.items.len();
+        }
+    }
+
+    /// Move the selection with Up and Down, repeating while they are held, or at the pace of the
+    /// tilt of the stick. The stick stops at the first and last items instead of wrapping around.
+    pub fn update(&mut self, input: &InputHandler) {
+        if input.is_action_repeated(Action::Up) {
+            self.previous();
+        }
+        if input.is_action_repeated(Action::Down) {
+            self.next();
+        }
+
+        let steps = input.stick_steps();
+        if steps != 0 && !self.items.is_empty() {
+            self.selected = self
+                .selected
+                .saturating_add_signed(steps as isize)
+                .min(self.items.len() - 1);
+        }
+    }
+
+    /// Draw the menu in the content area, using `label` to get the text of each item.
+    pub fn render<D, F>(&self, display: &mut D, label: F)
+    where
+        D: Display,
+        F: Fn(&T) -> &str,
+    {
+        let first = (self.selected + 1).saturating_sub(CONTENT_LINES);
+        for (i, item) in self
+            .items
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(CONTENT_LINES)
+        {
+            let y = CONTENT_Y + (i - first) as i16 * LINE_HEIGHT;
+            if i == self.selected {
+                display.fill_rect(4, y - 1, SCREEN_WIDTH - 8, 10, color::HIGHLIGHT);
+            }
+            display.draw_text(8, y, label(item), color::WHITE);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use super::*;
+    use crate::{
+        input::{
+            analog::{Analog, Stick},
+            ButtonMap, Buttons,
+        },
+        platform::fake::{FakeDisplay, FakeInput},
+    };
+
+    #[test]
+    fn wrap_breaks_at_spaces_and_newlines() {
+        assert_eq!(
+            wrap_text("the quick brown fox\njumps", 10),
+            ["the quick", "brown fox", "jumps"]
+        );
+        assert_eq!(wrap_text("", 10), [""]);
+        assert_eq!(wrap_text("a\n\nb", 10), ["a", "", "b"]);
+    }
+
+    #[test]
+    fn wrap_splits_long_words() {
+        assert_eq!(wrap_text("abcdefghij xy", 4), ["abcd", "efgh", "ij", "xy"]);
+        // counted in characters, not bytes
+        assert_eq!(wrap_text("ééééé", 5), ["ééééé"]);
+        assert_eq!(wrap_text("abc", 0), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn lines_fill_the_content_area() {
+        let lines: Vec<String> = (0..40).map(|i| alloc::format!("line {}", i)).collect();
+        let mut display = FakeDisplay::default();
+
+        draw_lines(&mut display, &lines, 5);
+
+        let texts = display.texts();
+        assert_eq!(texts.len(), CONTENT_LINES);
+        assert_eq!(texts[0], "line 5");
+        assert_eq!(display.text_at(CONTENT_Y), Some("line 5"));
+    }
+
+    #[test]
+    fn large_lines_fill_half_as_many_lines() {
+        let lines: Vec<String> = (0..40).map(|i| alloc::format!("line {}", i)).collect();
+        let mut display = FakeDisplay::default();
+
+        draw_lines_at_size(&mut display, &lines, 0, TextSize::Large);
+
+        assert_eq!(display.texts().len(), CONTENT_LINES / 2);
+        assert_eq!(display.text_at(CONTENT_Y + 2 * LINE_HEIGHT), Some("line 1"));
+        assert_eq!(TextSize::Large.columns(), SCREEN_COLUMNS / 2);
+        assert_eq!("large".parse(), Ok(TextSize::Large));
+    }
+
+    #[test]
+    fn themes_recolor_the_palette() {
+        assert_eq!(Theme::Dark.color(color::WHITE), color::WHITE);
+        assert_eq!(Theme::Light.color(color::WHITE), color::BLACK);
+        assert_eq!(Theme::Light.color(color::BLACK), color::WHITE);
+        assert_eq!(Theme::Amber.color(0xff_12_34_56), 0xff_12_34_56);
+        assert_eq!(Theme::Amber.to_string(), "amber");
+    }
+
+    #[test]
+    fn scrolling_stops_at_the_last_page() {
+        let mut input = FakeInput::new([Buttons::DOWN, Buttons::empty(), Buttons::DOWN]);
+        let mut handler = InputHandler::default();
+        let len = CONTENT_LINES + 1;
+        let mut first = 0;
+
+        for _ in 0..3 {
+            handler.update(&mut input);
+            scroll_lines(&handler, &mut first, len);
+        }
+        assert_eq!(first, 1);
+    }
+
+    #[test]
+    fn the_stick_scrolls_and_stops_at_the_ends() {
+        let down = Stick { x: 128, y: 255 };
+        let mut input = FakeInput::new([]).with_sticks(core::iter::repeat_n(down, 120));
+        let mut handler = InputHandler::default();
+        let mut first = 0;
+        let mut menu = Menu::new(vec!["a", "b", "c"]);
+
+        for _ in 0..60 {
+            handler.update(&mut input);
+            scroll_lines(&handler, &mut first, CONTENT_LINES + 100);
+            menu.update(&handler);
+        }
+        assert_eq!(first, Analog::default().speed as usize);
+        assert_eq!(menu.selected(), Some(&"c"));
+
+        for _ in 0..60 {
+            handler.update(&mut input);
+            scroll_lines(&handler, &mut first, CONTENT_LINES + 20);
+        }
+        assert_eq!(first, 20);
+    }
+
+    #[test]
+    fn hints_name_the_mapped_buttons() {
+        let mut handler = InputHandler::default();
+        let actions = [(Action::Confirm, "choose"), (Action::Cancel, "back")];
+        assert_eq!(action_hint(&handler, &actions), "X: choose  O: back");
+        assert_eq!(scroll_hint(&handler), "Up/Down: scroll");
+
+        handler.set_map(ButtonMap::CIRCLE_CONFIRMS);
+        assert_eq!(action_hint(&handler, &actions), "O: choose  X: back");
+    }
+
+    #[test]
+    fn menu_selection_wraps_around() {
+        let mut menu = Menu::new(vec!["a", "b", "c"]);
+        menu.previous();
+        assert_eq!(menu.selected(), Some(&"c"));
+        menu.next();
+        assert_eq!(menu.selected(), Some(&"a"));
+        menu.select_first(|item| *item == "b");
+        assert_eq!(menu.selected(), Some(&"b"));
+        menu.select_first(|item| *item == "z");
+        assert_eq!(menu.selected(), Some(&"b"));
+
+        let mut empty: Menu<&str> = Menu::new(Vec::new());
+        empty.next();
+        assert_eq!(empty.selected(), None);
+    }
+
+    #[test]
+    fn menu_highlights_the_selection() {
+        let mut menu = Menu::new(vec!["first", "second"]);
+        menu.next();
+        let mut display = FakeDisplay::default();
+
+        menu.render(&mut display, |item| item);
+
+        assert_eq!(display.texts(), ["first", "second"]);
+        let highlight = display
+            .rects()
+            .iter()
+            .find(|rect| rect.color == color::HIGHLIGHT)
+            .expect("the selection should be highlighted");
+        assert_eq!(highlight.y, CONTENT_Y + LINE_HEIGHT - 1);
+    }
+}
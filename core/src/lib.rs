@@ -0,0 +1,24 @@
+//! The logic of ChatGPSP that does not depend on the PSP: the OpenAI client, the configuration
+//! and settings, the prompt templates, name resolution, text layout and input handling, the
+//! in-app keyboard, the screen stack, and the UTF-16 strings of the system dialogs.
+//!
+//! Everything touching the hardware goes through the traits of [`platform`]. The PSP binary
+//! implements them with `psp::sys`, and the tests with the in-memory fakes of
+//! [`platform::fake`], so that this crate builds and runs its tests on the host.
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+pub mod config;
+pub mod fs;
+pub mod input;
+pub mod keyboard;
+pub mod layout;
+pub mod net;
+pub mod openai;
+pub mod platform;
+pub mod power;
+pub mod settings;
+pub mod stack;
+pub mod templates;
+pub mod utf16;
@@ -0,0 +1,3 @@
+pub mod abort;
+pub mod dns;
+pub mod monitor;
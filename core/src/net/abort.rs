@@ -0,0 +1,236 @@
+use alloc::sync::Arc;
+use core::{
+    ptr,
+    sync::atomic::{AtomicBool, AtomicI32, AtomicPtr, Ordering},
+};
+
+/// The descriptor registered when no socket is open.
+const NO_SOCKET: i32 = -1;
+
+/// Lets another thread stop a request: it is flagged as aborted, and the socket it is blocked on
+/// is handed out to be shut down, so that the blocked call returns at once.
+///
+/// The request registers each socket it opens with [`Self::register`], and unregisters it as it
+/// closes it, so that the descriptor is not shut down once reused by another socket.
+#[derive(Debug)]
+pub struct Abort {
+    aborted: AtomicBool,
+    socket: AtomicI32,
+}
+
+impl Default for Abort {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Abort {
+    pub const fn new() -> Self {
+        Self {
+            aborted: AtomicBool::new(false),
+            socket: AtomicI32::new(NO_SOCKET),
+        }
+    }
+
+    /// Abort the request.
+    ///
+    /// # Returns
+    /// The descriptor of the socket to shut down, if the request has one open.
+    pub fn abort(&self) -> Option<i32> {
+        self.aborted.store(true, Ordering::SeqCst);
+        let socket = self.socket.swap(NO_SOCKET, Ordering::SeqCst);
+        (socket != NO_SOCKET).then_some(socket)
+    }
+
+    #[inline]
+    pub fn is_aborted(&self) -> bool {
+        self.aborted.load(Ordering::SeqCst)
+    }
+
+    /// Register `socket` as the socket the request is using, replacing the previous one.
+    ///
+    /// # Returns
+    /// `false` if the request was aborted, in which case the socket must not be used.
+    pub fn register(&self, socket: i32) -> bool {
+        self.socket.store(socket, Ordering::SeqCst);
+        // an abort between the store and the check may have taken the socket already
+        if self.aborted.load(Ordering::SeqCst) {
+            self.unregister(socket);
+            return false;
+        }
+        true
+    }
+
+    /// Forget `socket`, if it is still the registered one, before it is closed.
+    pub fn unregister(&self, socket: i32) {
+        let _ = self
+            .socket
+            .compare_exchange(socket, NO_SOCKET, Ordering::SeqCst, Ordering::SeqCst);
+    }
+}
+
+/// Holds the [`Abort`] of a request for a thread that cannot own it, e.g. the power callback
+/// aborting the request in flight as the PSP goes to sleep.
+///
+/// The slot owns a reference to the abort it holds, which whoever takes it out, with
+/// [`Self::take`] or [`Self::clear`], releases. Both are lock-free, so that a thread of higher
+/// priority never waits for one it preempted.
+#[derive(Debug)]
+pub struct AbortSlot {
+    abort: AtomicPtr<Abort>,
+}
+
+impl Default for AbortSlot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AbortSlot {
+    pub const fn new() -> Self {
+        Self {
+            abort: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    /// Hold `abort`, instead of the abort held until now.
+    pub fn set(&self, abort: &Arc<Abort>) {
+        let abort = Arc::into_raw(abort.clone()).cast_mut();
+        Self::release(self.abort.swap(abort, Ordering::AcqRel));
+    }
+
+    /// Take the abort held out of the slot, if any.
+    pub fn take(&self) -> Option<Arc<Abort>> {
+        let abort = self.abort.swap(ptr::null_mut(), Ordering::AcqRel);
+        // SAFETY: the pointer comes from `Arc::into_raw` in `set`, and the swap hands the
+        // reference it owns to this thread only
+        (!abort.is_null()).then(|| unsafe { Arc::from_raw(abort) })
+    }
+
+    /// Stop holding `abort`, if it is still the one held, e.g. once its request is over.
+    pub fn clear(&self, abort: &Arc<Abort>) {
+        let current = Arc::as_ptr(abort).cast_mut();
+        if self
+            .abort
+            .compare_exchange(
+                current,
+                ptr::null_mut(),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_ok()
+        {
+            Self::release(current);
+        }
+    }
+
+    fn release(abort: *mut Abort) {
+        if !abort.is_null() {
+            // SAFETY: as in `take`
+            drop(unsafe { Arc::from_raw(abort) });
+        }
+    }
+}
+
+impl Drop for AbortSlot {
+    fn drop(&mut self) {
+        self.take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hands_out_the_open_socket() {
+        let abort = Abort::new();
+        assert!(abort.register(3));
+        assert!(!abort.is_aborted());
+
+        assert_eq!(abort.abort(), Some(3));
+        assert!(abort.is_aborted());
+        // only once, and no new socket is accepted
+        assert_eq!(abort.abort(), None);
+        assert!(!abort.register(4));
+        assert_eq!(abort.abort(), None);
+    }
+
+    #[test]
+    fn closed_sockets_are_not_shut_down() {
+        let abort = Abort::new();
+        assert!(abort.register(3));
+        abort.unregister(3);
+        assert_eq!(abort.abort(), None);
+
+        // a socket replaced by another one is left alone when closed
+        let abort = Abort::new();
+        assert!(abort.register(3));
+        assert!(abort.register(4));
+        abort.unregister(3);
+        assert_eq!(abort.abort(), Some(4));
+    }
+
+    #[test]
+    fn slots_hand_out_their_abort_once() {
+        let slot = AbortSlot::new();
+        assert!(slot.take().is_none());
+
+        let abort = Arc::new(Abort::new());
+        slot.set(&abort);
+        assert_eq!(Arc::strong_count(&abort), 2);
+        let taken = slot.take().unwrap();
+        assert!(Arc::ptr_eq(&taken, &abort));
+        assert!(slot.take().is_none());
+        drop(taken);
+        assert_eq!(Arc::strong_count(&abort), 1);
+    }
+
+    #[test]
+    fn slots_release_what_they_hold() {
+        let slot = AbortSlot::new();
+        let (first, second) = (Arc::new(Abort::new()), Arc::new(Abort::new()));
+        slot.set(&first);
+        slot.set(&second);
+        assert_eq!(Arc::strong_count(&first), 1);
+
+        // only the abort held is cleared
+        slot.clear(&first);
+        assert_eq!(Arc::strong_count(&second), 2);
+        slot.clear(&second);
+        assert_eq!(Arc::strong_count(&second), 1);
+        assert!(slot.take().is_none());
+
+        slot.set(&first);
+        drop(slot);
+        assert_eq!(Arc::strong_count(&first), 1);
+    }
+
+    #[test]
+    fn slots_abort_from_another_thread() {
+        let slot = Arc::new(AbortSlot::new());
+        let abort = Arc::new(Abort::new());
+        assert!(abort.register(6));
+        slot.set(&abort);
+
+        let remote = slot.clone();
+        let socket = std::thread::spawn(move || remote.take().and_then(|abort| abort.abort()))
+            .join()
+            .unwrap();
+        assert_eq!(socket, Some(6));
+        assert!(abort.is_aborted());
+        slot.clear(&abort);
+        assert_eq!(Arc::strong_count(&abort), 1);
+    }
+
+    #[test]
+    fn aborts_from_another_thread() {
+        let abort = std::sync::Arc::new(Abort::new());
+        assert!(abort.register(5));
+
+        let remote = abort.clone();
+        let socket = std::thread::spawn(move || remote.abort()).join().unwrap();
+        assert_eq!(socket, Some(5));
+        assert!(abort.is_aborted());
+    }
+}
@@ -0,0 +1,205 @@
+use alloc::{format, string::String, vec::Vec};
+use core::{
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    time::Duration,
+};
+
+use crate::platform::Clock;
+
+/// How long a resolved address is kept, unless configured otherwise.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(300);
+
+/// An error that can occur while resolving a hostname.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolveError {
+    /// No DNS resolver could be created.
+    NoResolver(String),
+    /// The hostname could not be resolved.
+    Failed(String),
+}
+
+/// Something able to find the address of a hostname, e.g. a DNS resolver.
+pub trait ResolveHostname {
+    type Error: core::fmt::Debug;
+
+    /// The address of `hostname`, with a port of 0.
+    fn resolve_hostname(&mut self, hostname: &str) -> Result<SocketAddr, Self::Error>;
+}
+
+/// An address resolved earlier, valid until `expires`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CacheEntry {
+    hostname: String,
+    addr: SocketAddr,
+    expires: i64,
+}
+
+/// A resolver caching the addresses resolved by another one for a fixed time.
+///
+/// The time to live of the DNS records is not used: the resolvers of the PSP do not report it,
+/// so every address is kept for the same `ttl`, whatever its record says.
+///
+/// Hostnames in the static hosts override are never looked up.
+pub struct CachingResolver<R, C> {
+    inner: R,
+    clock: C,
+    ttl: Duration,
+    hosts: Vec<(String, SocketAddr)>,
+    cache: Vec<CacheEntry>,
+}
+
+impl<R, C: Default> CachingResolver<R, C> {
+    /// Cache the addresses resolved by `inner` for `ttl`.
+    pub fn new(inner: R, ttl: Duration) -> Self {
+        Self::with_clock(inner, ttl, C::default())
+    }
+}
+
+impl<R, C> CachingResolver<R, C> {
+    /// Cache the addresses resolved by `inner` for `ttl`, as measured by `clock`.
+    pub fn with_clock(inner: R, ttl: Duration, clock: C) -> Self {
+        Self {
+            inner,
+            clock,
+            ttl,
+            hosts: Vec::new(),
+            cache: Vec::new(),
+        }
+    }
+
+    /// Always resolve `hostname` to `addr`, without looking it up.
+    pub fn add_host(&mut self, hostname: &str, addr: Ipv4Addr) {
+        self.hosts.retain(|(host, _)| host != hostname);
+        self.hosts
+            .push((hostname.into(), SocketAddr::V4(SocketAddrV4::new(addr, 0))));
+    }
+
+    /// Forget the cached address of `hostname`, so that it is looked up again.
+    pub fn invalidate(&mut self, hostname: &str) {
+        self.cache.retain(|entry| entry.hostname != hostname);
+    }
+}
+
+impl<R, C> ResolveHostname for CachingResolver<R, C>
+where
+    R: ResolveHostname,
+    C: Clock,
+{
+    type Error = ResolveError;
+
+    fn resolve_hostname(&mut self, hostname: &str) -> Result<SocketAddr, ResolveError> {
+        if let Some((_, addr)) = self.hosts.iter().find(|(host, _)| host == hostname) {
+            return Ok(*addr);
+        }
+
+        let now = self.clock.now();
+        self.cache.retain(|entry| entry.expires > now);
+        if let Some(entry) = self.cache.iter().find(|entry| entry.hostname == hostname) {
+            return Ok(entry.addr);
+        }
+
+        let addr = self
+            .inner
+            .resolve_hostname(hostname)
+            .map_err(|e| ResolveError::Failed(format!("{:?}", e)))?;
+        self.cache.push(CacheEntry {
+            hostname: hostname.into(),
+            addr,
+            expires: now.saturating_add(self.ttl.as_micros() as i64),
+        });
+
+        Ok(addr)
+    }
+}
+
+/// A resolver that can forget what it cached, used by
+/// [`OpenAiContext`](crate::openai::OpenAiContext) to look the host up again after a connection
+/// failure.
+pub trait Resolve: ResolveHostname<Error = ResolveError> {
+    /// Forget the cached address of `hostname`, if any.
+    fn invalidate(&mut self, hostname: &str);
+}
+
+impl<R, C> Resolve for CachingResolver<R, C>
+where
+    R: ResolveHostname,
+    C: Clock,
+{
+    fn invalidate(&mut self, hostname: &str) {
+        CachingResolver::invalidate(self, hostname);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::platform::fake::FakeClock;
+
+    /// Resolves every hostname to the next of its addresses, counting the lookups.
+    struct Counting {
+        addrs: Vec<Ipv4Addr>,
+        lookups: usize,
+    }
+
+    impl ResolveHostname for Counting {
+        type Error = ();
+
+        fn resolve_hostname(&mut self, _hostname: &str) -> Result<SocketAddr, ()> {
+            let addr = *self.addrs.get(self.lookups).ok_or(())?;
+            self.lookups += 1;
+            Ok(SocketAddr::V4(SocketAddrV4::new(addr, 0)))
+        }
+    }
+
+    fn resolver(addrs: Vec<Ipv4Addr>, clock: FakeClock) -> CachingResolver<Counting, FakeClock> {
+        let inner = Counting { addrs, lookups: 0 };
+        CachingResolver::with_clock(inner, Duration::from_secs(10), clock)
+    }
+
+    #[test]
+    fn caches_until_the_ttl_expires() {
+        let clock = FakeClock::default();
+        let first = Ipv4Addr::new(10, 0, 0, 1);
+        let second = Ipv4Addr::new(10, 0, 0, 2);
+        let mut resolver = resolver(vec![first, second], clock.clone());
+
+        assert_eq!(resolver.resolve_hostname("a").unwrap().ip(), first);
+        clock.advance(Duration::from_secs(9));
+        assert_eq!(resolver.resolve_hostname("a").unwrap().ip(), first);
+        assert_eq!(resolver.inner.lookups, 1);
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(resolver.resolve_hostname("a").unwrap().ip(), second);
+        assert_eq!(resolver.inner.lookups, 2);
+    }
+
+    #[test]
+    fn invalidate_forces_a_lookup() {
+        let first = Ipv4Addr::new(10, 0, 0, 1);
+        let second = Ipv4Addr::new(10, 0, 0, 2);
+        let mut resolver = resolver(vec![first, second], FakeClock::default());
+
+        resolver.resolve_hostname("a").unwrap();
+        Resolve::invalidate(&mut resolver, "a");
+        assert_eq!(resolver.resolve_hostname("a").unwrap().ip(), second);
+    }
+
+    #[test]
+    fn static_hosts_are_never_looked_up() {
+        let fixed = Ipv4Addr::new(192, 168, 1, 1);
+        let mut resolver = resolver(Vec::new(), FakeClock::default());
+        resolver.add_host("api.example.com", Ipv4Addr::new(1, 2, 3, 4));
+        resolver.add_host("api.example.com", fixed);
+
+        assert_eq!(
+            resolver.resolve_hostname("api.example.com").unwrap().ip(),
+            fixed
+        );
+        assert!(matches!(
+            resolver.resolve_hostname("other.example.com"),
+            Err(ResolveError::Failed(_))
+        ));
+    }
+}
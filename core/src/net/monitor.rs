@@ -0,0 +1,156 @@
+/// Number of frames to wait for a reconnection attempt, before trying again (about 30 seconds).
+const RECONNECT_RETRY_FRAMES: u32 = 30 * 60;
+
+/// The state of the link to the access point, as seen by a [`ConnectivityMonitor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkStatus {
+    Connected,
+    /// The link dropped, and the monitor is reconnecting.
+    Reconnecting {
+        /// Number of reconnection attempts so far.
+        attempts: u32,
+        /// Number of frames since the last attempt.
+        frames: u32,
+    },
+}
+
+/// What the application should do after a [`ConnectivityMonitor::update`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MonitorEvent {
+    /// The link dropped: start reconnecting.
+    Lost,
+    /// The last reconnection attempt is taking too long: start another one.
+    Retry,
+    /// The link is back: resources tied to the previous connection should be recreated.
+    Restored,
+}
+
+/// Watches the state of the connection to an access point, telling when it drops and when it is
+/// back.
+///
+/// The monitor does not touch the network itself: it is fed the access point state every frame,
+/// and the application acts on the returned events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectivityMonitor {
+    access_point: i32,
+    status: LinkStatus,
+}
+
+impl ConnectivityMonitor {
+    /// Start monitoring a link to the access point with profile `access_point`, which is
+    /// connected.
+    pub fn new(access_point: i32) -> Self {
+        Self {
+            access_point,
+            status: LinkStatus::Connected,
+        }
+    }
+
+    /// The access point connection profile to reconnect to.
+    #[inline]
+    pub fn access_point(&self) -> i32 {
+        self.access_point
+    }
+
+    #[inline]
+    pub fn status(&self) -> LinkStatus {
+        self.status
+    }
+
+    #[inline]
+    pub fn is_connected(&self) -> bool {
+        self.status == LinkStatus::Connected
+    }
+
+    /// Tell the monitor the link was cut while the access point may still look connected, e.g.
+    /// by a suspend of the PSP.
+    ///
+    /// # Returns
+    /// [`MonitorEvent::Lost`] to act on, unless the monitor is already reconnecting.
+    pub fn interrupt(&mut self) -> Option<MonitorEvent> {
+        if !self.is_connected() {
+            return None;
+        }
+        self.status = LinkStatus::Reconnecting {
+            attempts: 1,
+            frames: 0,
+        };
+        Some(MonitorEvent::Lost)
+    }
+
+    /// Advance the monitor by a frame, given whether the access point is `connected`, i.e. an IP
+    /// address was obtained.
+    ///
+    /// # Returns
+    /// The event to act on, if any.
+    pub fn update(&mut self, connected: bool) -> Option<MonitorEvent> {
+        match &mut self.status {
+            LinkStatus::Connected if connected => None,
+            LinkStatus::Connected => {
+                self.status = LinkStatus::Reconnecting {
+                    attempts: 1,
+                    frames: 0,
+                };
+                Some(MonitorEvent::Lost)
+            }
+            LinkStatus::Reconnecting { .. } if connected => {
+                self.status = LinkStatus::Connected;
+                Some(MonitorEvent::Restored)
+            }
+            LinkStatus::Reconnecting { attempts, frames } => {
+                *frames += 1;
+                if *frames < RECONNECT_RETRY_FRAMES {
+                    return None;
+                }
+                *attempts += 1;
+                *frames = 0;
+                Some(MonitorEvent::Retry)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_loss_and_restoration_once() {
+        let mut monitor = ConnectivityMonitor::new(1);
+        assert_eq!(monitor.update(true), None);
+        assert_eq!(monitor.update(false), Some(MonitorEvent::Lost));
+        assert!(!monitor.is_connected());
+        assert_eq!(monitor.update(false), None);
+        assert_eq!(monitor.update(true), Some(MonitorEvent::Restored));
+        assert_eq!(monitor.update(true), None);
+        assert_eq!(monitor.status(), LinkStatus::Connected);
+        assert_eq!(monitor.access_point(), 1);
+    }
+
+    #[test]
+    fn interruptions_reconnect() {
+        let mut monitor = ConnectivityMonitor::new(1);
+        assert_eq!(monitor.interrupt(), Some(MonitorEvent::Lost));
+        assert_eq!(monitor.interrupt(), None);
+        assert_eq!(monitor.update(false), None);
+        assert_eq!(monitor.update(true), Some(MonitorEvent::Restored));
+        assert!(monitor.is_connected());
+    }
+
+    #[test]
+    fn retries_slow_reconnections() {
+        let mut monitor = ConnectivityMonitor::new(1);
+        monitor.update(false);
+        for _ in 1..RECONNECT_RETRY_FRAMES {
+            assert_eq!(monitor.update(false), None);
+        }
+        assert_eq!(monitor.update(false), Some(MonitorEvent::Retry));
+        assert_eq!(
+            monitor.status(),
+            LinkStatus::Reconnecting {
+                attempts: 2,
+                frames: 0
+            }
+        );
+    }
+}
@@ -0,0 +1,21 @@
+use core::time::Duration;
+
+pub const OPENAI_API_HOST: &str = "api.openai.com";
+pub const HTTPS_PORT: u16 = 443;
+pub const POST_PATH: &str = "/v1/chat/completions";
+pub const GPT3_MODEL: &str = "gpt-3.5-turbo";
+#[allow(unused)]
+pub const CHAT_MAX_LENGTH: u16 = 128;
+#[allow(unused)]
+pub const CHAT_MAX_LENGTH_USIZE: usize = CHAT_MAX_LENGTH as usize;
+#[allow(unused)]
+pub const MAX_MESSAGES_IN_A_REQUEST: usize = 10;
+/// Longest answer parsed, in bytes.
+pub const MAX_CONTENT_LENGTH: usize = 1024;
+/// Most tools the model may call at once.
+pub const MAX_TOOL_CALLS: usize = 4;
+/// Most requests made for a prompt, answering tool calls, before giving up.
+pub const MAX_TOOL_ROUNDS: usize = 5;
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+pub const DEFAULT_FIRST_BYTE_TIMEOUT: Duration = Duration::from_secs(45);
+pub const DEFAULT_TOTAL_TIMEOUT: Duration = Duration::from_secs(90);
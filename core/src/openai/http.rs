@@ -0,0 +1,563 @@
+use alloc::{
+    borrow::ToOwned,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::time::Duration;
+
+use lazy_static::lazy_static;
+use regex::{Regex, RegexBuilder};
+
+use crate::{
+    openai::{
+        constants::MAX_CONTENT_LENGTH,
+        proxy::{check_connect_response, is_response_complete, Proxy},
+        timeout::{Deadline, TimeoutKind},
+        types::{CompletionResponse, Reply, ToolCall},
+        OpenAiError,
+    },
+    platform::{Clock, Socket},
+};
+
+/// Maximum number of headers read from a response.
+const MAX_HEADERS: usize = 16;
+
+lazy_static! {
+    static ref BODY_REGEX: Regex = RegexBuilder::new(r"\{.*\}")
+        .dot_matches_new_line(true)
+        .build()
+        .expect("regex should be valid");
+}
+
+/// How the end of a response body is told.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Framing {
+    /// `Content-Length` bytes follow the headers.
+    Length(usize),
+    /// The body is sent in chunks, see [`decode_chunked`].
+    Chunked,
+    /// The body ends when the connection closes.
+    Close,
+}
+
+/// An HTTP/1.1 request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: &'static str,
+    /// The path, or the absolute URL when the request goes through a forwarding proxy.
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// A `POST` request to `path` on `host`, sending `body` as `content_type`.
+    pub fn post(host: &str, path: &str, content_type: &str, body: Vec<u8>) -> Self {
+        Self {
+            method: "POST",
+            path: path.to_owned(),
+            headers: alloc::vec![
+                ("Host".to_owned(), host.to_owned()),
+                ("Content-Type".to_owned(), content_type.to_owned()),
+            ],
+            body,
+        }
+    }
+
+    /// Add the header `name`.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// The value of the header `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The request as sent on the wire, with its `Content-Length`.
+    pub fn render(&self) -> Vec<u8> {
+        let mut head = format!("{} {} HTTP/1.1\r\n", self.method, self.path);
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str(&format!("Content-Length: {}\r\n\r\n", self.body.len()));
+
+        let mut rendered = head.into_bytes();
+        rendered.extend_from_slice(&self.body);
+        rendered
+    }
+}
+
+/// Write the whole of `data` to `socket`, reporting socket errors with `error`.
+pub(crate) fn write_all(
+    socket: &mut dyn Socket,
+    data: &[u8],
+    error: fn(String) -> OpenAiError,
+) -> Result<(), OpenAiError> {
+    let mut written = 0;
+    while written < data.len() {
+        match socket.write(&data[written..]).map_err(error)? {
+            0 => return Err(error("Connection closed while writing".to_owned())),
+            n => written += n,
+        }
+    }
+    socket.flush().map_err(error)
+}
+
+/// Read a whole HTTP response from `socket`, until it is complete or the connection closes.
+///
+/// The first read is bounded by the first-byte timeout, started at `step_started`, and the next
+/// ones by the time left before the total timeout. Read errors are reported with `error`.
+///
+/// # Returns
+/// The response, with carriage returns stripped.
+pub(crate) fn read_response<C: Clock>(
+    socket: &mut dyn Socket,
+    clock: &C,
+    deadline: &Deadline,
+    step_started: i64,
+    error: fn(String) -> OpenAiError,
+) -> Result<String, OpenAiError> {
+    let first_byte_timeout = deadline.step_timeout(TimeoutKind::FirstByte, step_started)?;
+    let explain = |kind, timeout, e: String| {
+        deadline.explain(kind, step_started, timeout, clock.now(), error(e))
+    };
+
+    let mut response = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let read = socket.read(&mut buf).map_err(|e| {
+            if response.is_empty() {
+                explain(TimeoutKind::FirstByte, first_byte_timeout, e)
+            } else {
+                explain(TimeoutKind::Total, Duration::MAX, e)
+            }
+        })?;
+        if read == 0 {
+            break;
+        }
+        response.extend_from_slice(&buf[..read]);
+        if is_response_complete(&response) {
+            break;
+        }
+
+        let remaining = deadline.step_timeout(TimeoutKind::Total, clock.now())?;
+        socket
+            .set_timeout(remaining)
+            .map_err(OpenAiError::TlsError)?;
+    }
+
+    let response = unframe(response)?;
+    Ok(String::from_utf8_lossy(&response).replace(['\r', '\0'], ""))
+}
+
+/// Split `response` after its headers, if they are complete.
+pub(crate) fn split_head(response: &[u8]) -> Option<(&[u8], &[u8])> {
+    let end = response.windows(4).position(|w| w == b"\r\n\r\n")?;
+    Some((&response[..end], &response[end + 4..]))
+}
+
+/// How the body following the headers `head` ends.
+pub(crate) fn framing(head: &[u8]) -> Framing {
+    let head = String::from_utf8_lossy(head);
+    let mut framing = Framing::Close;
+    for (name, value) in head.lines().filter_map(|line| line.split_once(':')) {
+        let name = name.trim();
+        if name.eq_ignore_ascii_case("transfer-encoding") {
+            // chunked is always the last encoding, and takes precedence over the length
+            let last = value.rsplit(',').next().unwrap_or_default();
+            if last.trim().eq_ignore_ascii_case("chunked") {
+                return Framing::Chunked;
+            }
+        } else if name.eq_ignore_ascii_case("content-length") {
+            if let Ok(length) = value.trim().parse() {
+                framing = Framing::Length(length);
+            }
+        }
+    }
+    framing
+}
+
+/// Decode a body sent with `Transfer-Encoding: chunked`.
+///
+/// # Returns
+/// The body, or `None` if it is incomplete or malformed.
+pub fn decode_chunked(mut body: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::new();
+    loop {
+        let line_end = body.windows(2).position(|w| w == b"\r\n")?;
+        let size_line = core::str::from_utf8(&body[..line_end]).ok()?;
+        // chunk extensions are ignored
+        let size = size_line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16).ok()?;
+        body = &body[line_end + 2..];
+
+        if size == 0 {
+            // the trailers, if any, end with an empty line
+            return (body == b"\r\n" || body.ends_with(b"\r\n\r\n")).then_some(decoded);
+        }
+        if body.get(size..size + 2)? != b"\r\n" {
+            return None;
+        }
+        decoded.extend_from_slice(&body[..size]);
+        body = &body[size + 2..];
+    }
+}
+
+/// Check that `response` was received whole, and decode its body if it was sent in chunks.
+///
+/// # Errors
+/// [`OpenAiError::PartialResponse`] if the connection closed before the end of the body.
+fn unframe(response: Vec<u8>) -> Result<Vec<u8>, OpenAiError> {
+    // incomplete headers are reported when parsing the status
+    let Some((head, body)) = split_head(&response) else {
+        return Ok(response);
+    };
+
+    match framing(head) {
+        Framing::Length(length) if body.len() < length => Err(OpenAiError::PartialResponse(
+            format!("Received {} of {} bytes", body.len(), length),
+        )),
+        Framing::Chunked => {
+            let decoded = decode_chunked(body).ok_or_else(|| {
+                OpenAiError::PartialResponse("Incomplete chunked body".to_owned())
+            })?;
+            let mut unframed = head.to_vec();
+            unframed.extend_from_slice(b"\r\n\r\n");
+            unframed.extend_from_slice(&decoded);
+            Ok(unframed)
+        }
+        _ => Ok(response),
+    }
+}
+
+/// Ask `proxy` to open a tunnel to `host`:`port` through `socket`.
+pub(crate) fn open_tunnel(
+    socket: &mut dyn Socket,
+    proxy: &Proxy,
+    host: &str,
+    port: u16,
+) -> Result<(), OpenAiError> {
+    let request = proxy.connect_request(host, port);
+    write_all(socket, request.as_bytes(), OpenAiError::Proxy)?;
+
+    // the proxy sends nothing past its response until the TLS handshake starts
+    let mut response = Vec::new();
+    let mut buf = [0u8; 512];
+    while !response.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = socket.read(&mut buf).map_err(OpenAiError::Proxy)?;
+        if read == 0 {
+            return Err(OpenAiError::Proxy(
+                "Connection closed by the proxy".to_owned(),
+            ));
+        }
+        response.extend_from_slice(&buf[..read]);
+    }
+
+    check_connect_response(&String::from_utf8_lossy(&response))
+}
+
+/// The status code of `response`.
+///
+/// # Errors
+/// [`OpenAiError::UnparsableResponseCode`] if the status line is malformed, or
+/// [`OpenAiError::PartialResponse`] if the headers are incomplete.
+pub fn parse_status(response: &str) -> Result<u16, OpenAiError> {
+    let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+    let mut parsed = httparse::Response::new(&mut headers);
+    let status = parsed
+        .parse(response.as_bytes())
+        .map_err(|e| OpenAiError::UnparsableResponseCode(e.to_string()))?;
+    if status.is_partial() {
+        return Err(OpenAiError::new_empty_partial_response());
+    }
+    parsed
+        .code
+        .ok_or_else(|| OpenAiError::UnparsableResponseCode(String::new()))
+}
+
+/// Extract the reply of the model from a chat completion response, with carriage returns already
+/// stripped: its answer, or the tools it calls.
+///
+/// # Errors
+/// - [`OpenAiError::ResponseCodeNotOk`] if the API answered with an error.
+/// - [`OpenAiError::UnparsableResponseBody`] if the body is not a completion.
+/// - As in [`parse_status`] if the response is malformed.
+pub fn parse_completion(response: &str) -> Result<Reply, OpenAiError> {
+    if parse_status(response)? != 200 {
+        return Err(OpenAiError::ResponseCodeNotOk);
+    }
+
+    let body = response
+        .split("\n\n")
+        .nth(1)
+        .ok_or(OpenAiError::UnparsableResponseBody(
+            "Body not found".to_owned(),
+        ))?;
+    let body = BODY_REGEX
+        .find(body)
+        .ok_or(OpenAiError::UnparsableResponseBody(
+            "Malformed body".to_owned(),
+        ))?
+        .as_str();
+
+    let mut unescaped = [0u8; MAX_CONTENT_LENGTH];
+    let completion_response: CompletionResponse =
+        serde_json_core::from_str_escaped(body, &mut unescaped)
+            .map_err(|e| OpenAiError::UnparsableResponseBody(e.to_string()))?
+            .0;
+    let message = &completion_response
+        .choices
+        .first()
+        .ok_or(OpenAiError::UnparsableResponseBody("No choices".to_owned()))?
+        .message;
+
+    if !message.tool_calls.is_empty() {
+        let tool_calls = message
+            .tool_calls
+            .iter()
+            .map(|call| ToolCall {
+                id: call.id.as_str().to_owned(),
+                name: call.function.name.as_str().to_owned(),
+                arguments: call.function.arguments.as_str().to_owned(),
+            })
+            .collect();
+        return Ok(Reply::ToolCalls(tool_calls));
+    }
+
+    let content = message.content.as_deref().unwrap_or_default();
+    Ok(Reply::Answer(content.trim().to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        openai::{proxy::ProxyMode, timeout::Timeouts},
+        platform::fake::{FakeClock, FakeSocket},
+    };
+
+    const COMPLETION: &str = r#"{"id":"1","object":"chat.completion","created":1,"model":"m","choices":[{"index":0,"message":{"role":"assistant","content":" Paris. "},"logprobs":null,"finish_reason":"stop"}],"usage":{"prompt_tokens":1,"completion_tokens":1,"total_tokens":2}}"#;
+
+    #[test]
+    fn renders_a_request() {
+        let request = Request::post("example.com", "/v1", "application/json", b"{}".to_vec())
+            .with_header("Authorization", "Bearer key");
+
+        assert_eq!(request.header("authorization"), Some("Bearer key"));
+        assert_eq!(
+            String::from_utf8(request.render()).unwrap(),
+            "POST /v1 HTTP/1.1\r\nHost: example.com\r\nContent-Type: application/json\r\nAuthorization: Bearer key\r\nContent-Length: 2\r\n\r\n{}"
+        );
+    }
+
+    #[test]
+    fn extracts_the_answer() {
+        let response = format!(
+            "HTTP/1.1 200 OK\nContent-Type: application/json\n\n{}",
+            COMPLETION
+        );
+        assert_eq!(
+            parse_completion(&response),
+            Ok(Reply::Answer("Paris.".to_owned()))
+        );
+
+        // escaped characters are decoded
+        let response = response.replace(" Paris. ", r#"Paris,\n\"la Ville Lumi\u00e8re\""#);
+        assert_eq!(
+            parse_completion(&response),
+            Ok(Reply::Answer("Paris,\n\"la Ville Lumière\"".to_owned()))
+        );
+    }
+
+    #[test]
+    fn extracts_the_tool_calls() {
+        let body = r#"{"id":"1","object":"chat.completion","created":1,"model":"m","choices":[{"index":0,"message":{"role":"assistant","content":null,"tool_calls":[{"id":"call_1","type":"function","function":{"name":"search_notes","arguments":"{\"query\":\"wifi\"}"}},{"id":"call_2","type":"function","function":{"name":"get_battery_level","arguments":"{}"}}]},"logprobs":null,"finish_reason":"tool_calls"}],"usage":{"prompt_tokens":1,"completion_tokens":1,"total_tokens":2}}"#;
+        let response = format!("HTTP/1.1 200 OK\n\n{}", body);
+
+        assert_eq!(
+            parse_completion(&response),
+            Ok(Reply::ToolCalls(alloc::vec![
+                ToolCall {
+                    id: "call_1".to_owned(),
+                    name: "search_notes".to_owned(),
+                    arguments: r#"{"query":"wifi"}"#.to_owned(),
+                },
+                ToolCall {
+                    id: "call_2".to_owned(),
+                    name: "get_battery_level".to_owned(),
+                    arguments: "{}".to_owned(),
+                },
+            ]))
+        );
+    }
+
+    #[test]
+    fn rejects_errors_and_garbage() {
+        let error = "HTTP/1.1 401 Unauthorized\n\n{\"error\": {}}";
+        assert_eq!(parse_completion(error), Err(OpenAiError::ResponseCodeNotOk));
+        assert!(matches!(
+            parse_completion("HTTP/1.1 200 OK\n\nnot json"),
+            Err(OpenAiError::UnparsableResponseBody(_))
+        ));
+        assert!(matches!(
+            parse_status("garbage\n\n"),
+            Err(OpenAiError::UnparsableResponseCode(_))
+        ));
+        assert_eq!(
+            parse_status("HTTP/1.1 200 OK\nContent-Length: 2\n"),
+            Err(OpenAiError::new_empty_partial_response())
+        );
+    }
+
+    #[test]
+    fn reads_until_content_length() {
+        let mut socket = FakeSocket::new([
+            &b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhel"[..],
+            b"lo",
+            b"never read",
+        ]);
+        let clock = FakeClock::default();
+        let deadline = Deadline::new(Timeouts::default(), clock.now());
+
+        let response = read_response(
+            &mut socket,
+            &clock,
+            &deadline,
+            clock.now(),
+            OpenAiError::TlsError,
+        );
+
+        assert_eq!(
+            response,
+            Ok("HTTP/1.1 200 OK\nContent-Length: 5\n\nhello".to_owned())
+        );
+        assert_eq!(socket.timeouts().len(), 1);
+    }
+
+    #[test]
+    fn reads_until_closed_without_content_length() {
+        let mut socket = FakeSocket::new([&b"HTTP/1.1 200 OK\r\n\r\nstreamed"[..], b" answer"]);
+        let clock = FakeClock::default();
+        let deadline = Deadline::new(Timeouts::default(), clock.now());
+
+        let response = read_response(
+            &mut socket,
+            &clock,
+            &deadline,
+            clock.now(),
+            OpenAiError::Relay,
+        );
+
+        assert_eq!(response.unwrap(), "HTTP/1.1 200 OK\n\nstreamed answer");
+    }
+
+    #[test]
+    fn decodes_chunked_bodies() {
+        assert_eq!(
+            decode_chunked(b"4\r\nWiki\r\n6;ext=1\r\npedia \r\n0\r\n\r\n"),
+            Some(b"Wikipedia ".to_vec())
+        );
+        assert_eq!(
+            decode_chunked(b"0\r\nExpires: never\r\n\r\n"),
+            Some(Vec::new())
+        );
+        // incomplete
+        assert_eq!(decode_chunked(b"4\r\nWiki\r\n0\r\n"), None);
+        assert_eq!(decode_chunked(b"4\r\nWi"), None);
+        // malformed
+        assert_eq!(decode_chunked(b"4\r\nWikipedia\r\n0\r\n\r\n"), None);
+        assert_eq!(decode_chunked(b"zz\r\n"), None);
+    }
+
+    #[test]
+    fn reads_chunked_responses() {
+        let mut socket = FakeSocket::new([
+            &b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nhel\r\n"[..],
+            b"2\r\nlo\r\n0\r\n\r\n",
+            b"never read",
+        ]);
+        let clock = FakeClock::default();
+        let deadline = Deadline::new(Timeouts::default(), clock.now());
+
+        let response = read_response(
+            &mut socket,
+            &clock,
+            &deadline,
+            clock.now(),
+            OpenAiError::TlsError,
+        );
+
+        assert_eq!(
+            response,
+            Ok("HTTP/1.1 200 OK\nTransfer-Encoding: chunked\n\nhello".to_owned())
+        );
+    }
+
+    #[test]
+    fn truncated_bodies_are_partial() {
+        let clock = FakeClock::default();
+        let deadline = Deadline::new(Timeouts::default(), clock.now());
+
+        let mut socket = FakeSocket::new([&b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhel"[..]]);
+        let response = read_response(&mut socket, &clock, &deadline, 0, OpenAiError::TlsError);
+        assert_eq!(
+            response,
+            Err(OpenAiError::PartialResponse(
+                "Received 3 of 5 bytes".to_owned()
+            ))
+        );
+
+        let mut socket = FakeSocket::new([
+            &b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nhel\r\n"[..],
+        ]);
+        let response = read_response(&mut socket, &clock, &deadline, 0, OpenAiError::TlsError);
+        assert!(matches!(response, Err(OpenAiError::PartialResponse(_))));
+    }
+
+    #[test]
+    fn slow_first_byte_is_a_timeout() {
+        let clock = FakeClock::default();
+        let deadline = Deadline::new(Timeouts::default(), clock.now());
+        let mut socket = FakeSocket::new([]);
+        socket.fail_when_drained("timed out");
+        // the read fails once the first-byte timeout is over
+        clock.advance(Timeouts::default().first_byte);
+
+        let response = read_response(&mut socket, &clock, &deadline, 0, OpenAiError::TlsError);
+
+        assert_eq!(response, Err(OpenAiError::Timeout(TimeoutKind::FirstByte)));
+    }
+
+    #[test]
+    fn tunnels_through_a_proxy() {
+        let proxy = Proxy {
+            host: "proxy".to_owned(),
+            port: 3128,
+            mode: ProxyMode::Connect,
+            credentials: Some("user:pass".to_owned()),
+        };
+        let mut socket = FakeSocket::new([&b"HTTP/1.1 200 Connection established\r\n\r\n"[..]]);
+
+        assert_eq!(
+            open_tunnel(&mut socket, &proxy, "api.example.com", 443),
+            Ok(())
+        );
+        assert_eq!(
+            String::from_utf8(socket.written()).unwrap(),
+            "CONNECT api.example.com:443 HTTP/1.1\r\nHost: api.example.com:443\r\nProxy-Authorization: Basic dXNlcjpwYXNz\r\n\r\n"
+        );
+
+        let mut socket =
+            FakeSocket::new([&b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n"[..]]);
+        assert!(matches!(
+            open_tunnel(&mut socket, &proxy, "api.example.com", 443),
+            Err(OpenAiError::Proxy(_))
+        ));
+    }
+}
@@ -0,0 +1,252 @@
+use alloc::{format, string::String, vec::Vec};
+use core::{fmt::Display, str::FromStr};
+
+use crate::openai::{
+    http::{decode_chunked, framing, split_head, Framing},
+    OpenAiError,
+};
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// How the OpenAI client goes through an HTTP proxy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProxyMode {
+    /// Open a tunnel with `CONNECT`, and talk TLS to the API host through it.
+    #[default]
+    Connect,
+    /// Send plain HTTP requests to the proxy, which forwards them to the API host over TLS. Meant
+    /// for a local relay, as the requests, including the API key, are not encrypted.
+    Forward,
+}
+
+impl FromStr for ProxyMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "connect" => Ok(ProxyMode::Connect),
+            "forward" => Ok(ProxyMode::Forward),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for ProxyMode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ProxyMode::Connect => write!(f, "connect"),
+            ProxyMode::Forward => write!(f, "forward"),
+        }
+    }
+}
+
+/// An HTTP proxy to reach the OpenAI API through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proxy {
+    /// Hostname or IPv4 address of the proxy.
+    pub host: String,
+    pub port: u16,
+    pub mode: ProxyMode,
+    /// Credentials for basic authentication, as `user:password`.
+    pub credentials: Option<String>,
+}
+
+impl Proxy {
+    /// The value of the `Proxy-Authorization` header, if the proxy needs credentials.
+    pub fn authorization(&self) -> Option<String> {
+        self.credentials
+            .as_ref()
+            .map(|credentials| format!("Basic {}", base64_encode(credentials.as_bytes())))
+    }
+
+    /// The request opening a tunnel to `host`:`port`.
+    pub fn connect_request(&self, host: &str, port: u16) -> String {
+        let mut request = format!(
+            "CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n",
+            host = host,
+            port = port
+        );
+        if let Some(authorization) = self.authorization() {
+            request.push_str(&format!("Proxy-Authorization: {}\r\n", authorization));
+        }
+        request.push_str("\r\n");
+        request
+    }
+}
+
+/// Check the response of the proxy to a `CONNECT` request.
+///
+/// # Errors
+/// [`OpenAiError::Proxy`] if the response is malformed, or the proxy refused to open the tunnel.
+pub fn check_connect_response(response: &str) -> Result<(), OpenAiError> {
+    let status_line = response.lines().next().unwrap_or_default();
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| OpenAiError::Proxy(format!("Malformed response: {}", status_line)))?;
+
+    if (200..300).contains(&status) {
+        Ok(())
+    } else {
+        Err(OpenAiError::Proxy(format!(
+            "Tunnel refused: {}",
+            status_line
+        )))
+    }
+}
+
+/// Whether `response` holds a whole HTTP response: the headers, and as much body as announced
+/// by `Content-Length`, or every chunk with `Transfer-Encoding: chunked`. Otherwise, the response
+/// ends when the connection closes.
+pub fn is_response_complete(response: &[u8]) -> bool {
+    let Some((head, body)) = split_head(response) else {
+        return false;
+    };
+
+    match framing(head) {
+        Framing::Length(length) => body.len() >= length,
+        Framing::Chunked => decode_chunked(body).is_some(),
+        Framing::Close => false,
+    }
+}
+
+/// Encode `data` in base64, with padding.
+pub fn base64_encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let triple = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (triple >> (18 - 6 * i)) & 0b11_1111;
+                encoded.push(BASE64_ALPHABET[index as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Decode the base64 `encoded`, with or without padding.
+///
+/// # Returns
+/// The bytes, or `None` if `encoded` is not base64.
+pub fn base64_decode(encoded: &str) -> Option<Vec<u8>> {
+    let encoded = encoded.trim_end_matches('=').as_bytes();
+    // a lone character past the last group does not make a byte
+    if encoded.len() % 4 == 1 {
+        return None;
+    }
+    let mut decoded = Vec::with_capacity(encoded.len() * 3 / 4);
+
+    for chunk in encoded.chunks(4) {
+        let mut triple = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let index = BASE64_ALPHABET.iter().position(|a| a == c)? as u32;
+            triple |= index << (18 - 6 * i);
+        }
+        let bytes = triple.to_be_bytes();
+        decoded.extend_from_slice(&bytes[1..chunk.len()]);
+    }
+
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::borrow::ToOwned;
+
+    use super::*;
+
+    #[test]
+    fn encodes_base64_with_padding() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"user:password"), "dXNlcjpwYXNzd29yZA==");
+    }
+
+    #[test]
+    fn decodes_base64() {
+        assert_eq!(base64_decode("").as_deref(), Some(&b""[..]));
+        assert_eq!(base64_decode("Zg==").as_deref(), Some(&b"f"[..]));
+        assert_eq!(base64_decode("Zm8").as_deref(), Some(&b"fo"[..]));
+        assert_eq!(base64_decode("Zm9v").as_deref(), Some(&b"foo"[..]));
+        let data: Vec<u8> = (0..=255).collect();
+        assert_eq!(base64_decode(&base64_encode(&data)), Some(data));
+
+        assert_eq!(base64_decode("Zm9v!"), None);
+        assert_eq!(base64_decode("Zm9vY"), None);
+    }
+
+    #[test]
+    fn parses_modes() {
+        for mode in [ProxyMode::Connect, ProxyMode::Forward] {
+            assert_eq!(mode.to_string().parse(), Ok(mode));
+        }
+        assert_eq!("socks".parse::<ProxyMode>(), Err(()));
+    }
+
+    #[test]
+    fn checks_the_tunnel_response() {
+        assert_eq!(
+            check_connect_response("HTTP/1.1 200 Connection established\r\n\r\n"),
+            Ok(())
+        );
+        assert!(matches!(
+            check_connect_response("HTTP/1.1 403 Forbidden\r\n\r\n"),
+            Err(OpenAiError::Proxy(message)) if message.contains("403")
+        ));
+        assert!(matches!(
+            check_connect_response("nonsense"),
+            Err(OpenAiError::Proxy(_))
+        ));
+    }
+
+    #[test]
+    fn knows_when_a_response_is_complete() {
+        assert!(!is_response_complete(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 4"
+        ));
+        assert!(!is_response_complete(
+            b"HTTP/1.1 200 OK\r\ncontent-length: 4\r\n\r\nabc"
+        ));
+        assert!(is_response_complete(
+            b"HTTP/1.1 200 OK\r\ncontent-length: 4\r\n\r\nabcd"
+        ));
+        assert!(!is_response_complete(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nabcd\r\n"
+        ));
+        assert!(is_response_complete(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nabcd\r\n0\r\n\r\n"
+        ));
+        // without a length, only the end of the connection tells
+        assert!(!is_response_complete(b"HTTP/1.1 200 OK\r\n\r\nabcd"));
+    }
+
+    #[test]
+    fn builds_the_connect_request() {
+        let proxy = Proxy {
+            host: "proxy".to_owned(),
+            port: 8080,
+            mode: ProxyMode::Connect,
+            credentials: None,
+        };
+        assert_eq!(proxy.authorization(), None);
+        assert_eq!(
+            proxy.connect_request("api.openai.com", 443),
+            "CONNECT api.openai.com:443 HTTP/1.1\r\nHost: api.openai.com:443\r\n\r\n"
+        );
+    }
+}
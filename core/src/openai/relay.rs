@@ -0,0 +1,147 @@
+use alloc::{borrow::ToOwned, format, string::String};
+
+use crate::openai::{types::ChatHistory, OpenAiError};
+
+/// Path the relay serves chat requests on.
+pub const RELAY_PATH: &str = "/chat";
+/// Header carrying the secret shared with the relay.
+pub const SECRET_HEADER: &str = "X-Relay-Secret";
+
+/// A relay on the local network, forwarding chat requests to the OpenAI API with its own key.
+///
+/// The relay speaks plain HTTP. A request body has one message per line, as the first letter of
+/// the role, a space, and the content with backslashes and newlines escaped:
+/// ```text
+/// u What is the capital of France?
+/// a Paris.
+/// u And of Italy?
+/// ```
+/// The answer is streamed back as plain text, and ends when the relay closes the connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relay {
+    /// Hostname or IPv4 address of the relay.
+    pub host: String,
+    pub port: u16,
+    /// The secret shared with the relay, sent with every request.
+    pub secret: String,
+}
+
+impl Relay {
+    /// The request asking the relay to continue `history`.
+    pub fn request(&self, history: &ChatHistory) -> String {
+        let body = encode_history(history);
+        format!(
+            "POST {path} HTTP/1.1\r\nHost: {host}:{port}\r\n{secret_header}: {secret}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {length}\r\nUser-Agent: Sony PSP\r\nConnection: close\r\n\r\n{body}",
+            path = RELAY_PATH,
+            host = self.host,
+            port = self.port,
+            secret_header = SECRET_HEADER,
+            secret = self.secret,
+            length = body.len(),
+            body = body,
+        )
+    }
+}
+
+/// Encode the messages of `history` in the format of the relay.
+pub fn encode_history(history: &ChatHistory) -> String {
+    let mut encoded = String::new();
+    // the relay does not support tools: their calls and results are left out
+    let messages = history
+        .messages()
+        .iter()
+        .filter(|message| message.tool_calls().is_empty() && message.tool_call_id().is_none());
+    for message in messages {
+        let role = message.role().chars().next().unwrap_or('u');
+        let content = message.content.replace('\\', "\\\\").replace('\n', "\\n");
+        encoded.push_str(&format!("{} {}\n", role, content));
+    }
+    encoded
+}
+
+/// Extract the answer from the response of the relay, with carriage returns already stripped.
+///
+/// # Errors
+/// [`OpenAiError::Relay`] with the message of the relay if it failed to answer, or
+/// [`OpenAiError::UnparsableResponseCode`] if the response is malformed.
+pub fn parse_response(response: &str) -> Result<String, OpenAiError> {
+    let (headers, body) = response.split_once("\n\n").unwrap_or((response, ""));
+    let status_line = headers.lines().next().unwrap_or_default();
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| OpenAiError::UnparsableResponseCode(status_line.to_owned()))?;
+
+    if status == 200 {
+        Ok(body.trim().to_owned())
+    } else {
+        Err(OpenAiError::Relay(format!("{}: {}", status, body.trim())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::openai::types::ToolCall;
+
+    fn history() -> ChatHistory {
+        let mut history = ChatHistory::new_gpt3(0.7);
+        history.add_user_message("two\nlines with a \\".to_owned());
+        history.add_assistant_message("ok".to_owned());
+        history
+    }
+
+    #[test]
+    fn encodes_one_message_per_line() {
+        assert_eq!(
+            encode_history(&history()),
+            "u two\\nlines with a \\\\\na ok\n"
+        );
+    }
+
+    #[test]
+    fn leaves_out_the_tool_calls() {
+        let mut history = history();
+        history.add_tool_calls(alloc::vec![ToolCall {
+            id: "call_1".to_owned(),
+            name: "get_current_time".to_owned(),
+            arguments: "{}".to_owned(),
+        }]);
+        history.add_tool_result("call_1".to_owned(), "12:00".to_owned());
+
+        assert_eq!(encode_history(&history), encode_history(&self::history()));
+    }
+
+    #[test]
+    fn sends_the_secret_and_length() {
+        let relay = Relay {
+            host: "relay.lan".to_owned(),
+            port: 9000,
+            secret: "s3cret".to_owned(),
+        };
+        let request = relay.request(&history());
+        let (head, body) = request.split_once("\r\n\r\n").unwrap();
+
+        assert!(head.starts_with("POST /chat HTTP/1.1\r\nHost: relay.lan:9000\r\n"));
+        assert!(head.contains("\r\nX-Relay-Secret: s3cret\r\n"));
+        assert!(head.contains(&format!("\r\nContent-Length: {}\r\n", body.len())));
+        assert_eq!(body, encode_history(&history()));
+    }
+
+    #[test]
+    fn parses_answers_and_errors() {
+        assert_eq!(
+            parse_response("HTTP/1.1 200 OK\nConnection: close\n\n Paris. \n"),
+            Ok("Paris.".to_owned())
+        );
+        assert_eq!(
+            parse_response("HTTP/1.1 401 Unauthorized\n\nwrong secret"),
+            Err(OpenAiError::Relay("401: wrong secret".to_owned()))
+        );
+        assert!(matches!(
+            parse_response("garbage"),
+            Err(OpenAiError::UnparsableResponseCode(_))
+        ));
+    }
+}
@@ -0,0 +1,183 @@
+use core::time::Duration;
+
+use crate::openai::{
+    constants::{DEFAULT_CONNECT_TIMEOUT, DEFAULT_FIRST_BYTE_TIMEOUT, DEFAULT_TOTAL_TIMEOUT},
+    OpenAiError,
+};
+
+/// The step of a request that took too long.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutKind {
+    /// Connecting to the server, including the TLS handshake.
+    Connect,
+    /// Waiting for the first byte of the response, once the request is sent.
+    FirstByte,
+    /// The whole request.
+    Total,
+}
+
+/// How long a request to the OpenAI API may take.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeouts {
+    /// Maximum time to connect to the server, including the TLS handshake.
+    pub connect: Duration,
+    /// Maximum time to wait for the first byte of the response, once the request is sent.
+    pub first_byte: Duration,
+    /// Maximum time for the whole request.
+    pub total: Duration,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            connect: DEFAULT_CONNECT_TIMEOUT,
+            first_byte: DEFAULT_FIRST_BYTE_TIMEOUT,
+            total: DEFAULT_TOTAL_TIMEOUT,
+        }
+    }
+}
+
+/// Tracks the time spent by a request against its [`Timeouts`].
+///
+/// Times are in microseconds, as returned by a [`Clock`](crate::platform::Clock), and passed in
+/// so that the deadline logic does not depend on the clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline {
+    timeouts: Timeouts,
+    started: i64,
+}
+
+impl Deadline {
+    /// Start tracking a request started at `now`.
+    pub fn new(timeouts: Timeouts, now: i64) -> Self {
+        Self {
+            timeouts,
+            started: now,
+        }
+    }
+
+    /// The time left before the total timeout, if any.
+    pub fn remaining(&self, now: i64) -> Option<Duration> {
+        let elapsed = Duration::from_micros(now.saturating_sub(self.started).max(0) as u64);
+        self.timeouts
+            .total
+            .checked_sub(elapsed)
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    /// How long a step of kind `kind` may block at `now`: the timeout of the step, capped by the
+    /// time left before the total timeout.
+    ///
+    /// # Errors
+    /// [`OpenAiError::Timeout`] with [`TimeoutKind::Total`] if the total timeout has expired.
+    pub fn step_timeout(&self, kind: TimeoutKind, now: i64) -> Result<Duration, OpenAiError> {
+        let remaining = self
+            .remaining(now)
+            .ok_or(OpenAiError::Timeout(TimeoutKind::Total))?;
+        let step = match kind {
+            TimeoutKind::Connect => self.timeouts.connect,
+            TimeoutKind::FirstByte => self.timeouts.first_byte,
+            TimeoutKind::Total => remaining,
+        };
+        Ok(step.min(remaining))
+    }
+
+    /// Explain why a step of kind `kind`, started at `step_started` with a timeout of
+    /// `step_timeout`, failed at `now`.
+    ///
+    /// A failing socket call does not tell whether it timed out, so a step failing after its
+    /// timeout is considered to have timed out.
+    ///
+    /// # Returns
+    /// - [`OpenAiError::Timeout`] if the step or the request took too long.
+    /// - `error` otherwise.
+    pub fn explain(
+        &self,
+        kind: TimeoutKind,
+        step_started: i64,
+        step_timeout: Duration,
+        now: i64,
+        error: OpenAiError,
+    ) -> OpenAiError {
+        if self.remaining(now).is_none() {
+            return OpenAiError::Timeout(TimeoutKind::Total);
+        }
+        let step_elapsed = Duration::from_micros(now.saturating_sub(step_started).max(0) as u64);
+        if step_elapsed >= step_timeout {
+            OpenAiError::Timeout(kind)
+        } else {
+            error
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: i64 = 1_000_000;
+
+    fn deadline() -> Deadline {
+        let timeouts = Timeouts {
+            connect: Duration::from_secs(5),
+            first_byte: Duration::from_secs(20),
+            total: Duration::from_secs(30),
+        };
+        Deadline::new(timeouts, 100 * SECOND)
+    }
+
+    #[test]
+    fn steps_are_capped_by_the_total_timeout() {
+        let deadline = deadline();
+        let now = 100 * SECOND;
+        assert_eq!(
+            deadline.step_timeout(TimeoutKind::Connect, now),
+            Ok(Duration::from_secs(5))
+        );
+        assert_eq!(
+            deadline.step_timeout(TimeoutKind::FirstByte, now + 15 * SECOND),
+            Ok(Duration::from_secs(15))
+        );
+        assert_eq!(
+            deadline.step_timeout(TimeoutKind::Connect, now + 30 * SECOND),
+            Err(OpenAiError::Timeout(TimeoutKind::Total))
+        );
+        assert_eq!(
+            deadline.remaining(now - SECOND),
+            Some(Duration::from_secs(30))
+        );
+    }
+
+    #[test]
+    fn failures_after_the_step_timeout_are_timeouts() {
+        let deadline = deadline();
+        let started = 101 * SECOND;
+        let step = Duration::from_secs(5);
+        let error = || OpenAiError::TlsError("reset".into());
+
+        let explained = deadline.explain(
+            TimeoutKind::Connect,
+            started,
+            step,
+            started + SECOND,
+            error(),
+        );
+        assert_eq!(explained, error());
+        let explained = deadline.explain(
+            TimeoutKind::Connect,
+            started,
+            step,
+            started + 5 * SECOND,
+            error(),
+        );
+        assert_eq!(explained, OpenAiError::Timeout(TimeoutKind::Connect));
+        let explained = deadline.explain(
+            TimeoutKind::Connect,
+            started,
+            step,
+            started + 40 * SECOND,
+            error(),
+        );
+        assert_eq!(explained, OpenAiError::Timeout(TimeoutKind::Total));
+    }
+}
@@ -0,0 +1,135 @@
+use alloc::vec::Vec;
+use core::{fmt::Display, str::FromStr};
+
+pub mod entropy;
+pub mod pin;
+pub mod session;
+
+/// The root certificates the API host is verified against, by default.
+///
+/// `api.openai.com` is served with a Google Trust Services chain, which some servers send
+/// cross-signed by GlobalSign.
+pub const BUNDLED_ROOTS: &[&[u8]] = &[
+    include_bytes!("certs/gts_root_r4.der"),
+    include_bytes!("certs/gts_root_r1.der"),
+    include_bytes!("certs/globalsign_root_ca.der"),
+];
+
+/// How the certificate of the API host is checked.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum TlsVerification {
+    /// Accept any certificate. Anyone on the network can read the requests, API key included.
+    None,
+    /// Require a chain up to one of the [bundled roots](BUNDLED_ROOTS).
+    #[default]
+    Roots,
+    /// Require a chain up to this DER certificate, and nothing else. It can be the self-signed
+    /// certificate of the server itself.
+    Pinned(Vec<u8>),
+    /// Require the certificate of the server to hold the public key with this SHA-256 digest,
+    /// whoever issued it. See [`TrustAnchor::PublicKey`].
+    PinnedKey([u8; 32]),
+}
+
+impl TlsVerification {
+    /// The anchors to try, in turn, to trust the certificate of the server. Empty if certificates
+    /// are not verified.
+    pub fn trust_anchors(&self) -> Vec<TrustAnchor<'_>> {
+        match self {
+            TlsVerification::None => Vec::new(),
+            TlsVerification::Roots => BUNDLED_ROOTS
+                .iter()
+                .map(|root| TrustAnchor::Certificate(root))
+                .collect(),
+            TlsVerification::Pinned(cert) => alloc::vec![TrustAnchor::Certificate(cert)],
+            TlsVerification::PinnedKey(digest) => alloc::vec![TrustAnchor::PublicKey(digest)],
+        }
+    }
+}
+
+/// What the certificate of a server is trusted through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrustAnchor<'a> {
+    /// A DER certificate the chain of the server must lead to.
+    Certificate(&'a [u8]),
+    /// The SHA-256 digest of the public key of the server certificate, as its DER
+    /// `SubjectPublicKeyInfo`. The rest of the chain is not checked, so the certificate may be
+    /// self-signed, renewed with the same key, or issued for another name.
+    PublicKey(&'a [u8; 32]),
+}
+
+impl AsRef<[u8]> for TrustAnchor<'_> {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            TrustAnchor::Certificate(cert) => cert,
+            TrustAnchor::PublicKey(digest) => &digest[..],
+        }
+    }
+}
+
+impl FromStr for TlsVerification {
+    type Err = ();
+
+    /// Parse `none` or `roots`. A pinned certificate is read from a file instead.
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "none" => Ok(TlsVerification::None),
+            "roots" => Ok(TlsVerification::Roots),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for TlsVerification {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TlsVerification::None => write!(f, "none"),
+            TlsVerification::Roots => write!(f, "roots"),
+            TlsVerification::Pinned(_) => write!(f, "pinned"),
+            TlsVerification::PinnedKey(_) => write!(f, "pinned key"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anchors_follow_the_verification() {
+        assert!(TlsVerification::None.trust_anchors().is_empty());
+        let roots = TlsVerification::Roots.trust_anchors();
+        assert_eq!(roots.len(), BUNDLED_ROOTS.len());
+        assert_eq!(roots[0], TrustAnchor::Certificate(BUNDLED_ROOTS[0]));
+        let pinned = TlsVerification::Pinned(alloc::vec![0x30, 0x82]);
+        assert_eq!(
+            pinned.trust_anchors(),
+            [TrustAnchor::Certificate(&[0x30, 0x82])]
+        );
+        let pinned_key = TlsVerification::PinnedKey([7; 32]);
+        assert_eq!(
+            pinned_key.trust_anchors(),
+            [TrustAnchor::PublicKey(&[7; 32])]
+        );
+    }
+
+    #[test]
+    fn bundled_roots_are_der_certificates() {
+        for root in BUNDLED_ROOTS {
+            // a SEQUENCE with a two-byte length
+            assert_eq!(&root[..2], &[0x30, 0x82]);
+            assert_eq!(
+                usize::from(root[2]) << 8 | usize::from(root[3]),
+                root.len() - 4
+            );
+        }
+    }
+
+    #[test]
+    fn parses_what_it_displays() {
+        for tls in [TlsVerification::None, TlsVerification::Roots] {
+            assert_eq!(tls.to_string().parse(), Ok(tls));
+        }
+        assert_eq!("pinned".parse::<TlsVerification>(), Err(()));
+    }
+}
@@ -0,0 +1,84 @@
+use sha2::{Digest, Sha256};
+use spin::Mutex;
+
+/// Gathers unpredictable samples into seeds for the random number generators of the TLS
+/// handshakes.
+///
+/// User programs of the PSP have no hardware entropy source: the samples are timings of the
+/// system and of the network, each worth a few bits. Every sample is hashed into the state of the
+/// pool, which every seed is derived from, so a seed is as unpredictable as all the samples
+/// gathered before it together.
+#[derive(Debug)]
+pub struct EntropyPool {
+    state: Mutex<[u8; 32]>,
+}
+
+impl Default for EntropyPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EntropyPool {
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new([0; 32]),
+        }
+    }
+
+    /// Mix `sample` into the pool.
+    pub fn add(&self, sample: &[u8]) {
+        let mut state = self.state.lock();
+        *state = Sha256::new()
+            .chain_update(*state)
+            .chain_update(b"sample")
+            .chain_update(sample)
+            .finalize()
+            .into();
+    }
+
+    /// A seed derived from every sample so far.
+    ///
+    /// The state moves on, so two seeds are never the same, even without new samples, and a seed
+    /// tells nothing about the next ones.
+    pub fn seed(&self) -> [u8; 32] {
+        let mut state = self.state.lock();
+        let seed = Sha256::new()
+            .chain_update(*state)
+            .chain_update(b"seed")
+            .finalize()
+            .into();
+        *state = Sha256::new()
+            .chain_update(*state)
+            .chain_update(b"next")
+            .finalize()
+            .into();
+        seed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeds_are_never_repeated() {
+        let pool = EntropyPool::new();
+        let first = pool.seed();
+        let second = pool.seed();
+        assert_ne!(first, second);
+        assert_ne!(first, [0; 32]);
+    }
+
+    #[test]
+    fn seeds_follow_the_samples() {
+        let (a, b, c) = (EntropyPool::new(), EntropyPool::new(), EntropyPool::new());
+        a.add(&1234u64.to_le_bytes());
+        b.add(&1234u64.to_le_bytes());
+        c.add(&1235u64.to_le_bytes());
+
+        let seed = a.seed();
+        assert_eq!(seed, b.seed());
+        assert_ne!(seed, c.seed());
+    }
+}
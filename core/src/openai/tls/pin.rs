@@ -0,0 +1,295 @@
+use alloc::vec::Vec;
+
+use embedded_tls::{
+    blocking::Aes128GcmSha256, CertificateEntryRef, CertificateRef, CertificateVerifyRef,
+    SignatureScheme, TlsError, TlsVerifier,
+};
+use sha2::{Digest, Sha256};
+
+/// DER tag of a SEQUENCE.
+const SEQUENCE: u8 = 0x30;
+/// DER tag of a BIT STRING.
+const BIT_STRING: u8 = 0x03;
+/// DER tag of the explicit version of a certificate, `[0]`.
+const VERSION: u8 = 0xa0;
+/// What the server signs in its CertificateVerify message, before the transcript hash.
+const SIGNATURE_CONTEXT: &[u8] = b"TLS 1.3, server CertificateVerify\x00";
+
+/// A DER element.
+struct Element<'a> {
+    tag: u8,
+    /// The whole element: tag, length and contents.
+    encoded: &'a [u8],
+    contents: &'a [u8],
+}
+
+/// Split the first DER element off `input`.
+///
+/// # Returns
+/// The element and what follows it, or `None` if `input` does not start with a whole element.
+fn element(input: &[u8]) -> Option<(Element<'_>, &[u8])> {
+    let (&tag, rest) = input.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (length, rest) = if first < 0x80 {
+        (usize::from(first), rest)
+    } else {
+        // the long form: the number of length bytes, then the length, big endian
+        let count = usize::from(first & 0x7f);
+        if count == 0 || count > size_of::<u32>() || rest.len() < count {
+            return None;
+        }
+        let (bytes, rest) = rest.split_at(count);
+        let length = bytes
+            .iter()
+            .fold(0usize, |length, byte| length << 8 | usize::from(*byte));
+        (length, rest)
+    };
+    if rest.len() < length {
+        return None;
+    }
+
+    let header = input.len() - rest.len();
+    let (contents, rest) = rest.split_at(length);
+    let element = Element {
+        tag,
+        encoded: &input[..header + length],
+        contents,
+    };
+    Some((element, rest))
+}
+
+/// Split the first element off `input`, if it is tagged `tag`.
+fn expect(input: &[u8], tag: u8) -> Option<(Element<'_>, &[u8])> {
+    element(input).filter(|(element, _)| element.tag == tag)
+}
+
+/// The `SubjectPublicKeyInfo` of the DER certificate `cert`, whole.
+pub fn subject_public_key_info(cert: &[u8]) -> Option<&[u8]> {
+    let (cert, _) = expect(cert, SEQUENCE)?;
+    let (tbs, _) = expect(cert.contents, SEQUENCE)?;
+
+    let mut fields = tbs.contents;
+    if fields.first() == Some(&VERSION) {
+        fields = element(fields)?.1;
+    }
+    // the serial number, the signature algorithm, the issuer, the validity and the subject
+    for _ in 0..5 {
+        fields = element(fields)?.1;
+    }
+
+    let (spki, _) = expect(fields, SEQUENCE)?;
+    Some(spki.encoded)
+}
+
+/// The key held in the DER `SubjectPublicKeyInfo` `spki`, without its algorithm.
+fn public_key(spki: &[u8]) -> Option<&[u8]> {
+    let (spki, _) = expect(spki, SEQUENCE)?;
+    let (_algorithm, rest) = expect(spki.contents, SEQUENCE)?;
+    let (key, _) = expect(rest, BIT_STRING)?;
+    // keys are whole bytes: no bits are unused
+    match key.contents.split_first()? {
+        (0, key) => Some(key),
+        _ => None,
+    }
+}
+
+/// Check that `signature` is the signature of `message` with `scheme` by the owner of the public
+/// key of `spki`.
+///
+/// # Errors
+/// [`TlsError::InvalidSignature`] if it is not, [`TlsError::InvalidSignatureScheme`] if the scheme
+/// is not supported, or [`TlsError::DecodeError`] if the key or the signature is malformed.
+fn verify_signature(
+    spki: &[u8],
+    scheme: SignatureScheme,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), TlsError> {
+    use rsa::{pkcs1::DecodeRsaPublicKey, pss, signature::Verifier, RsaPublicKey};
+
+    let key = public_key(spki).ok_or(TlsError::DecodeError)?;
+    let verified = match scheme {
+        SignatureScheme::EcdsaSecp256r1Sha256 => {
+            use p256::ecdsa::{Signature, VerifyingKey};
+            let key = VerifyingKey::from_sec1_bytes(key).map_err(|_| TlsError::DecodeError)?;
+            let signature = Signature::from_der(signature).map_err(|_| TlsError::DecodeError)?;
+            key.verify(message, &signature).is_ok()
+        }
+        SignatureScheme::EcdsaSecp384r1Sha384 => {
+            use p384::ecdsa::{Signature, VerifyingKey};
+            let key = VerifyingKey::from_sec1_bytes(key).map_err(|_| TlsError::DecodeError)?;
+            let signature = Signature::from_der(signature).map_err(|_| TlsError::DecodeError)?;
+            key.verify(message, &signature).is_ok()
+        }
+        SignatureScheme::Ed25519 => {
+            use ed25519_dalek::{Signature, VerifyingKey};
+            let key = key.try_into().map_err(|_| TlsError::DecodeError)?;
+            let key = VerifyingKey::from_bytes(key).map_err(|_| TlsError::DecodeError)?;
+            let signature = Signature::try_from(signature).map_err(|_| TlsError::DecodeError)?;
+            key.verify(message, &signature).is_ok()
+        }
+        SignatureScheme::RsaPssRsaeSha256
+        | SignatureScheme::RsaPssRsaeSha384
+        | SignatureScheme::RsaPssRsaeSha512 => {
+            let key = RsaPublicKey::from_pkcs1_der(key).map_err(|_| TlsError::DecodeError)?;
+            let signature =
+                pss::Signature::try_from(signature).map_err(|_| TlsError::DecodeError)?;
+            match scheme {
+                SignatureScheme::RsaPssRsaeSha256 => pss::VerifyingKey::<Sha256>::new(key)
+                    .verify(message, &signature)
+                    .is_ok(),
+                SignatureScheme::RsaPssRsaeSha384 => pss::VerifyingKey::<sha2::Sha384>::new(key)
+                    .verify(message, &signature)
+                    .is_ok(),
+                _ => pss::VerifyingKey::<sha2::Sha512>::new(key)
+                    .verify(message, &signature)
+                    .is_ok(),
+            }
+        }
+        _ => return Err(TlsError::InvalidSignatureScheme),
+    };
+
+    if !verified {
+        return Err(TlsError::InvalidSignature);
+    }
+    Ok(())
+}
+
+/// A verifier trusting the server whose certificate holds the pinned public key, see
+/// [`TrustAnchor::PublicKey`](super::TrustAnchor::PublicKey).
+///
+/// The server still has to prove it owns the key, by signing the handshake with it.
+pub struct KeyPinVerifier<'a> {
+    /// The SHA-256 digest of the `SubjectPublicKeyInfo` expected.
+    pin: &'a [u8; 32],
+    /// The `SubjectPublicKeyInfo` of the server once it matched the pin, with the handshake
+    /// transcript up to its certificate.
+    key: Option<(Vec<u8>, Sha256)>,
+}
+
+impl<'a> KeyPinVerifier<'a> {
+    pub fn new(pin: &'a [u8; 32]) -> Self {
+        Self { pin, key: None }
+    }
+}
+
+impl TlsVerifier<Aes128GcmSha256> for KeyPinVerifier<'_> {
+    fn set_hostname_verification(&mut self, _hostname: &str) -> Result<(), TlsError> {
+        // the key identifies the server, whatever names its certificate has
+        Ok(())
+    }
+
+    fn verify_certificate(
+        &mut self,
+        transcript: &Sha256,
+        cert: CertificateRef,
+    ) -> Result<(), TlsError> {
+        let Some(CertificateEntryRef::X509(leaf)) = cert.entries.first() else {
+            return Err(TlsError::InvalidCertificate);
+        };
+        let spki = subject_public_key_info(leaf).ok_or(TlsError::DecodeError)?;
+        if Sha256::digest(spki).as_slice() != self.pin {
+            return Err(TlsError::InvalidCertificate);
+        }
+
+        self.key = Some((spki.to_vec(), transcript.clone()));
+        Ok(())
+    }
+
+    fn verify_signature(&mut self, verify: CertificateVerifyRef) -> Result<(), TlsError> {
+        let (spki, transcript) = self.key.take().ok_or(TlsError::InvalidCertificate)?;
+
+        // 64 spaces, the context string, then the transcript hash, as in RFC 8446 4.4.3
+        let mut message = Vec::with_capacity(64 + SIGNATURE_CONTEXT.len() + 32);
+        message.resize(64, b' ');
+        message.extend_from_slice(SIGNATURE_CONTEXT);
+        message.extend_from_slice(&transcript.finalize());
+
+        verify_signature(&spki, verify.signature_scheme, &message, verify.signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::openai::tls::BUNDLED_ROOTS;
+
+    #[test]
+    fn reads_lengths_of_every_form() {
+        let (short, rest) = element(&[0x04, 0x02, 0xaa, 0xbb, 0xcc]).unwrap();
+        assert_eq!(
+            (short.tag, short.contents, rest),
+            (0x04, &[0xaa, 0xbb][..], &[0xcc][..])
+        );
+
+        let mut long = vec![0x04, 0x82, 0x01, 0x00];
+        long.resize(4 + 0x100, 0xaa);
+        let (long, rest) = element(&long).unwrap();
+        assert_eq!((long.contents.len(), long.encoded.len()), (0x100, 0x104));
+        assert!(rest.is_empty());
+
+        // cut short, or longer than the input
+        assert!(element(&[0x04]).is_none());
+        assert!(element(&[0x04, 0x03, 0xaa]).is_none());
+        assert!(element(&[0x04, 0x82, 0x01]).is_none());
+        assert!(element(&[0x04, 0x80]).is_none());
+    }
+
+    #[test]
+    fn finds_the_key_of_certificates() {
+        for root in BUNDLED_ROOTS {
+            let spki = subject_public_key_info(root).unwrap();
+            assert_eq!(spki[0], SEQUENCE);
+            assert!(public_key(spki).is_some_and(|key| !key.is_empty()));
+        }
+        assert!(subject_public_key_info(&[0x30, 0x03, 0x30, 0x01, 0x00]).is_none());
+        assert!(subject_public_key_info(&[]).is_none());
+    }
+
+    #[test]
+    fn only_the_pinned_key_is_trusted() {
+        let root = BUNDLED_ROOTS[0];
+        let pin: [u8; 32] = Sha256::digest(subject_public_key_info(root).unwrap()).into();
+        let mut cert = CertificateRef::with_context(&[]);
+        cert.add(CertificateEntryRef::X509(root)).unwrap();
+
+        let mut verifier = KeyPinVerifier::new(&pin);
+        assert!(verifier.verify_certificate(&Sha256::new(), cert).is_ok());
+
+        let other = [0; 32];
+        let mut cert = CertificateRef::with_context(&[]);
+        cert.add(CertificateEntryRef::X509(root)).unwrap();
+        let mut verifier = KeyPinVerifier::new(&other);
+        assert!(matches!(
+            verifier.verify_certificate(&Sha256::new(), cert),
+            Err(TlsError::InvalidCertificate)
+        ));
+    }
+
+    #[test]
+    fn wrong_signatures_are_rejected() {
+        let spki = subject_public_key_info(BUNDLED_ROOTS[0]).unwrap();
+        // GTS Root R4 holds a P-384 key: a signature of another message does not match
+        let signature = [0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x01];
+        assert!(matches!(
+            verify_signature(
+                spki,
+                SignatureScheme::EcdsaSecp384r1Sha384,
+                b"message",
+                &signature
+            ),
+            Err(TlsError::InvalidSignature)
+        ));
+        assert!(matches!(
+            verify_signature(
+                spki,
+                SignatureScheme::EcdsaSecp256r1Sha256,
+                b"message",
+                &signature
+            ),
+            Err(TlsError::DecodeError)
+        ));
+    }
+}
@@ -0,0 +1,100 @@
+use embedded_io::{Read, Write};
+use embedded_tls::{
+    blocking::{Aes128GcmSha256, TlsConfig, TlsConnection, TlsContext, TlsError, UnsecureProvider},
+    pki::CertVerifier,
+    Certificate, CryptoProvider, CryptoRngCore, TlsClock, TlsVerifier,
+};
+
+use super::{pin::KeyPinVerifier, TrustAnchor};
+
+/// Largest certificate chain the server may send, in bytes.
+pub const MAX_CHAIN_SIZE: usize = 8192;
+
+/// A TLS session over `T`.
+pub type Connection<'a, T> = TlsConnection<'a, T, Aes128GcmSha256>;
+
+/// Whether `error` means that the server certificate was rejected, rather than the connection
+/// failing.
+pub fn is_certificate_error(error: &TlsError) -> bool {
+    matches!(
+        error,
+        TlsError::InvalidCertificate
+            | TlsError::InvalidSignature
+            | TlsError::InvalidSignatureScheme
+            | TlsError::DecodeError
+    )
+}
+
+/// A crypto provider checking the certificate of the server with `V`.
+struct VerifyingProvider<R, V> {
+    rng: R,
+    verifier: V,
+}
+
+impl<R, V> CryptoProvider for VerifyingProvider<R, V>
+where
+    R: CryptoRngCore,
+    V: TlsVerifier<Aes128GcmSha256>,
+{
+    type CipherSuite = Aes128GcmSha256;
+    type Signature = &'static [u8];
+
+    fn rng(&mut self) -> impl CryptoRngCore {
+        &mut self.rng
+    }
+
+    fn verifier(&mut self) -> Result<&mut impl TlsVerifier<Self::CipherSuite>, TlsError> {
+        Ok(&mut self.verifier)
+    }
+}
+
+/// Open a TLS session with `host` through `transport`.
+///
+/// # Parameters
+/// - `trust_anchor`: what the certificate of the server is trusted through, or `None` to accept
+///   any certificate. Validity periods are checked against the time of `C`.
+/// - `rng`: the random number generator of the handshake. It must be seeded with unpredictable
+///   bytes, e.g. from an [`EntropyPool`](super::entropy::EntropyPool), or the session keys can
+///   be guessed.
+/// - `read_buf`, `write_buf`: the record buffers of the session
+///
+/// # Errors
+/// A [`TlsError`] if the handshake fails. See [`is_certificate_error`] to know if the certificate
+/// was rejected.
+pub fn open<'a, T, C, R>(
+    transport: T,
+    host: &'a str,
+    trust_anchor: Option<TrustAnchor<'a>>,
+    rng: R,
+    read_buf: &'a mut [u8],
+    write_buf: &'a mut [u8],
+) -> Result<Connection<'a, T>, TlsError>
+where
+    T: Read + Write,
+    C: TlsClock,
+    R: CryptoRngCore,
+{
+    let mut connection = TlsConnection::new(transport, read_buf, write_buf);
+    let config = TlsConfig::new().with_server_name(host);
+
+    match trust_anchor {
+        Some(TrustAnchor::Certificate(anchor)) => {
+            let verifier = CertVerifier::<_, C, MAX_CHAIN_SIZE>::new(Certificate::X509(anchor));
+            let provider = VerifyingProvider { rng, verifier };
+            connection.open(TlsContext::new(&config, provider))?;
+        }
+        Some(TrustAnchor::PublicKey(pin)) => {
+            let provider = VerifyingProvider {
+                rng,
+                verifier: KeyPinVerifier::new(pin),
+            };
+            connection.open(TlsContext::new(&config, provider))?;
+        }
+        None => {
+            let provider = UnsecureProvider::new::<Aes128GcmSha256>(rng);
+            connection.open(TlsContext::new(&config, provider))?;
+        }
+    }
+
+    Ok(connection)
+}
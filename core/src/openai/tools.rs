@@ -0,0 +1,316 @@
+use alloc::{borrow::ToOwned, boxed::Box, format, string::String, vec::Vec};
+
+use serde::Deserialize;
+
+use crate::{
+    fs::data_path,
+    openai::types::ToolDefinition,
+    platform::{Device, FileSystem},
+};
+
+/// Name of the notes file, in the data directory. Each line is a note.
+pub const NOTES_FILE: &str = "notes.txt";
+/// Most notes given back by a search.
+const MAX_NOTES: usize = 10;
+/// Schema of the arguments of the tools taking none.
+const NO_PARAMETERS: &str = r#"{"type": "object", "properties": {}}"#;
+
+/// A tool the model may call, run on the PSP.
+pub trait Tool {
+    /// The name, description and parameters of the tool, as told to the model.
+    fn definition(&self) -> ToolDefinition;
+
+    /// Run the tool with `arguments`, a JSON object.
+    ///
+    /// # Returns
+    /// The result, for the model to read.
+    ///
+    /// # Errors
+    /// Why the call failed, for the model to read.
+    fn call(&mut self, arguments: &str) -> Result<String, String>;
+}
+
+/// The tools the model may call.
+#[derive(Default)]
+pub struct ToolRegistry {
+    tools: Vec<(ToolDefinition, Box<dyn Tool>)>,
+}
+
+impl ToolRegistry {
+    /// The tools reading the state of the PSP: the time, the battery, the free space and the
+    /// notes.
+    pub fn on_device<D, F>(device: D, files: F) -> Self
+    where
+        D: Device + Clone + 'static,
+        F: FileSystem + 'static,
+    {
+        Self::default()
+            .with_tool(CurrentTime(device.clone()))
+            .with_tool(BatteryLevel(device.clone()))
+            .with_tool(FreeSpace(device))
+            .with_tool(SearchNotes(files))
+    }
+
+    /// Add `tool`, replacing the tool with the same name, if any.
+    pub fn with_tool<T: Tool + 'static>(mut self, tool: T) -> Self {
+        let definition = tool.definition();
+        self.tools
+            .retain(|(existing, _)| existing.name != definition.name);
+        self.tools.push((definition, Box::new(tool)));
+        self
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    /// The definitions of the tools, to send to the model.
+    pub fn definitions(&self) -> Vec<ToolDefinition> {
+        self.tools
+            .iter()
+            .map(|(definition, _)| definition.clone())
+            .collect()
+    }
+
+    /// Run the tool `name` with `arguments`.
+    ///
+    /// # Returns
+    /// The result for the model. Failures are reported to it as well, so that it can answer
+    /// without them.
+    pub fn call(&mut self, name: &str, arguments: &str) -> String {
+        let Some((_, tool)) = self
+            .tools
+            .iter_mut()
+            .find(|(definition, _)| definition.name == name)
+        else {
+            return format!("error: unknown tool {}", name);
+        };
+
+        tool.call(arguments)
+            .unwrap_or_else(|e| format!("error: {}", e))
+    }
+}
+
+/// A tool without parameters.
+fn definition(name: &str, description: &str) -> ToolDefinition {
+    ToolDefinition {
+        name: name.to_owned(),
+        description: description.to_owned(),
+        parameters: NO_PARAMETERS.to_owned(),
+    }
+}
+
+/// The local date and time, from the real-time clock.
+pub struct CurrentTime<D>(pub D);
+
+impl<D: Device> Tool for CurrentTime<D> {
+    fn definition(&self) -> ToolDefinition {
+        definition(
+            "get_current_time",
+            "Get the local date and time of the user's PSP.",
+        )
+    }
+
+    fn call(&mut self, _arguments: &str) -> Result<String, String> {
+        let time = self.0.local_time().ok_or("the clock cannot be read")?;
+        Ok(format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            time.year, time.month, time.day, time.hour, time.minute, time.second
+        ))
+    }
+}
+
+/// The charge of the battery.
+pub struct BatteryLevel<D>(pub D);
+
+impl<D: Device> Tool for BatteryLevel<D> {
+    fn definition(&self) -> ToolDefinition {
+        definition(
+            "get_battery_level",
+            "Get the battery charge of the user's PSP, in percent.",
+        )
+    }
+
+    fn call(&mut self, _arguments: &str) -> Result<String, String> {
+        Ok(match self.0.battery() {
+            Some(battery) if battery.charging => format!("{}%, charging", battery.percent),
+            Some(battery) => format!("{}%", battery.percent),
+            None => "no battery, running on the power adapter".to_owned(),
+        })
+    }
+}
+
+/// The free space on the Memory Stick.
+pub struct FreeSpace<D>(pub D);
+
+impl<D: Device> Tool for FreeSpace<D> {
+    fn definition(&self) -> ToolDefinition {
+        definition(
+            "get_free_space",
+            "Get the free space on the Memory Stick of the user's PSP.",
+        )
+    }
+
+    fn call(&mut self, _arguments: &str) -> Result<String, String> {
+        let bytes = self
+            .0
+            .free_space()
+            .ok_or("the Memory Stick cannot be read")?;
+        Ok(format!("{} MiB free", bytes / (1024 * 1024)))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchArguments {
+    query: heapless::String<128>,
+}
+
+/// The notes of the user, kept in [`NOTES_FILE`], matching a query.
+pub struct SearchNotes<F>(pub F);
+
+impl<F: FileSystem> Tool for SearchNotes<F> {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "search_notes".to_owned(),
+            description: "Search the notes the user keeps on their PSP. Returns the notes \
+                          containing the query, one per line."
+                .to_owned(),
+            parameters: r#"{"type": "object", "properties": {"query": {"type": "string", "description": "Text to look for, ignoring case."}}, "required": ["query"]}"#
+                .to_owned(),
+        }
+    }
+
+    fn call(&mut self, arguments: &str) -> Result<String, String> {
+        let mut unescaped = [0u8; 128];
+        let (arguments, _): (SearchArguments, _) =
+            serde_json_core::from_str_escaped(arguments, &mut unescaped)
+                .map_err(|e| format!("invalid arguments: {}", e))?;
+        let query = arguments.query.to_lowercase();
+
+        // no notes file means no notes
+        let notes = self
+            .0
+            .read_to_string(&data_path(NOTES_FILE))
+            .unwrap_or_default();
+        let matches: Vec<&str> = notes
+            .lines()
+            .map(str::trim)
+            .filter(|note| !note.is_empty() && note.to_lowercase().contains(&query))
+            .take(MAX_NOTES)
+            .collect();
+
+        if matches.is_empty() {
+            Ok("no note matches".to_owned())
+        } else {
+            Ok(matches.join("\n"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::{
+        fake::{FakeDevice, MemoryFs},
+        Battery, DateTime,
+    };
+
+    fn device() -> FakeDevice {
+        FakeDevice {
+            time: Some(DateTime {
+                year: 2024,
+                month: 3,
+                day: 9,
+                hour: 7,
+                minute: 5,
+                second: 0,
+            }),
+            battery: Some(Battery {
+                percent: 42,
+                charging: true,
+            }),
+            free_space: Some(3 * 1024 * 1024 * 1024),
+        }
+    }
+
+    fn notes() -> MemoryFs {
+        MemoryFs::default().with_file(
+            &data_path(NOTES_FILE),
+            b"Wi-Fi password: hunter2\n\nDentist on Monday\nwifi of the office: guest\n",
+        )
+    }
+
+    #[test]
+    fn reads_the_device() {
+        let mut tools = ToolRegistry::on_device(device(), MemoryFs::default());
+
+        assert_eq!(tools.call("get_current_time", "{}"), "2024-03-09 07:05:00");
+        assert_eq!(tools.call("get_battery_level", "{}"), "42%, charging");
+        assert_eq!(tools.call("get_free_space", "{}"), "3072 MiB free");
+
+        let mut tools = ToolRegistry::on_device(FakeDevice::default(), MemoryFs::default());
+        assert_eq!(
+            tools.call("get_current_time", "{}"),
+            "error: the clock cannot be read"
+        );
+        assert_eq!(
+            tools.call("get_battery_level", "{}"),
+            "no battery, running on the power adapter"
+        );
+    }
+
+    #[test]
+    fn searches_the_notes() {
+        let mut tools = ToolRegistry::on_device(device(), notes());
+
+        assert_eq!(
+            tools.call("search_notes", r#"{"query": "WI"}"#),
+            "Wi-Fi password: hunter2\nwifi of the office: guest"
+        );
+        assert_eq!(
+            tools.call("search_notes", r#"{"query": "\"dentist\""}"#),
+            "no note matches"
+        );
+        assert_eq!(
+            tools.call("search_notes", r#"{"query": "dentist"}"#),
+            "Dentist on Monday"
+        );
+        assert!(tools
+            .call("search_notes", "{}")
+            .starts_with("error: invalid arguments"));
+
+        let mut tools = ToolRegistry::on_device(device(), MemoryFs::default());
+        assert_eq!(
+            tools.call("search_notes", r#"{"query": "wifi"}"#),
+            "no note matches"
+        );
+    }
+
+    #[test]
+    fn describes_the_tools() {
+        let tools = ToolRegistry::on_device(device(), notes());
+        let names: Vec<String> = tools
+            .definitions()
+            .into_iter()
+            .map(|definition| definition.name)
+            .collect();
+
+        assert_eq!(
+            names,
+            [
+                "get_current_time",
+                "get_battery_level",
+                "get_free_space",
+                "search_notes"
+            ]
+        );
+        assert!(ToolRegistry::default().is_empty());
+    }
+
+    #[test]
+    fn unknown_tools_are_reported_to_the_model() {
+        let mut tools = ToolRegistry::default();
+        assert_eq!(tools.call("rm_rf", "{}"), "error: unknown tool rm_rf");
+    }
+}
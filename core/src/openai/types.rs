@@ -0,0 +1,583 @@
+use core::fmt::Display;
+
+use crate::openai::constants::*;
+use alloc::{
+    borrow::ToOwned,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::openai::OpenAiError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    role: String,
+    pub content: String,
+    /// The tools the assistant asked to call, instead of answering.
+    tool_calls: Vec<ToolCall>,
+    /// The call a `tool` message gives the result of.
+    tool_call_id: Option<String>,
+}
+
+impl Message {
+    fn new(role: &str, content: String) -> Self {
+        Self {
+            role: role.to_owned(),
+            content,
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        }
+    }
+
+    pub fn new_user(content: String) -> Self {
+        Self::new("user", content)
+    }
+    pub fn new_assistant(content: String) -> Self {
+        Self::new("assistant", content)
+    }
+    pub fn new_system(content: String) -> Self {
+        Self::new("system", content)
+    }
+
+    /// A message of the assistant asking to call `tool_calls`.
+    pub fn new_tool_calls(tool_calls: Vec<ToolCall>) -> Self {
+        Self {
+            tool_calls,
+            ..Self::new("assistant", String::new())
+        }
+    }
+
+    /// The result `content` of the call `tool_call_id`.
+    pub fn new_tool_result(tool_call_id: String, content: String) -> Self {
+        Self {
+            tool_call_id: Some(tool_call_id),
+            ..Self::new("tool", content)
+        }
+    }
+
+    /// The role of the author of the message, e.g. `user`.
+    pub fn role(&self) -> &str {
+        &self.role
+    }
+
+    #[inline]
+    pub fn tool_calls(&self) -> &[ToolCall] {
+        &self.tool_calls
+    }
+
+    #[inline]
+    pub fn tool_call_id(&self) -> Option<&str> {
+        self.tool_call_id.as_deref()
+    }
+}
+
+impl Display for Message {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if let Some(tool_call_id) = &self.tool_call_id {
+            return write!(
+                f,
+                "{{\"role\": \"{}\", \"tool_call_id\": \"{}\", \"content\": \"{}\"}}",
+                self.role,
+                escape_json(tool_call_id),
+                escape_json(&self.content)
+            );
+        }
+        if !self.tool_calls.is_empty() {
+            return write!(
+                f,
+                "{{\"role\": \"{}\", \"content\": null, \"tool_calls\": [{}]}}",
+                self.role,
+                join(&self.tool_calls)
+            );
+        }
+
+        write!(
+            f,
+            "{{\"role\": \"{}\", \"content\": \"{}\"}}",
+            self.role,
+            escape_json(&self.content)
+        )
+    }
+}
+
+/// A call of a tool, asked by the model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    /// The arguments, as a JSON object.
+    pub arguments: String,
+}
+
+impl Display for ToolCall {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{{\"id\": \"{}\", \"type\": \"function\", \"function\": {{\"name\": \"{}\", \"arguments\": \"{}\"}}}}",
+            escape_json(&self.id),
+            escape_json(&self.name),
+            escape_json(&self.arguments)
+        )
+    }
+}
+
+/// A tool the model may call, as described to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolDefinition {
+    pub name: String,
+    pub description: String,
+    /// The JSON schema of the arguments.
+    pub parameters: String,
+}
+
+impl Display for ToolDefinition {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{{\"type\": \"function\", \"function\": {{\"name\": \"{}\", \"description\": \"{}\", \"parameters\": {}}}}}",
+            escape_json(&self.name),
+            escape_json(&self.description),
+            self.parameters
+        )
+    }
+}
+
+/// Join `items` with commas, as in a JSON array.
+fn join<T: Display>(items: &[T]) -> String {
+    let mut joined = String::new();
+    for item in items {
+        if !joined.is_empty() {
+            joined.push(',');
+        }
+        joined.push_str(&item.to_string());
+    }
+    joined
+}
+
+/// Escape `s` to be put between quotes in a JSON string.
+pub fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if u32::from(c) < 0x20 => escaped.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The format the model must answer in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ResponseFormat {
+    /// Free text.
+    #[default]
+    Text,
+    /// Any JSON object. The messages must ask for JSON as well, or the API refuses the request.
+    JsonObject,
+    /// JSON following a schema.
+    JsonSchema {
+        /// The name of the schema, made of letters, digits, `_` and `-`.
+        name: String,
+        /// The JSON schema of the answer.
+        schema: String,
+        /// Whether the answer must follow the schema exactly, which restricts the schemas
+        /// allowed.
+        strict: bool,
+    },
+}
+
+impl Display for ResponseFormat {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ResponseFormat::Text => write!(f, "{{\"type\": \"text\"}}"),
+            ResponseFormat::JsonObject => write!(f, "{{\"type\": \"json_object\"}}"),
+            ResponseFormat::JsonSchema {
+                name,
+                schema,
+                strict,
+            } => write!(
+                f,
+                "{{\"type\": \"json_schema\", \"json_schema\": {{\"name\": \"{}\", \"schema\": {}, \"strict\": {}}}}}",
+                escape_json(name),
+                schema,
+                strict
+            ),
+        }
+    }
+}
+
+/// Deserialize `answer`, given in a JSON [`ResponseFormat`], into a `T`.
+///
+/// # Errors
+/// [`OpenAiError::SchemaMismatch`] if `answer` is not JSON, or does not match `T`.
+pub fn parse_json_answer<T: DeserializeOwned>(answer: &str) -> Result<T, OpenAiError> {
+    // unescaped strings are never longer than the answer
+    let mut unescaped = alloc::vec![0u8; answer.len()];
+    let (value, read) = serde_json_core::from_str_escaped(answer, &mut unescaped)
+        .map_err(|e| OpenAiError::SchemaMismatch(e.to_string()))?;
+    if !answer[read..].trim().is_empty() {
+        return Err(OpenAiError::SchemaMismatch(
+            "Trailing characters".to_owned(),
+        ));
+    }
+    Ok(value)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatHistory {
+    model: String,
+    messages: Vec<Message>,
+    temperature: f32,
+    /// The tools the model may call.
+    tools: Vec<ToolDefinition>,
+    response_format: ResponseFormat,
+}
+
+impl ChatHistory {
+    pub fn new(model: String, temperature: f32) -> Self {
+        Self {
+            model,
+            messages: Vec::new(),
+            temperature,
+            tools: Vec::new(),
+            response_format: ResponseFormat::Text,
+        }
+    }
+
+    #[inline]
+    pub fn new_gpt3(temperature: f32) -> Self {
+        Self::new(GPT3_MODEL.to_owned(), temperature)
+    }
+
+    /// Send the next requests to `model`, at `temperature`.
+    pub fn set_model(&mut self, model: String, temperature: f32) {
+        self.model = model;
+        self.temperature = temperature;
+    }
+
+    #[allow(unused)]
+    pub fn clear(&mut self) {
+        self.messages.clear();
+    }
+
+    pub fn add_user_message(&mut self, content: String) {
+        self.messages.push(Message::new_user(content));
+    }
+
+    pub fn add_assistant_message(&mut self, content: String) {
+        self.messages.push(Message::new_assistant(content));
+    }
+
+    /// Instruct the model with `content`, replacing the previous instructions, or remove them if
+    /// `None`. The system message is kept first, before the conversation.
+    pub fn set_system_message(&mut self, content: Option<String>) {
+        if self
+            .messages
+            .first()
+            .is_some_and(|message| message.role() == "system")
+        {
+            self.messages.remove(0);
+        }
+        if let Some(content) = content {
+            self.messages.insert(0, Message::new_system(content));
+        }
+    }
+
+    /// The instructions given to the model, if any.
+    pub fn system_message(&self) -> Option<&str> {
+        self.messages
+            .first()
+            .filter(|message| message.role() == "system")
+            .map(|message| message.content.as_str())
+    }
+
+    /// Record that the assistant asked to call `tool_calls`.
+    pub fn add_tool_calls(&mut self, tool_calls: Vec<ToolCall>) {
+        self.messages.push(Message::new_tool_calls(tool_calls));
+    }
+
+    /// Record the result `content` of the tool call `tool_call_id`.
+    pub fn add_tool_result(&mut self, tool_call_id: String, content: String) {
+        self.messages
+            .push(Message::new_tool_result(tool_call_id, content));
+    }
+
+    /// Let the model call `tools`, or none if empty.
+    pub fn set_tools(&mut self, tools: Vec<ToolDefinition>) {
+        self.tools = tools;
+    }
+
+    #[inline]
+    pub fn tools(&self) -> &[ToolDefinition] {
+        &self.tools
+    }
+
+    /// Make the model answer in `response_format`.
+    pub fn set_response_format(&mut self, response_format: ResponseFormat) {
+        self.response_format = response_format;
+    }
+
+    #[inline]
+    pub fn response_format(&self) -> &ResponseFormat {
+        &self.response_format
+    }
+
+    pub fn messages(&self) -> &[Message] {
+        &self.messages
+    }
+
+    pub fn to_string_with_content_length(&self) -> (String, usize) {
+        let string = self.to_string();
+        let len = string.len();
+
+        (string, len)
+    }
+}
+
+impl Display for ChatHistory {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let tools = if self.tools.is_empty() {
+            String::new()
+        } else {
+            format!("\n  \"tools\": [{}],", join(&self.tools))
+        };
+
+        let response_format = match self.response_format {
+            ResponseFormat::Text => String::new(),
+            ref format => format!("\n  \"response_format\": {},", format),
+        };
+
+        write!(
+            f,
+            "{{\n  \"model\": \"{}\",\n  \"messages\": [{}],{}{}\n  \"temperature\": {},\n  \"stream\": false\n}}",
+            self.model,
+            join(&self.messages),
+            tools,
+            response_format,
+            self.temperature,
+        )
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+pub struct Usage {
+    pub prompt_tokens: i32,
+    pub completion_tokens: i32,
+    pub total_tokens: i32,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+pub struct ResponseMessage {
+    pub role: heapless::String<32>,
+    /// The answer, or `None` when the model calls tools instead.
+    pub content: Option<heapless::String<MAX_CONTENT_LENGTH>>,
+    #[serde(default)]
+    pub tool_calls: heapless::Vec<ResponseToolCall, MAX_TOOL_CALLS>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResponseToolCall {
+    pub id: heapless::String<64>,
+    pub function: ResponseFunction,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResponseFunction {
+    pub name: heapless::String<64>,
+    /// The arguments, as a JSON object.
+    pub arguments: heapless::String<512>,
+}
+
+/// What the model replied to a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+    /// The final answer.
+    Answer(String),
+    /// Tools to call, whose results the model needs to answer.
+    ToolCalls(Vec<ToolCall>),
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+/// # Note
+/// This struct does not support the [`Self::logprobs`] field different from `None` yet.
+pub struct CompletionChoice {
+    pub message: ResponseMessage,
+    pub index: i64,
+    pub finish_reason: heapless::String<32>,
+    pub logprobs: Option<LogprobResult>,
+}
+
+#[derive(Debug, Deserialize)]
+/// This is a dummy struct, it's not actually used.
+pub struct LogprobResult {}
+
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+/// Completion response from OpenAI.
+pub struct CompletionResponse<'a> {
+    pub id: &'a str,
+    pub object: &'a str,
+    pub created: i64,
+    pub model: &'a str,
+    pub choices: heapless::Vec<CompletionChoice, 3>,
+    pub usage: Usage,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_content() {
+        assert_eq!(
+            escape_json("say \"hi\"\n\tC:\\ it's\u{1}"),
+            "say \\\"hi\\\"\\n\\tC:\\\\ it's\\u0001"
+        );
+    }
+
+    #[test]
+    fn serializes_the_request_body() {
+        let mut history = ChatHistory::new("gpt-test".to_owned(), 0.5);
+        history.add_user_message("Hello \"you\"".to_owned());
+        history.add_assistant_message("Hi!".to_owned());
+
+        assert_eq!(
+            history.to_string(),
+            "{\n  \"model\": \"gpt-test\",\n  \"messages\": [{\"role\": \"user\", \"content\": \"Hello \\\"you\\\"\"},{\"role\": \"assistant\", \"content\": \"Hi!\"}],\n  \"temperature\": 0.5,\n  \"stream\": false\n}"
+        );
+        let (body, length) = history.to_string_with_content_length();
+        assert_eq!(body.len(), length);
+    }
+
+    #[test]
+    fn switches_the_model_mid_conversation() {
+        let mut history = ChatHistory::new("gpt-test".to_owned(), 0.5);
+        history.add_user_message("Hello".to_owned());
+        history.set_model("gpt-other".to_owned(), 1.5);
+
+        let body = history.to_string();
+        assert!(
+            body.starts_with("{\n  \"model\": \"gpt-other\","),
+            "{}",
+            body
+        );
+        assert!(body.contains("\"temperature\": 1.5,"), "{}", body);
+        assert_eq!(history.messages().len(), 1);
+    }
+
+    #[test]
+    fn keeps_the_system_message_first() {
+        let mut history = ChatHistory::new("gpt-test".to_owned(), 0.5);
+        history.add_user_message("Hello".to_owned());
+        history.set_system_message(Some("Be brief.".to_owned()));
+        history.set_system_message(Some("Be a pirate.".to_owned()));
+
+        assert_eq!(history.system_message(), Some("Be a pirate."));
+        let roles: Vec<&str> = history.messages().iter().map(Message::role).collect();
+        assert_eq!(roles, ["system", "user"]);
+
+        history.set_system_message(None);
+        assert_eq!(history.system_message(), None);
+        assert_eq!(history.messages().len(), 1);
+    }
+
+    #[test]
+    fn serializes_tools_and_their_calls() {
+        let mut history = ChatHistory::new("gpt-test".to_owned(), 0.5);
+        history.set_tools(alloc::vec![ToolDefinition {
+            name: "get_battery_level".to_owned(),
+            description: "Get the \"battery\" level.".to_owned(),
+            parameters: r#"{"type": "object", "properties": {}}"#.to_owned(),
+        }]);
+        history.add_user_message("Battery?".to_owned());
+        history.add_tool_calls(alloc::vec![ToolCall {
+            id: "call_1".to_owned(),
+            name: "get_battery_level".to_owned(),
+            arguments: "{}".to_owned(),
+        }]);
+        history.add_tool_result("call_1".to_owned(), "42%".to_owned());
+
+        assert_eq!(
+            history.to_string(),
+            "{\n  \"model\": \"gpt-test\",\n  \"messages\": [{\"role\": \"user\", \"content\": \"Battery?\"},{\"role\": \"assistant\", \"content\": null, \"tool_calls\": [{\"id\": \"call_1\", \"type\": \"function\", \"function\": {\"name\": \"get_battery_level\", \"arguments\": \"{}\"}}]},{\"role\": \"tool\", \"tool_call_id\": \"call_1\", \"content\": \"42%\"}],\n  \"tools\": [{\"type\": \"function\", \"function\": {\"name\": \"get_battery_level\", \"description\": \"Get the \\\"battery\\\" level.\", \"parameters\": {\"type\": \"object\", \"properties\": {}}}}],\n  \"temperature\": 0.5,\n  \"stream\": false\n}"
+        );
+    }
+
+    #[test]
+    fn serializes_the_response_format() {
+        let mut history = ChatHistory::new("gpt-test".to_owned(), 0.5);
+        history.set_response_format(ResponseFormat::JsonObject);
+        assert!(history
+            .to_string()
+            .contains("\n  \"response_format\": {\"type\": \"json_object\"},\n"));
+
+        let format = ResponseFormat::JsonSchema {
+            name: "card".to_owned(),
+            schema: r#"{"type": "object"}"#.to_owned(),
+            strict: true,
+        };
+        assert_eq!(
+            format.to_string(),
+            r#"{"type": "json_schema", "json_schema": {"name": "card", "schema": {"type": "object"}, "strict": true}}"#
+        );
+
+        history.set_response_format(ResponseFormat::Text);
+        assert!(!history.to_string().contains("response_format"));
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Card {
+        question: heapless::String<64>,
+        answer: u32,
+    }
+
+    #[test]
+    fn parses_json_answers() {
+        assert_eq!(
+            parse_json_answer::<Card>(r#" {"question": "2 \u00d7 3?", "answer": 6} "#),
+            Ok(Card {
+                question: "2 × 3?".try_into().unwrap(),
+                answer: 6,
+            })
+        );
+        for answer in [
+            r#"{"question": "2 + 2?"}"#,
+            r#"{"question": "2 + 2?", "answer": "four"}"#,
+            "Sure! Here is your card.",
+            r#"{"question": "2 + 2?", "answer": 4} and more"#,
+        ] {
+            assert!(
+                matches!(
+                    parse_json_answer::<Card>(answer),
+                    Err(OpenAiError::SchemaMismatch(_))
+                ),
+                "{}",
+                answer
+            );
+        }
+    }
+
+    #[test]
+    fn parses_a_completion() {
+        let body = r#"{"id":"chatcmpl-1","object":"chat.completion","created":1,"model":"gpt-test","choices":[{"index":0,"message":{"role":"assistant","content":"Paris."},"logprobs":null,"finish_reason":"stop"}],"usage":{"prompt_tokens":9,"completion_tokens":2,"total_tokens":11}}"#;
+        let (response, _) = serde_json_core::from_str::<CompletionResponse>(body).unwrap();
+
+        assert_eq!(response.model, "gpt-test");
+        assert_eq!(
+            response.choices[0].message.content.as_deref(),
+            Some("Paris.")
+        );
+        assert_eq!(response.usage.total_tokens, 11);
+    }
+}
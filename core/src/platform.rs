@@ -0,0 +1,176 @@
+use alloc::{string::String, vec::Vec};
+use core::{net::SocketAddr, time::Duration};
+
+use crate::{
+    fs::FsError,
+    input::{analog::Stick, Buttons},
+    openai::tls::TrustAnchor,
+};
+
+pub mod fake;
+
+/// A source of time, in microseconds, e.g. the system time of the PSP.
+///
+/// Only differences between two times are meaningful.
+pub trait Clock {
+    fn now(&self) -> i64;
+}
+
+/// Access to the files of the Memory Stick.
+pub trait FileSystem {
+    /// Read the whole content of the file at `path`.
+    ///
+    /// # Errors
+    /// - [`FsError::Open`] if the file cannot be opened, e.g. because it does not exist.
+    /// - [`FsError::Read`] if the file cannot be read.
+    fn read(&self, path: &str) -> Result<Vec<u8>, FsError>;
+
+    /// Write `content` to the file at `path`, replacing it if it exists.
+    ///
+    /// The [data directory](crate::fs::DATA_DIR) is created if it does not exist.
+    ///
+    /// # Errors
+    /// - [`FsError::Open`] if the file cannot be created.
+    /// - [`FsError::Write`] if the file cannot be written.
+    fn write(&mut self, path: &str, content: &[u8]) -> Result<(), FsError>;
+
+    /// List the names of the files in the directory `dir`, leaving out its subdirectories.
+    ///
+    /// # Errors
+    /// [`FsError::Open`] if the directory cannot be opened, e.g. because it does not exist.
+    fn list(&self, dir: &str) -> Result<Vec<String>, FsError>;
+
+    /// Read the whole content of the file at `path` as a [`String`].
+    ///
+    /// # Errors
+    /// Same as [`Self::read`], plus [`FsError::InvalidUtf8`] if the file is not valid UTF-8.
+    fn read_to_string(&self, path: &str) -> Result<String, FsError> {
+        let content = self.read(path)?;
+        String::from_utf8(content).map_err(|_| FsError::InvalidUtf8(path.into()))
+    }
+}
+
+/// A connected stream socket. Errors are described as text, as they are only shown to the user.
+pub trait Socket {
+    /// Read what is available into `buf`, blocking until something is.
+    ///
+    /// # Returns
+    /// The number of bytes read, 0 once the connection is closed.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, String>;
+
+    /// Write as much of `buf` as possible, and return how much was written.
+    fn write(&mut self, buf: &[u8]) -> Result<usize, String>;
+
+    /// Send what is still buffered.
+    fn flush(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Bound how long the next reads and writes may block.
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), String>;
+}
+
+/// Why a TLS session could not be opened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HandshakeError {
+    /// The certificate of the server is not trusted.
+    Rejected(String),
+    /// The handshake failed for another reason, e.g. the connection dropped.
+    Failed(String),
+}
+
+impl core::fmt::Display for HandshakeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            HandshakeError::Rejected(message) => write!(f, "certificate rejected: {}", message),
+            HandshakeError::Failed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// Opens connections to servers.
+pub trait Network {
+    type Socket: Socket;
+
+    /// Connect to `addr`, giving up after `timeout`. The timeout also applies to the reads and
+    /// writes on the socket, until changed.
+    fn connect(&mut self, addr: SocketAddr, timeout: Duration) -> Result<Self::Socket, String>;
+
+    /// Open a TLS session with `host` over `socket`, and run `exchange` on it.
+    ///
+    /// The certificate of the server must be trusted through `trust_anchor`, unless it is `None`.
+    ///
+    /// # Errors
+    /// A [`HandshakeError`] if the session cannot be opened.
+    fn with_tls<R, F>(
+        &mut self,
+        socket: Self::Socket,
+        host: &str,
+        trust_anchor: Option<TrustAnchor>,
+        exchange: F,
+    ) -> Result<R, HandshakeError>
+    where
+        F: FnOnce(&mut dyn Socket) -> R;
+}
+
+/// A date and time, as shown by the system.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+/// The charge of the battery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Battery {
+    pub percent: u8,
+    pub charging: bool,
+}
+
+/// The state of the console itself.
+pub trait Device {
+    /// The local time of the real-time clock, if it can be read.
+    fn local_time(&self) -> Option<DateTime>;
+
+    /// The charge of the battery, or `None` without one, e.g. on the power adapter alone.
+    fn battery(&self) -> Option<Battery>;
+
+    /// The free space on the Memory Stick, in bytes, if it can be read.
+    fn free_space(&self) -> Option<u64>;
+}
+
+/// The buttons and the analog stick of the PSP.
+pub trait Input {
+    /// The buttons down right now.
+    fn buttons(&mut self) -> Buttons;
+
+    /// The position of the analog stick right now. Centered if there is none.
+    fn stick(&mut self) -> Stick {
+        Stick::CENTER
+    }
+}
+
+/// A screen able to draw rectangles and text, a frame at a time.
+///
+/// Colors are in the `0xAABBGGRR` format of the GU, see [`color`](crate::layout::color).
+pub trait Display {
+    /// Start a new frame, clearing the screen with `clear_color`.
+    fn begin(&mut self, clear_color: u32);
+
+    /// Finish the frame and show it.
+    fn end(&mut self);
+
+    /// Draw a `width`x`height` rectangle filled with `color`, with its top left corner at
+    /// (`x`, `y`).
+    fn fill_rect(&mut self, x: i16, y: i16, width: i16, height: i16, color: u32);
+
+    /// Draw `text` with its top left corner at (`x`, `y`), on a single line.
+    fn draw_text(&mut self, x: i16, y: i16, text: &str, color: u32);
+
+    /// Draw `text` like [`Self::draw_text`], with glyphs `scale` times as large.
+    fn draw_text_scaled(&mut self, x: i16, y: i16, text: &str, color: u32, scale: i16);
+}
@@ -0,0 +1,429 @@
+//! In-memory implementations of the [platform](super) traits, for tests on the host.
+
+use alloc::{
+    borrow::ToOwned,
+    collections::{BTreeMap, VecDeque},
+    format,
+    rc::Rc,
+    string::String,
+    sync::Arc,
+    vec::Vec,
+};
+use core::{
+    cell::RefCell,
+    net::SocketAddr,
+    sync::atomic::{AtomicI64, Ordering},
+    time::Duration,
+};
+
+use crate::{
+    fs::FsError,
+    input::{analog::Stick, Buttons},
+    openai::tls::TrustAnchor,
+    platform::{
+        Battery, Clock, DateTime, Device, Display, FileSystem, HandshakeError, Input, Network,
+        Socket,
+    },
+};
+
+/// Error code of the firmware for a missing file.
+const ENOENT: i32 = 0x8001_0002_u32 as i32;
+
+/// A clock that only moves when told to. Clones share the same time.
+#[derive(Debug, Clone, Default)]
+pub struct FakeClock {
+    now: Arc<AtomicI64>,
+}
+
+impl FakeClock {
+    /// Move the time forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        self.now
+            .fetch_add(duration.as_micros() as i64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> i64 {
+        self.now.load(Ordering::SeqCst)
+    }
+}
+
+/// Files kept in memory, by path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryFs {
+    files: BTreeMap<String, Vec<u8>>,
+}
+
+impl MemoryFs {
+    /// Add the file `path` with `content`.
+    pub fn with_file(mut self, path: &str, content: &[u8]) -> Self {
+        self.files.insert(path.to_owned(), content.to_vec());
+        self
+    }
+
+    /// The content of the file `path`, if it exists.
+    pub fn file(&self, path: &str) -> Option<&[u8]> {
+        self.files.get(path).map(Vec::as_slice)
+    }
+}
+
+impl FileSystem for MemoryFs {
+    fn read(&self, path: &str) -> Result<Vec<u8>, FsError> {
+        self.files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| FsError::Open(path.to_owned(), ENOENT))
+    }
+
+    fn write(&mut self, path: &str, content: &[u8]) -> Result<(), FsError> {
+        self.files.insert(path.to_owned(), content.to_vec());
+        Ok(())
+    }
+
+    fn list(&self, dir: &str) -> Result<Vec<String>, FsError> {
+        let prefix = format!("{}/", dir);
+        let names: Vec<String> = self
+            .files
+            .keys()
+            .filter_map(|path| path.strip_prefix(&prefix))
+            .filter(|name| !name.contains('/'))
+            .map(ToOwned::to_owned)
+            .collect();
+        // directories only exist through their files
+        if names.is_empty() && !self.files.keys().any(|path| path.starts_with(&prefix)) {
+            return Err(FsError::Open(dir.to_owned(), ENOENT));
+        }
+        Ok(names)
+    }
+}
+
+#[derive(Debug, Default)]
+struct SocketState {
+    reads: VecDeque<Vec<u8>>,
+    /// The error reads fail with once `reads` is drained, instead of reporting the end.
+    drained_error: Option<String>,
+    written: Vec<u8>,
+    timeouts: Vec<Duration>,
+}
+
+/// A socket replaying scripted chunks of data, and recording what is written to it. Clones share
+/// the same connection, so that a test can keep a handle on a socket given away.
+#[derive(Debug, Clone, Default)]
+pub struct FakeSocket {
+    state: Rc<RefCell<SocketState>>,
+}
+
+impl FakeSocket {
+    /// A socket whose reads return `chunks`, one per read, then report the end of the
+    /// connection.
+    pub fn new<const N: usize>(chunks: [&[u8]; N]) -> Self {
+        let socket = Self::default();
+        socket
+            .state
+            .borrow_mut()
+            .reads
+            .extend(chunks.iter().map(|chunk| chunk.to_vec()));
+        socket
+    }
+
+    /// Fail the reads with `error` once the chunks are drained, e.g. to simulate a timeout.
+    pub fn fail_when_drained(&self, error: &str) {
+        self.state.borrow_mut().drained_error = Some(error.to_owned());
+    }
+
+    /// Everything written so far.
+    pub fn written(&self) -> Vec<u8> {
+        self.state.borrow().written.clone()
+    }
+
+    /// The timeouts set so far, in order.
+    pub fn timeouts(&self) -> Vec<Duration> {
+        self.state.borrow().timeouts.clone()
+    }
+}
+
+impl Socket for FakeSocket {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, String> {
+        let mut state = self.state.borrow_mut();
+        let Some(chunk) = state.reads.front_mut() else {
+            return match &state.drained_error {
+                Some(error) => Err(error.clone()),
+                None => Ok(0),
+            };
+        };
+
+        let read = chunk.len().min(buf.len());
+        buf[..read].copy_from_slice(&chunk[..read]);
+        chunk.drain(..read);
+        if chunk.is_empty() {
+            state.reads.pop_front();
+        }
+        Ok(read)
+    }
+
+    fn write(&mut self, buf: &[u8]) -> Result<usize, String> {
+        self.state.borrow_mut().written.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), String> {
+        self.state.borrow_mut().timeouts.push(timeout);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct NetworkState {
+    sockets: VecDeque<FakeSocket>,
+    connections: Vec<SocketAddr>,
+    handshakes: Vec<(String, Option<Vec<u8>>)>,
+    rejected: Vec<Vec<u8>>,
+}
+
+/// A network handing out scripted sockets, one per connection, in order. TLS sessions are not
+/// encrypted: the exchange runs on the socket itself. Clones share the same network.
+#[derive(Debug, Clone, Default)]
+pub struct FakeNetwork {
+    state: Rc<RefCell<NetworkState>>,
+}
+
+impl FakeNetwork {
+    /// Serve the next connection with `socket`. Connecting fails once there are no more.
+    pub fn push(&self, socket: FakeSocket) {
+        self.state.borrow_mut().sockets.push_back(socket);
+    }
+
+    /// Reject the server certificate when `anchor` is the trust anchor.
+    pub fn reject_anchor(&self, anchor: &[u8]) {
+        self.state.borrow_mut().rejected.push(anchor.to_vec());
+    }
+
+    /// The addresses connected to so far, in order.
+    pub fn connections(&self) -> Vec<SocketAddr> {
+        self.state.borrow().connections.clone()
+    }
+
+    /// The host and trust anchor of every TLS session opened so far, in order.
+    pub fn handshakes(&self) -> Vec<(String, Option<Vec<u8>>)> {
+        self.state.borrow().handshakes.clone()
+    }
+}
+
+impl Network for FakeNetwork {
+    type Socket = FakeSocket;
+
+    fn connect(&mut self, addr: SocketAddr, timeout: Duration) -> Result<FakeSocket, String> {
+        let mut state = self.state.borrow_mut();
+        state.connections.push(addr);
+        let mut socket = state
+            .sockets
+            .pop_front()
+            .ok_or_else(|| "Connection refused".to_owned())?;
+        socket.set_timeout(timeout)?;
+        Ok(socket)
+    }
+
+    fn with_tls<R, F>(
+        &mut self,
+        mut socket: FakeSocket,
+        host: &str,
+        trust_anchor: Option<TrustAnchor>,
+        exchange: F,
+    ) -> Result<R, HandshakeError>
+    where
+        F: FnOnce(&mut dyn Socket) -> R,
+    {
+        {
+            let mut state = self.state.borrow_mut();
+            state.handshakes.push((
+                host.to_owned(),
+                trust_anchor.map(|anchor| anchor.as_ref().to_vec()),
+            ));
+            if trust_anchor
+                .is_some_and(|anchor| state.rejected.iter().any(|r| r == anchor.as_ref()))
+            {
+                return Err(HandshakeError::Rejected("InvalidCertificate".to_owned()));
+            }
+        }
+        Ok(exchange(&mut socket))
+    }
+}
+
+/// A device whose state is set by the test.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FakeDevice {
+    pub time: Option<DateTime>,
+    pub battery: Option<Battery>,
+    pub free_space: Option<u64>,
+}
+
+impl Device for FakeDevice {
+    fn local_time(&self) -> Option<DateTime> {
+        self.time
+    }
+
+    fn battery(&self) -> Option<Battery> {
+        self.battery
+    }
+
+    fn free_space(&self) -> Option<u64> {
+        self.free_space
+    }
+}
+
+/// Buttons replayed one sample at a time. No button is down once they are drained.
+#[derive(Debug, Clone, Default)]
+pub struct FakeInput {
+    samples: VecDeque<Buttons>,
+    sticks: VecDeque<Stick>,
+}
+
+impl FakeInput {
+    pub fn new<I: IntoIterator<Item = Buttons>>(samples: I) -> Self {
+        Self {
+            samples: samples.into_iter().collect(),
+            sticks: VecDeque::new(),
+        }
+    }
+
+    /// The number of samples of the buttons not replayed yet.
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Replay `sticks` as the positions of the stick, one per frame, then keep it centered.
+    pub fn with_sticks<I: IntoIterator<Item = Stick>>(mut self, sticks: I) -> Self {
+        self.sticks = sticks.into_iter().collect();
+        self
+    }
+
+    /// Replay a recorded sequence of samples, one per frame, separated by whitespace: the names
+    /// of the buttons down joined by `+`, or `-` for none, each optionally followed by `*n` to
+    /// repeat it for `n` frames.
+    ///
+    /// # Example
+    /// `"down*30 - cross+r"`: Down held for 30 frames, released, then Cross and R pressed
+    /// together.
+    ///
+    /// # Panics
+    /// If the recording is malformed.
+    pub fn recorded(recording: &str) -> Self {
+        let mut samples = VecDeque::new();
+        for frame in recording.split_whitespace() {
+            let (buttons, count) = match frame.split_once('*') {
+                Some((buttons, count)) => (buttons, count.parse().expect("invalid repeat count")),
+                None => (frame, 1),
+            };
+            let buttons = match buttons {
+                "-" => Buttons::empty(),
+                buttons => buttons.parse().expect("invalid button name"),
+            };
+            samples.extend(core::iter::repeat_n(buttons, count));
+        }
+        Self {
+            samples,
+            sticks: VecDeque::new(),
+        }
+    }
+}
+
+impl Input for FakeInput {
+    fn buttons(&mut self) -> Buttons {
+        self.samples.pop_front().unwrap_or_default()
+    }
+
+    fn stick(&mut self) -> Stick {
+        self.sticks.pop_front().unwrap_or_default()
+    }
+}
+
+/// A rectangle drawn on a [`FakeDisplay`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i16,
+    pub y: i16,
+    pub width: i16,
+    pub height: i16,
+    pub color: u32,
+}
+
+/// Text drawn on a [`FakeDisplay`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Text {
+    pub x: i16,
+    pub y: i16,
+    pub text: String,
+    pub color: u32,
+    /// How many times larger than the font the glyphs are.
+    pub scale: i16,
+}
+
+/// A display recording what is drawn in the current frame.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FakeDisplay {
+    rects: Vec<Rect>,
+    texts: Vec<Text>,
+    frames: usize,
+}
+
+impl FakeDisplay {
+    /// The texts drawn, in order.
+    pub fn texts(&self) -> Vec<&str> {
+        self.texts.iter().map(|text| text.text.as_str()).collect()
+    }
+
+    /// The first text drawn at the height `y`, if any.
+    pub fn text_at(&self, y: i16) -> Option<&str> {
+        self.texts
+            .iter()
+            .find(|text| text.y == y)
+            .map(|text| text.text.as_str())
+    }
+
+    /// The rectangles drawn, in order.
+    pub fn rects(&self) -> &[Rect] {
+        &self.rects
+    }
+
+    /// The number of frames finished.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+}
+
+impl Display for FakeDisplay {
+    fn begin(&mut self, _clear_color: u32) {
+        self.rects.clear();
+        self.texts.clear();
+    }
+
+    fn end(&mut self) {
+        self.frames += 1;
+    }
+
+    fn fill_rect(&mut self, x: i16, y: i16, width: i16, height: i16, color: u32) {
+        self.rects.push(Rect {
+            x,
+            y,
+            width,
+            height,
+            color,
+        });
+    }
+
+    fn draw_text(&mut self, x: i16, y: i16, text: &str, color: u32) {
+        self.draw_text_scaled(x, y, text, color, 1);
+    }
+
+    fn draw_text_scaled(&mut self, x: i16, y: i16, text: &str, color: u32, scale: i16) {
+        self.texts.push(Text {
+            x,
+            y,
+            text: text.to_owned(),
+            color,
+            scale,
+        });
+    }
+}
@@ -0,0 +1,76 @@
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// Flag of the power state: the PSP is going to sleep.
+pub const SUSPENDING: u32 = 0x0001_0000;
+/// Flag of the power state: the PSP is waking up.
+pub const RESUMING: u32 = 0x0002_0000;
+/// Flag of the power state: the PSP is awake again.
+pub const RESUME_COMPLETE: u32 = 0x0004_0000;
+
+/// The suspends and resumes of the PSP, recorded by the power callback and read by the
+/// application.
+///
+/// The callback runs on a thread of its own, so the state is kept in atomics. A suspend is
+/// followed by several resume flags: only the first one counts.
+#[derive(Debug, Default)]
+pub struct PowerEvents {
+    suspended: AtomicBool,
+    resumes: AtomicU32,
+}
+
+impl PowerEvents {
+    pub const fn new() -> Self {
+        Self {
+            suspended: AtomicBool::new(false),
+            resumes: AtomicU32::new(0),
+        }
+    }
+
+    /// Record the power state `power_info`, the flags passed to the power callbacks.
+    pub fn record(&self, power_info: u32) {
+        if power_info & SUSPENDING != 0 {
+            self.suspended.store(true, Ordering::Release);
+        } else if power_info & (RESUMING | RESUME_COMPLETE) != 0
+            && self.suspended.swap(false, Ordering::AcqRel)
+        {
+            self.resumes.fetch_add(1, Ordering::Release);
+        }
+    }
+
+    /// Whether the PSP is going to sleep, and has not woken up yet.
+    #[inline]
+    pub fn is_suspended(&self) -> bool {
+        self.suspended.load(Ordering::Acquire)
+    }
+
+    /// The number of times the PSP woke up from sleep. Compare it with a previous count to know
+    /// whether it slept since.
+    #[inline]
+    pub fn resumes(&self) -> u32 {
+        self.resumes.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_each_resume_once() {
+        let events = PowerEvents::new();
+        events.record(RESUME_COMPLETE);
+        assert_eq!(events.resumes(), 0);
+
+        events.record(SUSPENDING);
+        assert!(events.is_suspended());
+        events.record(RESUMING);
+        events.record(RESUME_COMPLETE);
+        assert!(!events.is_suspended());
+        assert_eq!(events.resumes(), 1);
+
+        // the battery flags come with every event
+        events.record(SUSPENDING | 0x80);
+        events.record(RESUME_COMPLETE | 0x80);
+        assert_eq!(events.resumes(), 2);
+    }
+}
@@ -0,0 +1,221 @@
+use alloc::{borrow::ToOwned, format, string::String};
+use core::fmt::Display;
+
+use crate::{
+    keyboard::InputMethod,
+    layout::{TextSize, Theme},
+    openai::constants::GPT3_MODEL,
+};
+
+/// Highest temperature the API accepts, in tenths.
+pub const MAX_TEMPERATURE: u8 = 20;
+/// Longest model name accepted.
+pub const MAX_MODEL_LENGTH: usize = 64;
+
+/// Why [`Settings`] are invalid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SettingsError {
+    /// The model name is empty.
+    EmptyModel,
+    /// The model name is longer than [`MAX_MODEL_LENGTH`].
+    ModelTooLong,
+    /// The model name has a character that is not a letter, a digit, `-`, `.`, `_` or `:`.
+    InvalidModel(char),
+    /// The temperature, in tenths, is above [`MAX_TEMPERATURE`].
+    Temperature(u8),
+    /// The network profile is not a positive number.
+    NetworkProfile(i32),
+}
+
+impl Display for SettingsError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SettingsError::EmptyModel => write!(f, "the model is empty"),
+            SettingsError::ModelTooLong => {
+                write!(
+                    f,
+                    "the model is longer than {} characters",
+                    MAX_MODEL_LENGTH
+                )
+            }
+            SettingsError::InvalidModel(c) => write!(f, "the model cannot contain '{}'", c),
+            SettingsError::Temperature(tenths) => write!(
+                f,
+                "the temperature {}.{} is above {}.{}",
+                tenths / 10,
+                tenths % 10,
+                MAX_TEMPERATURE / 10,
+                MAX_TEMPERATURE % 10
+            ),
+            SettingsError::NetworkProfile(profile) => {
+                write!(f, "there is no network profile {}", profile)
+            }
+        }
+    }
+}
+
+/// The settings the user edits in the app.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+    /// The model answering the prompts.
+    pub model: String,
+    /// The temperature of the answers, in tenths, from 0 to [`MAX_TEMPERATURE`].
+    pub temperature: u8,
+    /// The access point connection profile to connect to, if one was chosen.
+    pub network_profile: Option<i32>,
+    /// The size of the text of the conversations.
+    pub text_size: TextSize,
+    pub theme: Theme,
+    /// The way the user types text.
+    pub input_method: InputMethod,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            model: GPT3_MODEL.to_owned(),
+            temperature: 7,
+            network_profile: None,
+            text_size: TextSize::default(),
+            theme: Theme::default(),
+            input_method: InputMethod::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Check that the settings can be used.
+    ///
+    /// # Errors
+    /// The first [`SettingsError`] found.
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        validate_model(&self.model)?;
+        if self.temperature > MAX_TEMPERATURE {
+            return Err(SettingsError::Temperature(self.temperature));
+        }
+        match self.network_profile {
+            Some(profile) if profile < 1 => Err(SettingsError::NetworkProfile(profile)),
+            _ => Ok(()),
+        }
+    }
+
+    /// The temperature of the answers, as sent to the API.
+    #[inline]
+    pub fn temperature(&self) -> f32 {
+        f32::from(self.temperature) / 10.0
+    }
+
+    /// Change the model to `model`, trimmed.
+    ///
+    /// # Errors
+    /// A [`SettingsError`] if `model` is not a valid name, leaving the model unchanged.
+    pub fn set_model(&mut self, model: &str) -> Result<(), SettingsError> {
+        let model = model.trim();
+        validate_model(model)?;
+        self.model = model.to_owned();
+        Ok(())
+    }
+
+    /// Change the temperature by `tenths`, staying between 0 and [`MAX_TEMPERATURE`].
+    pub fn adjust_temperature(&mut self, tenths: i8) {
+        self.temperature = self
+            .temperature
+            .saturating_add_signed(tenths)
+            .min(MAX_TEMPERATURE);
+    }
+}
+
+/// Check that `model` can be sent as is in a request.
+fn validate_model(model: &str) -> Result<(), SettingsError> {
+    if model.is_empty() {
+        return Err(SettingsError::EmptyModel);
+    }
+    if model.chars().count() > MAX_MODEL_LENGTH {
+        return Err(SettingsError::ModelTooLong);
+    }
+    match model
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | ':')))
+    {
+        Some(c) => Err(SettingsError::InvalidModel(c)),
+        None => Ok(()),
+    }
+}
+
+/// Parse a temperature written as a decimal number, e.g. `0.7`, into tenths.
+///
+/// # Returns
+/// `None` if `value` is not a number with at most one decimal.
+pub fn parse_temperature(value: &str) -> Option<u8> {
+    let (units, tenths) = value.split_once('.').unwrap_or((value, "0"));
+    if units.is_empty() || tenths.len() != 1 {
+        return None;
+    }
+    let units: u8 = units.parse().ok()?;
+    let tenths: u8 = tenths.parse().ok()?;
+    units.checked_mul(10)?.checked_add(tenths)
+}
+
+/// Write `tenths` as a decimal number, e.g. `0.7`.
+pub fn format_temperature(tenths: u8) -> String {
+    format!("{}.{}", tenths / 10, tenths % 10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_the_settings() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.validate(), Ok(()));
+        assert_eq!(settings.temperature(), 0.7);
+
+        settings.temperature = 21;
+        assert_eq!(settings.validate(), Err(SettingsError::Temperature(21)));
+        settings.temperature = 0;
+        settings.network_profile = Some(0);
+        assert_eq!(settings.validate(), Err(SettingsError::NetworkProfile(0)));
+        settings.network_profile = Some(2);
+        settings.model = "gpt \"4\"".to_owned();
+        assert_eq!(settings.validate(), Err(SettingsError::InvalidModel(' ')));
+    }
+
+    #[test]
+    fn keeps_the_model_if_invalid() {
+        let mut settings = Settings::default();
+
+        assert_eq!(settings.set_model(" gpt-4o-mini "), Ok(()));
+        assert_eq!(settings.model, "gpt-4o-mini");
+        assert_eq!(settings.set_model(""), Err(SettingsError::EmptyModel));
+        assert_eq!(
+            settings.set_model(&"a".repeat(MAX_MODEL_LENGTH + 1)),
+            Err(SettingsError::ModelTooLong)
+        );
+        assert_eq!(settings.model, "gpt-4o-mini");
+        assert_eq!(
+            SettingsError::InvalidModel('"').to_string(),
+            "the model cannot contain '\"'"
+        );
+    }
+
+    #[test]
+    fn adjusts_the_temperature_within_bounds() {
+        let mut settings = Settings::default();
+        settings.adjust_temperature(-10);
+        assert_eq!(settings.temperature, 0);
+        settings.adjust_temperature(25);
+        assert_eq!(settings.temperature, MAX_TEMPERATURE);
+    }
+
+    #[test]
+    fn parses_temperatures() {
+        assert_eq!(parse_temperature("0.7"), Some(7));
+        assert_eq!(parse_temperature("1"), Some(10));
+        assert_eq!(parse_temperature("1.25"), None);
+        assert_eq!(parse_temperature(".5"), None);
+        assert_eq!(parse_temperature("-1"), None);
+        assert_eq!(parse_temperature("30.0"), None);
+        assert_eq!(format_temperature(15), "1.5");
+    }
+}
@@ -0,0 +1,167 @@
+use alloc::{vec, vec::Vec};
+
+/// A change of screen, requested by the screen on top of a [`ScreenStack`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transition<S> {
+    /// Stay on the current screen.
+    Stay,
+    /// Show a new screen on top of the current one.
+    Push(S),
+    /// Go back to the previous screen. Popping the last screen exits the application.
+    Pop,
+    /// Replace the current screen with a new one.
+    Replace(S),
+    /// Replace every screen with a new one.
+    Reset(S),
+    /// Exit the application.
+    Exit,
+}
+
+/// Whether the application keeps running after a [`Transition`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Running,
+    Exited,
+}
+
+/// A stack of screens. Only the screen on top is updated and shown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScreenStack<S> {
+    screens: Vec<S>,
+}
+
+impl<S> ScreenStack<S> {
+    /// Create a stack with `initial` as its only screen.
+    pub fn new(initial: S) -> Self {
+        Self {
+            screens: vec![initial],
+        }
+    }
+
+    /// The screen on top of the stack, if the application is still running.
+    #[inline]
+    pub fn top(&self) -> Option<&S> {
+        self.screens.last()
+    }
+
+    /// The screen on top of the stack, if the application is still running.
+    #[inline]
+    pub fn top_mut(&mut self) -> Option<&mut S> {
+        self.screens.last_mut()
+    }
+
+    /// The number of screens in the stack.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.screens.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.screens.is_empty()
+    }
+
+    /// Apply `transition` to the stack.
+    ///
+    /// # Returns
+    /// [`Status::Exited`] if the stack is now empty, [`Status::Running`] otherwise.
+    pub fn apply(&mut self, transition: Transition<S>) -> Status {
+        match transition {
+            Transition::Stay => (),
+            Transition::Push(screen) => self.screens.push(screen),
+            Transition::Pop => {
+                self.screens.pop();
+            }
+            Transition::Replace(screen) => {
+                self.screens.pop();
+                self.screens.push(screen);
+            }
+            Transition::Reset(screen) => {
+                self.screens.clear();
+                self.screens.push(screen);
+            }
+            Transition::Exit => self.screens.clear(),
+        }
+
+        if self.screens.is_empty() {
+            Status::Exited
+        } else {
+            Status::Running
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stack(screens: &[&'static str]) -> ScreenStack<&'static str> {
+        let mut stack = ScreenStack::new(screens[0]);
+        for screen in &screens[1..] {
+            stack.apply(Transition::Push(*screen));
+        }
+        stack
+    }
+
+    #[test]
+    fn stays_on_the_top_screen() {
+        let mut stack = stack(&["splash"]);
+        assert_eq!(stack.apply(Transition::Stay), Status::Running);
+        assert_eq!(stack, ScreenStack::new("splash"));
+    }
+
+    #[test]
+    fn pushes_and_pops_screens() {
+        let mut stack = stack(&["menu"]);
+        assert_eq!(stack.apply(Transition::Push("chat")), Status::Running);
+        assert_eq!(stack.top(), Some(&"chat"));
+        assert_eq!(stack.len(), 2);
+
+        assert_eq!(stack.apply(Transition::Pop), Status::Running);
+        assert_eq!(stack.top(), Some(&"menu"));
+        assert_eq!(stack.len(), 1);
+    }
+
+    #[test]
+    fn popping_the_last_screen_exits() {
+        let mut stack = stack(&["menu"]);
+        assert_eq!(stack.apply(Transition::Pop), Status::Exited);
+        assert!(stack.is_empty());
+        assert_eq!(stack.top(), None);
+    }
+
+    #[test]
+    fn replaces_the_top_screen() {
+        let mut stack = stack(&["menu", "settings"]);
+        assert_eq!(stack.apply(Transition::Replace("error")), Status::Running);
+        assert_eq!(stack, self::stack(&["menu", "error"]));
+
+        // the last screen too, without exiting
+        let mut stack = self::stack(&["splash"]);
+        assert_eq!(stack.apply(Transition::Replace("network")), Status::Running);
+        assert_eq!(stack, ScreenStack::new("network"));
+    }
+
+    #[test]
+    fn resets_every_screen() {
+        let mut stack = stack(&["menu", "chat", "error"]);
+        assert_eq!(stack.apply(Transition::Reset("network")), Status::Running);
+        assert_eq!(stack, ScreenStack::new("network"));
+    }
+
+    #[test]
+    fn exits_from_any_depth() {
+        let mut stack = stack(&["menu", "chat", "history"]);
+        assert_eq!(stack.apply(Transition::Exit), Status::Exited);
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn top_mut_changes_the_top_screen() {
+        let mut stack = stack(&["menu", "chat"]);
+        if let Some(top) = stack.top_mut() {
+            *top = "history";
+        }
+        assert_eq!(stack, self::stack(&["menu", "history"]));
+    }
+}
@@ -0,0 +1,169 @@
+use alloc::{borrow::ToOwned, format, string::String, vec::Vec};
+
+use crate::{fs::data_path, platform::FileSystem};
+
+/// Directory, inside the [data directory](crate::fs::DATA_DIR), holding the template files.
+pub const TEMPLATES_DIR: &str = "templates";
+/// Where the input of the user goes in the prompt of a template.
+pub const INPUT_PLACEHOLDER: &str = "{input}";
+
+/// A persona: instructions for the model, and a prompt wrapping what the user types.
+///
+/// A template file has one `key=value` setting per line, with `\n` standing for a line break.
+/// Without a `name`, the template is named after its file.
+///
+/// # Example
+/// ```text
+/// name=Translator
+/// system=You translate English to French.\nAnswer with the translation only.
+/// prompt=Translate: {input}
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Template {
+    pub name: String,
+    /// The instructions given to the model as the system message, if any.
+    pub system: Option<String>,
+    /// The prompt sent instead of the input of the user, with the input in place of
+    /// [`INPUT_PLACEHOLDER`]. The input is sent as typed if empty.
+    pub prompt: String,
+}
+
+impl Template {
+    /// Parse the content of the template file `file_name`.
+    pub fn parse(file_name: &str, content: &str) -> Self {
+        let mut template = Self {
+            name: file_name
+                .rsplit_once('.')
+                .map_or(file_name, |(stem, _)| stem)
+                .to_owned(),
+            ..Self::default()
+        };
+
+        for line in content.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = unescape(value.trim());
+            match key.trim() {
+                "name" if !value.is_empty() => template.name = value,
+                "system" => template.system = Some(value).filter(|system| !system.is_empty()),
+                "prompt" => template.prompt = value,
+                _ => (),
+            }
+        }
+
+        template
+    }
+
+    /// Wrap `input` in the prompt of the template.
+    ///
+    /// # Returns
+    /// The prompt with `input` in place of the placeholders, or after the prompt if it has none.
+    pub fn apply(&self, input: &str) -> String {
+        if self.prompt.is_empty() {
+            input.to_owned()
+        } else if self.prompt.contains(INPUT_PLACEHOLDER) {
+            self.prompt.replace(INPUT_PLACEHOLDER, input)
+        } else {
+            format!("{}\n\n{}", self.prompt, input)
+        }
+    }
+}
+
+/// Turn the `\n` of a value into line breaks, and `\\` into backslashes.
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                unescaped.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                unescaped.push('\\');
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Load the templates in [`TEMPLATES_DIR`], sorted by name, ignoring case.
+///
+/// Files that cannot be read are left out, and there are none if the directory does not exist.
+pub fn load_templates<F: FileSystem>(files: &F) -> Vec<Template> {
+    let dir = data_path(TEMPLATES_DIR);
+    let mut templates: Vec<Template> = files
+        .list(&dir)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|file_name| {
+            let content = files
+                .read_to_string(&format!("{}/{}", dir, file_name))
+                .ok()?;
+            Some(Template::parse(&file_name, &content))
+        })
+        .collect();
+    templates.sort_by_key(|template| template.name.to_lowercase());
+    templates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::fake::MemoryFs;
+
+    #[test]
+    fn parses_a_template() {
+        let template = Template::parse(
+            "french.txt",
+            "name=Translator\nsystem=You translate to French.\\nBe brief.\nprompt=Translate: {input}\nbogus\n",
+        );
+
+        assert_eq!(
+            template,
+            Template {
+                name: "Translator".to_owned(),
+                system: Some("You translate to French.\nBe brief.".to_owned()),
+                prompt: "Translate: {input}".to_owned(),
+            }
+        );
+        assert_eq!(Template::parse("pirate.txt", "system=Arr.").name, "pirate");
+        assert_eq!(
+            Template::parse("a.txt", r"prompt=C:\\{input}\n").prompt,
+            "C:\\{input}\n"
+        );
+    }
+
+    #[test]
+    fn wraps_the_input() {
+        let mut template = Template::parse("t.txt", "prompt=Fix: {input} ({input})");
+        assert_eq!(template.apply("teh"), "Fix: teh (teh)");
+
+        template.prompt = "Summarize this.".to_owned();
+        assert_eq!(template.apply("text"), "Summarize this.\n\ntext");
+
+        template.prompt.clear();
+        assert_eq!(template.apply("text"), "text");
+    }
+
+    #[test]
+    fn loads_the_templates_sorted_by_name() {
+        let dir = data_path(TEMPLATES_DIR);
+        let files = MemoryFs::default()
+            .with_file(&format!("{}/b.txt", dir), b"name=Zed")
+            .with_file(&format!("{}/a.txt", dir), b"prompt=x")
+            .with_file(&format!("{}/broken.txt", dir), b"\xff")
+            .with_file(&format!("{}/old/c.txt", dir), b"name=Old");
+
+        let names: Vec<String> = load_templates(&files)
+            .into_iter()
+            .map(|template| template.name)
+            .collect();
+        assert_eq!(names, ["a", "Zed"]);
+
+        assert!(load_templates(&MemoryFs::default()).is_empty());
+    }
+}
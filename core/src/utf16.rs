@@ -0,0 +1,170 @@
+use alloc::{string::String, vec, vec::Vec};
+
+/// The UTF-16 NUL terminator expected by the PSP utility dialogs.
+pub const NUL: u16 = 0;
+
+/// An owned, NUL-terminated UTF-16 buffer.
+///
+/// The PSP utility dialogs (such as the OSK) take raw pointers to UTF-16 strings. This type owns
+/// the memory those pointers refer to, so that it stays alive for as long as the dialog needs it.
+///
+/// The buffer always contains at least one unit, the NUL terminator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Utf16String {
+    units: Vec<u16>,
+}
+
+impl Utf16String {
+    #[inline]
+    /// Create an empty string, made only of the NUL terminator.
+    pub fn new() -> Self {
+        Self { units: vec![NUL] }
+    }
+
+    #[inline]
+    /// Create a zeroed buffer able to hold `len` UTF-16 units plus the NUL terminator.
+    ///
+    /// Use it as the output buffer of a dialog, passing [`Self::capacity`] as its length.
+    pub fn zeroed(len: usize) -> Self {
+        Self {
+            units: vec![NUL; len + 1],
+        }
+    }
+
+    /// The UTF-16 units, including the NUL terminator.
+    #[inline]
+    pub fn as_units(&self) -> &[u16] {
+        &self.units
+    }
+
+    /// The number of UTF-16 units the buffer can hold, excluding the NUL terminator.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.units.len() - 1
+    }
+
+    /// A mutable pointer to the first unit of the buffer.
+    ///
+    /// The pointer is valid for [`Self::capacity`] + 1 units, as long as `self` is alive and not
+    /// moved.
+    #[inline]
+    pub fn as_mut_ptr(&mut self) -> *mut u16 {
+        self.units.as_mut_ptr()
+    }
+
+    /// Decode the buffer into a [`String`], up to the first NUL unit.
+    ///
+    /// See [`decode`] for how invalid sequences are handled.
+    #[inline]
+    pub fn to_string_lossy(&self) -> String {
+        decode(&self.units)
+    }
+}
+
+impl Default for Utf16String {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<&str> for Utf16String {
+    #[inline]
+    fn from(s: &str) -> Self {
+        Self { units: encode(s) }
+    }
+}
+
+/// Encode `s` as UTF-16, appending the NUL terminator.
+///
+/// Characters outside the Basic Multilingual Plane are encoded as surrogate pairs. Any NUL
+/// character inside `s` is kept, and will terminate the string early for the PSP.
+pub fn encode(s: &str) -> Vec<u16> {
+    let mut units: Vec<u16> = Vec::with_capacity(s.len() + 1);
+    units.extend(s.encode_utf16());
+    units.push(NUL);
+    units
+}
+
+/// Decode UTF-16 `units` into a [`String`].
+///
+/// Decoding stops at the first NUL unit, or at the end of `units`. Surrogate pairs are combined
+/// into a single character, while unpaired surrogates are replaced with
+/// [`char::REPLACEMENT_CHARACTER`].
+pub fn decode(units: &[u16]) -> String {
+    let end = units.iter().position(|&u| u == NUL).unwrap_or(units.len());
+
+    char::decode_utf16(units[..end].iter().copied())
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+/// Decode at most `len` UTF-16 units starting at `ptr` into a [`String`].
+///
+/// # Safety
+/// `ptr` must be non-null and valid for reads of `len` units.
+pub unsafe fn decode_ptr(ptr: *const u16, len: usize) -> String {
+    let units = unsafe { core::slice::from_raw_parts(ptr, len) };
+    decode(units)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_with_the_terminator() {
+        assert_eq!(encode(""), [NUL]);
+        assert_eq!(encode("Hé"), [0x48, 0xe9, NUL]);
+        assert_eq!(encode("日本語"), [0x65e5, 0x672c, 0x8a9e, NUL]);
+        // outside the Basic Multilingual Plane, as a surrogate pair
+        assert_eq!(encode("😀"), [0xd83d, 0xde00, NUL]);
+    }
+
+    #[test]
+    fn round_trips_cjk_and_emoji() {
+        for text in [
+            "こんにちは、世界",
+            "안녕하세요",
+            "你好 👋🏽",
+            "𠜎𠜱 & 🇫🇷",
+            "mixed ascii ✓",
+        ] {
+            assert_eq!(decode(&encode(text)), text);
+            assert_eq!(Utf16String::from(text).to_string_lossy(), text);
+        }
+    }
+
+    #[test]
+    fn decodes_up_to_the_first_nul() {
+        assert_eq!(decode(&[0x41, NUL, 0x42, NUL]), "A");
+        // without a terminator, up to the end
+        assert_eq!(decode(&[0x41, 0x42]), "AB");
+        assert_eq!(decode(&[]), "");
+    }
+
+    #[test]
+    fn replaces_unpaired_surrogates() {
+        // a high surrogate without its low one, then a lone low one
+        assert_eq!(decode(&[0xd83d, 0x41, 0xde00, NUL]), "\u{fffd}A\u{fffd}");
+        // cut before the low surrogate
+        assert_eq!(decode(&[0x41, 0xd83d]), "A\u{fffd}");
+    }
+
+    #[test]
+    fn zeroed_buffers_hold_the_terminator_too() {
+        let mut buffer = Utf16String::zeroed(4);
+        assert_eq!(buffer.capacity(), 4);
+        assert_eq!(buffer.as_units(), [NUL; 5]);
+        assert_eq!(buffer.to_string_lossy(), "");
+
+        // as a dialog writes its output
+        let text = encode("語😀");
+        unsafe {
+            core::ptr::copy_nonoverlapping(text.as_ptr(), buffer.as_mut_ptr(), text.len());
+        }
+        assert_eq!(buffer.to_string_lossy(), "語😀");
+        assert_eq!(unsafe { decode_ptr(buffer.as_units().as_ptr(), 5) }, "語😀");
+
+        assert_eq!(Utf16String::new().capacity(), 0);
+    }
+}
@@ -0,0 +1,469 @@
+//! A fake OpenAI server on the loopback interface, and the host implementations of the platform
+//! traits to reach it.
+//!
+//! The server replays scripted [`Reply`]s, one per connection, and records the requests it
+//! receives. The [`HostNetwork`] sends every connection to it, as if it were the API host, the
+//! proxy or the relay, and does not encrypt the TLS sessions.
+
+#![allow(dead_code)]
+
+use std::{
+    io::{Read, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use chat_gpsp_core::{
+    net::dns::{CachingResolver, ResolveHostname, DEFAULT_TTL},
+    openai::{constants::OPENAI_API_HOST, timeout::Timeouts, tls::TrustAnchor, OpenAiContext},
+    platform::{Clock, HandshakeError, Network, Socket},
+};
+
+/// A response of the fake server, and how it is sent.
+#[derive(Debug, Clone, Default)]
+pub struct Reply {
+    bytes: Vec<u8>,
+    /// How long to wait before answering.
+    delay: Duration,
+    /// How many bytes to send before closing the connection, if not all of them.
+    cut_after: Option<usize>,
+    /// How many bytes to send before stalling, and for how long.
+    pause: Option<(usize, Duration)>,
+    /// The reply to the next request on the same connection, if any.
+    next: Option<Box<Reply>>,
+}
+
+impl Reply {
+    /// `bytes`, sent as they are.
+    pub fn raw(bytes: &[u8]) -> Self {
+        Self {
+            bytes: bytes.to_vec(),
+            ..Self::default()
+        }
+    }
+
+    /// A JSON `body` with the status `status`, and its `Content-Length`.
+    pub fn json(status: u16, body: &str) -> Self {
+        Self::raw(
+            format!(
+                "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                status,
+                reason(status),
+                body.len(),
+                body
+            )
+            .as_bytes(),
+        )
+    }
+
+    /// A chat completion answering `content`.
+    pub fn completion(content: &str) -> Self {
+        Self::json(200, &completion_body(content))
+    }
+
+    /// A chat completion calling tools, each given as its id, name and JSON arguments.
+    pub fn tool_calls(calls: &[(&str, &str, &str)]) -> Self {
+        let calls: Vec<String> = calls
+            .iter()
+            .map(|(id, name, arguments)| {
+                format!(
+                    "{{\"id\":\"{}\",\"type\":\"function\",\"function\":{{\"name\":\"{}\",                     \"arguments\":\"{}\"}}}}",
+                    id,
+                    name,
+                    arguments.replace('"', "\\\"")
+                )
+            })
+            .collect();
+        Self::json(
+            200,
+            &format!(
+                "{{\"id\":\"chatcmpl-1\",\"object\":\"chat.completion\",\"created\":1,\
+                 \"model\":\"gpt-3.5-turbo\",\"choices\":[{{\"index\":0,\"message\":{{\"role\":\
+                 \"assistant\",\"content\":null,\"tool_calls\":[{}]}},\"logprobs\":null,\
+                 \"finish_reason\":\"tool_calls\"}}],\"usage\":{{\"prompt_tokens\":1,\
+                 \"completion_tokens\":1,\"total_tokens\":2}}}}",
+                calls.join(",")
+            ),
+        )
+    }
+
+    /// A chat completion answering `content`, sent in chunks of at most `chunk_size` bytes.
+    pub fn chunked(content: &str, chunk_size: usize) -> Self {
+        let mut response = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+            Transfer-Encoding: chunked\r\n\r\n"
+            .to_vec();
+        for chunk in completion_body(content).as_bytes().chunks(chunk_size) {
+            response.extend_from_slice(format!("{:x}\r\n", chunk.len()).as_bytes());
+            response.extend_from_slice(chunk);
+            response.extend_from_slice(b"\r\n");
+        }
+        response.extend_from_slice(b"0\r\n\r\n");
+        Self::raw(&response)
+    }
+
+    /// A stream of server-sent events, one chat completion chunk per delta, as sent with
+    /// `"stream": true`.
+    pub fn sse(deltas: &[&str]) -> Self {
+        let mut response =
+            "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n"
+                .to_owned();
+        for delta in deltas {
+            response.push_str(&format!(
+                "data: {{\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"created\":1,\
+                 \"model\":\"gpt-3.5-turbo\",\"choices\":[{{\"index\":0,\"delta\":{{\"content\":\
+                 \"{}\"}},\"finish_reason\":null}}]}}\n\n",
+                delta
+            ));
+        }
+        response.push_str("data: [DONE]\n\n");
+        Self::raw(response.as_bytes())
+    }
+
+    /// An error envelope of the API, with the status `status`.
+    pub fn error(status: u16, kind: &str, message: &str) -> Self {
+        Self::json(
+            status,
+            &format!(
+                "{{\"error\":{{\"message\":\"{}\",\"type\":\"{}\",\"param\":null,\"code\":null}}}}",
+                message, kind
+            ),
+        )
+    }
+
+    /// Wait `delay` before answering.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Close the connection after sending `bytes` bytes of the reply.
+    pub fn cut_after(mut self, bytes: usize) -> Self {
+        self.cut_after = Some(bytes);
+        self
+    }
+
+    /// Stop for `pause` after sending `bytes` bytes of the reply, keeping the connection open.
+    pub fn pause_after(mut self, bytes: usize, pause: Duration) -> Self {
+        self.pause = Some((bytes, pause));
+        self
+    }
+
+    /// Keep the connection open, and answer the next request on it with `next`, e.g. once a
+    /// proxy tunnel is open.
+    pub fn followed_by(mut self, next: Reply) -> Self {
+        self.next = Some(Box::new(next));
+        self
+    }
+}
+
+/// The body of a chat completion answering `content`.
+pub fn completion_body(content: &str) -> String {
+    let content = content.replace('\\', "\\\\").replace('"', "\\\"");
+    format!(
+        "{{\"id\":\"chatcmpl-1\",\"object\":\"chat.completion\",\"created\":1,\
+         \"model\":\"gpt-3.5-turbo\",\"choices\":[{{\"index\":0,\"message\":{{\"role\":\
+         \"assistant\",\"content\":\"{}\"}},\"logprobs\":null,\"finish_reason\":\"stop\"}}],\
+         \"usage\":{{\"prompt_tokens\":1,\"completion_tokens\":1,\"total_tokens\":2}}}}",
+        content
+    )
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+/// A request received by the fake server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recorded {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Recorded {
+    /// The value of the header `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// A server answering each connection with the next scripted [`Reply`]. Connections are refused
+/// once the replies run out.
+pub struct FakeOpenAi {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<Recorded>>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FakeOpenAi {
+    pub fn start<I: IntoIterator<Item = Reply>>(replies: I) -> Self {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).expect("bind the fake server");
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let replies: Vec<Reply> = replies.into_iter().collect();
+
+        let recorded = Arc::clone(&requests);
+        let thread = thread::spawn(move || {
+            for reply in replies {
+                let Ok((mut stream, _)) = listener.accept() else {
+                    return;
+                };
+                let mut reply = Some(reply);
+                while let Some(current) = reply {
+                    // the client may close the connection without a request, e.g. on a
+                    // rejected certificate
+                    let Some(request) = read_request(&mut stream) else {
+                        break;
+                    };
+                    recorded.lock().unwrap().push(request);
+                    thread::sleep(current.delay);
+                    let end = current.cut_after.unwrap_or(current.bytes.len());
+                    let (before, after) = match current.pause {
+                        Some((at, pause)) if at < end => {
+                            (&current.bytes[..at], Some((pause, &current.bytes[at..end])))
+                        }
+                        _ => (&current.bytes[..end], None),
+                    };
+                    // the client may have given up already
+                    let _ = stream.write_all(before);
+                    if let Some((pause, after)) = after {
+                        thread::sleep(pause);
+                        let _ = stream.write_all(after);
+                    }
+                    if current.cut_after.is_some() {
+                        break;
+                    }
+                    reply = current.next.map(|next| *next);
+                }
+            }
+        });
+
+        Self {
+            addr,
+            requests,
+            thread: Some(thread),
+        }
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The requests received so far, in order.
+    pub fn requests(&self) -> Vec<Recorded> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// A network sending every connection to this server.
+    pub fn network(&self) -> HostNetwork {
+        HostNetwork::new(self.addr)
+    }
+}
+
+impl Drop for FakeOpenAi {
+    fn drop(&mut self) {
+        // a server still waiting for a connection is left behind, so as not to hang the test
+        if let Some(thread) = self.thread.take() {
+            if thread.is_finished() {
+                thread.join().expect("the fake server panicked");
+            }
+        }
+    }
+}
+
+/// Read a request, with its body if it has a `Content-Length`.
+fn read_request(stream: &mut TcpStream) -> Option<Recorded> {
+    stream.set_read_timeout(Some(Duration::from_secs(5))).ok()?;
+    let mut data = Vec::new();
+    let mut buf = [0u8; 4096];
+    let head_end = loop {
+        if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end;
+        }
+        let read = stream.read(&mut buf).ok()?;
+        if read == 0 {
+            return None;
+        }
+        data.extend_from_slice(&buf[..read]);
+    };
+
+    let head = String::from_utf8_lossy(&data[..head_end]).into_owned();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split(' ');
+    let method = request_line.next()?.to_owned();
+    let path = request_line.next()?.to_owned();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_owned(), value.trim().to_owned()))
+        .collect();
+
+    let length = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut body = data[head_end + 4..].to_vec();
+    while body.len() < length {
+        let read = stream.read(&mut buf).ok()?;
+        if read == 0 {
+            break;
+        }
+        body.extend_from_slice(&buf[..read]);
+    }
+
+    Some(Recorded {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    })
+}
+
+/// The time since the clock was created.
+#[derive(Debug, Clone, Copy)]
+pub struct HostClock {
+    start: Instant,
+}
+
+impl Default for HostClock {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock for HostClock {
+    fn now(&self) -> i64 {
+        self.start.elapsed().as_micros() as i64
+    }
+}
+
+/// The network of the host, sending every connection to one server, whatever the address asked
+/// for. TLS sessions are not encrypted.
+#[derive(Debug, Clone)]
+pub struct HostNetwork {
+    server: SocketAddr,
+    connections: Arc<Mutex<Vec<SocketAddr>>>,
+    rejected: Vec<Vec<u8>>,
+}
+
+impl HostNetwork {
+    pub fn new(server: SocketAddr) -> Self {
+        Self {
+            server,
+            connections: Arc::default(),
+            rejected: Vec::new(),
+        }
+    }
+
+    /// Reject the server certificate when `anchor` is the trust anchor.
+    pub fn rejecting(mut self, anchor: &[u8]) -> Self {
+        self.rejected.push(anchor.to_vec());
+        self
+    }
+
+    /// The addresses asked for so far, in order.
+    pub fn connections(&self) -> Vec<SocketAddr> {
+        self.connections.lock().unwrap().clone()
+    }
+}
+
+impl Network for HostNetwork {
+    type Socket = HostSocket;
+
+    fn connect(&mut self, addr: SocketAddr, timeout: Duration) -> Result<HostSocket, String> {
+        self.connections.lock().unwrap().push(addr);
+        let stream =
+            TcpStream::connect_timeout(&self.server, timeout).map_err(|e| e.to_string())?;
+        let mut socket = HostSocket(stream);
+        socket.set_timeout(timeout)?;
+        Ok(socket)
+    }
+
+    fn with_tls<R, F>(
+        &mut self,
+        mut socket: HostSocket,
+        _host: &str,
+        trust_anchor: Option<TrustAnchor>,
+        exchange: F,
+    ) -> Result<R, HandshakeError>
+    where
+        F: FnOnce(&mut dyn Socket) -> R,
+    {
+        if trust_anchor.is_some_and(|anchor| self.rejected.iter().any(|r| r == anchor.as_ref())) {
+            return Err(HandshakeError::Rejected("InvalidCertificate".to_owned()));
+        }
+        Ok(exchange(&mut socket))
+    }
+}
+
+/// A TCP connection of the host.
+#[derive(Debug)]
+pub struct HostSocket(TcpStream);
+
+impl Socket for HostSocket {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, String> {
+        self.0.read(buf).map_err(|e| e.to_string())
+    }
+
+    fn write(&mut self, buf: &[u8]) -> Result<usize, String> {
+        self.0.write(buf).map_err(|e| e.to_string())
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        self.0.flush().map_err(|e| e.to_string())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), String> {
+        // a zero timeout would mean no timeout at all
+        let timeout = Some(timeout.max(Duration::from_millis(1)));
+        self.0
+            .set_read_timeout(timeout)
+            .and_then(|_| self.0.set_write_timeout(timeout))
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Knows no hostname: only the static hosts resolve.
+pub struct NoLookup;
+
+impl ResolveHostname for NoLookup {
+    type Error = ();
+
+    fn resolve_hostname(&mut self, _hostname: &str) -> Result<SocketAddr, ()> {
+        Err(())
+    }
+}
+
+/// A resolver knowing the API host, and nothing else.
+pub fn resolver() -> Box<CachingResolver<NoLookup, HostClock>> {
+    let mut resolver = CachingResolver::with_clock(NoLookup, DEFAULT_TTL, HostClock::default());
+    resolver.add_host(OPENAI_API_HOST, Ipv4Addr::new(10, 0, 0, 1));
+    Box::new(resolver)
+}
+
+/// A context for the API, with short timeouts so that stalls are noticed quickly.
+pub fn context() -> OpenAiContext {
+    OpenAiContext::new(resolver(), "sk-test")
+        .expect("the API host is static")
+        .with_timeouts(Timeouts {
+            connect: Duration::from_secs(2),
+            first_byte: Duration::from_millis(300),
+            total: Duration::from_secs(2),
+        })
+}
@@ -0,0 +1,450 @@
+//! The OpenAI client against a fake server on the loopback interface.
+
+mod common;
+
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use chat_gpsp_core::{
+    fs::data_path,
+    openai::{
+        constants::MAX_TOOL_ROUNDS,
+        proxy::{Proxy, ProxyMode},
+        relay::Relay,
+        timeout::{TimeoutKind, Timeouts},
+        tls::{TlsVerification, BUNDLED_ROOTS},
+        tools::{ToolRegistry, NOTES_FILE},
+        types::ResponseFormat,
+        OpenAi, OpenAiContext, OpenAiError,
+    },
+    platform::{
+        fake::{FakeDevice, MemoryFs},
+        Battery,
+    },
+};
+use common::{context, resolver, FakeOpenAi, HostClock, Reply};
+
+fn client(server: &FakeOpenAi, context: &OpenAiContext) -> OpenAi<common::HostNetwork, HostClock> {
+    OpenAi::with_platform(context, server.network(), HostClock::default()).unwrap()
+}
+
+#[test]
+fn sends_the_conversation_and_reads_the_answer() {
+    let server = FakeOpenAi::start([Reply::completion("Paris.")]);
+    let network = server.network();
+    let mut openai =
+        OpenAi::with_platform(&context(), network.clone(), HostClock::default()).unwrap();
+
+    assert_eq!(
+        openai.ask_gpt("Capital of France?"),
+        Ok("Paris.".to_owned())
+    );
+
+    assert_eq!(network.connections(), ["10.0.0.1:443".parse().unwrap()]);
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    let request = &requests[0];
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.path, "/v1/chat/completions");
+    assert_eq!(request.header("Host"), Some("api.openai.com"));
+    assert_eq!(request.header("Authorization"), Some("Bearer sk-test"));
+    assert_eq!(request.header("Content-Type"), Some("application/json"));
+    assert_eq!(
+        request.header("Content-Length"),
+        Some(request.body.len().to_string().as_str())
+    );
+    assert!(request.body.contains(r#""model": "gpt-3.5-turbo""#));
+    assert!(request
+        .body
+        .contains(r#"{"role": "user", "content": "Capital of France?"}"#));
+}
+
+#[test]
+fn keeps_the_conversation_between_requests() {
+    let server = FakeOpenAi::start([Reply::completion("Paris."), Reply::completion("Lyon.")]);
+    let mut openai = client(&server, &context());
+
+    openai.ask_gpt("Capital of France?").unwrap();
+    assert_eq!(
+        openai.ask_gpt("And the second city?"),
+        Ok("Lyon.".to_owned())
+    );
+
+    let body = &server.requests()[1].body;
+    assert!(body.contains(r#"{"role": "assistant", "content": "Paris."}"#));
+    assert!(body.contains(r#"{"role": "user", "content": "And the second city?"}"#));
+    assert_eq!(openai.history().messages().len(), 4);
+}
+
+#[test]
+fn decodes_chunked_answers() {
+    let server = FakeOpenAi::start([Reply::chunked("Paris, on the Seine.", 7)]);
+    let mut openai = client(&server, &context());
+
+    assert_eq!(
+        openai.ask_gpt("Capital of France?"),
+        Ok("Paris, on the Seine.".to_owned())
+    );
+}
+
+#[test]
+fn reports_api_errors() {
+    let server = FakeOpenAi::start([
+        Reply::error(401, "invalid_request_error", "Incorrect API key provided"),
+        Reply::error(429, "rate_limit_exceeded", "Rate limit reached"),
+        Reply::error(500, "server_error", "The server had an error"),
+    ]);
+    let mut openai = client(&server, &context());
+
+    for _ in 0..3 {
+        assert_eq!(
+            openai.ask_gpt("Capital of France?"),
+            Err(OpenAiError::ResponseCodeNotOk)
+        );
+    }
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[test]
+fn streamed_answers_are_not_understood() {
+    // the client does not ask for a stream, so one is a protocol error
+    let server = FakeOpenAi::start([Reply::sse(&["Par", "is."])]);
+    let mut openai = client(&server, &context());
+
+    assert!(matches!(
+        openai.ask_gpt("Capital of France?"),
+        Err(OpenAiError::UnparsableResponseBody(_))
+    ));
+}
+
+#[test]
+fn stalls_are_timeouts() {
+    let server =
+        FakeOpenAi::start([Reply::completion("Paris.").with_delay(Duration::from_millis(800))]);
+    let mut openai = client(&server, &context());
+
+    assert_eq!(
+        openai.ask_gpt("Capital of France?"),
+        Err(OpenAiError::Timeout(TimeoutKind::FirstByte))
+    );
+}
+
+#[test]
+fn stalled_tunnels_are_connect_timeouts() {
+    // the proxy accepts the connection, and never opens the tunnel in time
+    let server = FakeOpenAi::start([Reply::raw(b"HTTP/1.1 200 Connection established\r\n\r\n")
+        .with_delay(Duration::from_millis(800))]);
+    let mut context = context().with_timeouts(Timeouts {
+        connect: Duration::from_millis(300),
+        first_byte: Duration::from_millis(300),
+        total: Duration::from_secs(5),
+    });
+    context
+        .set_proxy(Some(Proxy {
+            host: "192.168.1.3".to_owned(),
+            port: 3128,
+            mode: ProxyMode::Connect,
+            credentials: None,
+        }))
+        .unwrap();
+    let mut openai = client(&server, &context);
+
+    assert_eq!(
+        openai.ask_gpt("Capital of France?"),
+        Err(OpenAiError::Timeout(TimeoutKind::Connect))
+    );
+}
+
+#[test]
+fn stalls_mid_answer_are_total_timeouts() {
+    let timeouts = Timeouts {
+        connect: Duration::from_secs(1),
+        first_byte: Duration::from_millis(300),
+        total: Duration::from_millis(800),
+    };
+
+    // the first byte comes in time, the rest after the total timeout
+    let server =
+        FakeOpenAi::start([Reply::completion("Paris.").pause_after(20, Duration::from_secs(2))]);
+    let mut openai = client(&server, &context().with_timeouts(timeouts));
+    assert_eq!(
+        openai.ask_gpt("Capital of France?"),
+        Err(OpenAiError::Timeout(TimeoutKind::Total))
+    );
+
+    // a pause longer than the first-byte timeout is fine, within the total one
+    let server =
+        FakeOpenAi::start(
+            [Reply::completion("Paris.").pause_after(20, Duration::from_millis(400))],
+        );
+    let mut openai = client(&server, &context().with_timeouts(timeouts));
+    assert_eq!(
+        openai.ask_gpt("Capital of France?"),
+        Ok("Paris.".to_owned())
+    );
+}
+
+#[test]
+fn disconnections_mid_answer_are_partial() {
+    let server = FakeOpenAi::start([
+        Reply::completion("Paris.").cut_after(100),
+        Reply::chunked("Paris.", 16).cut_after(150),
+        Reply::raw(b""),
+    ]);
+    let mut openai = client(&server, &context());
+
+    for _ in 0..2 {
+        assert!(matches!(
+            openai.ask_gpt("Capital of France?"),
+            Err(OpenAiError::PartialResponse(_))
+        ));
+    }
+    // closed before the status line
+    assert!(matches!(
+        openai.ask_gpt("Capital of France?"),
+        Err(OpenAiError::UnparsableResponseCode(_) | OpenAiError::PartialResponse(_))
+    ));
+}
+
+#[test]
+fn retries_with_the_next_root() {
+    let server = FakeOpenAi::start([Reply::raw(b""), Reply::completion("Paris.")]);
+    let network = server.network().rejecting(BUNDLED_ROOTS[0]);
+    let mut openai =
+        OpenAi::with_platform(&context(), network.clone(), HostClock::default()).unwrap();
+
+    assert_eq!(
+        openai.ask_gpt("Capital of France?"),
+        Ok("Paris.".to_owned())
+    );
+    assert_eq!(network.connections().len(), 2);
+    // the rejected connection carried no request
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[test]
+fn rejected_pins_are_reported() {
+    let pin = b"not a real certificate".to_vec();
+    let server = FakeOpenAi::start([Reply::raw(b"")]);
+    let network = server.network().rejecting(&pin);
+    let mut context = context();
+    context.set_tls_verification(TlsVerification::Pinned(pin));
+    let mut openai = OpenAi::with_platform(&context, network, HostClock::default()).unwrap();
+
+    assert!(matches!(
+        openai.ask_gpt("Capital of France?"),
+        Err(OpenAiError::CertificateRejected(_))
+    ));
+}
+
+#[test]
+fn tunnels_through_a_proxy() {
+    let server = FakeOpenAi::start([Reply::raw(b"HTTP/1.1 200 Connection established\r\n\r\n")
+        .followed_by(Reply::completion("Paris."))]);
+    let network = server.network();
+    let mut context = context();
+    context
+        .set_proxy(Some(Proxy {
+            host: "192.168.1.3".to_owned(),
+            port: 3128,
+            mode: ProxyMode::Connect,
+            credentials: None,
+        }))
+        .unwrap();
+    let mut openai =
+        OpenAi::with_platform(&context, network.clone(), HostClock::default()).unwrap();
+
+    assert_eq!(
+        openai.ask_gpt("Capital of France?"),
+        Ok("Paris.".to_owned())
+    );
+
+    assert_eq!(network.connections(), ["192.168.1.3:3128".parse().unwrap()]);
+    let requests = server.requests();
+    assert_eq!(requests[0].method, "CONNECT");
+    assert_eq!(requests[0].path, "api.openai.com:443");
+    assert_eq!(requests[1].path, "/v1/chat/completions");
+}
+
+#[test]
+fn forwards_plain_requests_to_a_proxy() {
+    let server = FakeOpenAi::start([Reply::completion("Paris.")]);
+    let mut context = context();
+    context
+        .set_proxy(Some(Proxy {
+            host: "192.168.1.3".to_owned(),
+            port: 8080,
+            mode: ProxyMode::Forward,
+            credentials: Some("user:password".to_owned()),
+        }))
+        .unwrap();
+    let mut openai = client(&server, &context);
+
+    assert_eq!(
+        openai.ask_gpt("Capital of France?"),
+        Ok("Paris.".to_owned())
+    );
+
+    let request = &server.requests()[0];
+    assert_eq!(request.path, "http://api.openai.com/v1/chat/completions");
+    assert_eq!(
+        request.header("Proxy-Authorization"),
+        Some("Basic dXNlcjpwYXNzd29yZA==")
+    );
+    assert_eq!(request.header("Authorization"), Some("Bearer sk-test"));
+}
+
+#[test]
+fn goes_through_the_relay() {
+    let server = FakeOpenAi::start([
+        Reply::raw(b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\nParis."),
+        Reply::raw(b"HTTP/1.1 502 Bad Gateway\r\nConnection: close\r\n\r\nupstream down"),
+    ]);
+    let relay = Relay {
+        host: "192.168.1.2".to_owned(),
+        port: 8080,
+        secret: "s3cret".to_owned(),
+    };
+    let context = OpenAiContext::with_relay(resolver(), relay).unwrap();
+    let mut openai = client(&server, &context);
+
+    assert_eq!(
+        openai.ask_gpt("Capital of France?"),
+        Ok("Paris.".to_owned())
+    );
+    assert_eq!(
+        openai.ask_gpt("And Spain?"),
+        Err(OpenAiError::Relay("502: upstream down".to_owned()))
+    );
+
+    let request = &server.requests()[0];
+    assert_eq!(request.header("Authorization"), None);
+    assert!(request.body.ends_with("u Capital of France?\n"));
+}
+
+#[test]
+fn refused_connections_are_reported() {
+    let server = FakeOpenAi::start([]);
+    let mut openai = client(&server, &context());
+
+    assert!(matches!(
+        openai.ask_gpt("Capital of France?"),
+        Err(OpenAiError::TlsError(_))
+    ));
+}
+
+#[test]
+fn calls_tools_until_the_model_answers() {
+    let server = FakeOpenAi::start([
+        Reply::tool_calls(&[
+            ("call_1", "get_battery_level", "{}"),
+            ("call_2", "search_notes", r#"{"query":"charger"}"#),
+        ]),
+        Reply::completion("42%, and the charger is in the drawer."),
+    ]);
+    let files = MemoryFs::default().with_file(
+        &data_path(NOTES_FILE),
+        b"The charger is in the drawer\nBuy milk\n",
+    );
+    let device = FakeDevice {
+        battery: Some(Battery {
+            percent: 42,
+            charging: false,
+        }),
+        ..FakeDevice::default()
+    };
+    let mut tools = ToolRegistry::on_device(device, files);
+    let mut openai = client(&server, &context());
+
+    assert_eq!(
+        openai.ask_gpt_with_tools("Battery, and where is my charger?", &mut tools),
+        Ok("42%, and the charger is in the drawer.".to_owned())
+    );
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[0]
+        .body
+        .contains(r#""function": {"name": "search_notes""#));
+    let body = &requests[1].body;
+    assert!(body.contains(r#""tool_calls": [{"id": "call_1""#));
+    assert!(body.contains(r#"{"role": "tool", "tool_call_id": "call_1", "content": "42%"}"#));
+    assert!(body.contains(
+        r#"{"role": "tool", "tool_call_id": "call_2", "content": "The charger is in the drawer"}"#
+    ));
+    // the question, the calls, their two results and the answer
+    assert_eq!(openai.history().messages().len(), 5);
+}
+
+#[test]
+fn gives_up_on_endless_tool_calls() {
+    let server = FakeOpenAi::start(
+        (0..MAX_TOOL_ROUNDS).map(|_| Reply::tool_calls(&[("call", "get_current_time", "{}")])),
+    );
+    let mut tools = ToolRegistry::on_device(FakeDevice::default(), MemoryFs::default());
+    let mut openai = client(&server, &context());
+
+    assert_eq!(
+        openai.ask_gpt_with_tools("What time is it?", &mut tools),
+        Err(OpenAiError::TooManyToolRounds)
+    );
+    assert_eq!(server.requests().len(), MAX_TOOL_ROUNDS);
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct FlashCard {
+    question: heapless::String<64>,
+    answer: heapless::String<64>,
+}
+
+fn flash_card_format() -> ResponseFormat {
+    ResponseFormat::JsonSchema {
+        name: "flash_card".to_owned(),
+        schema: r#"{"type": "object", "properties": {"question": {"type": "string"}, "answer": {"type": "string"}}, "required": ["question", "answer"], "additionalProperties": false}"#.to_owned(),
+        strict: true,
+    }
+}
+
+#[test]
+fn deserializes_structured_answers() {
+    let server = FakeOpenAi::start([
+        Reply::completion(r#"{"question": "Capital of \"France\"?", "answer": "Paris"}"#),
+        Reply::completion("Paris, again."),
+    ]);
+    let mut openai = client(&server, &context());
+
+    assert_eq!(
+        openai.ask_gpt_json::<FlashCard>("A flash card about France.", flash_card_format()),
+        Ok(FlashCard {
+            question: "Capital of \"France\"?".try_into().unwrap(),
+            answer: "Paris".try_into().unwrap(),
+        })
+    );
+    // the format only applies to its prompt
+    openai.ask_gpt("And its capital?").unwrap();
+
+    let requests = server.requests();
+    assert!(requests[0].body.contains(
+        r#""response_format": {"type": "json_schema", "json_schema": {"name": "flash_card""#
+    ));
+    assert!(!requests[1].body.contains("response_format"));
+}
+
+#[test]
+fn reports_answers_not_matching_the_schema() {
+    let server = FakeOpenAi::start([
+        Reply::completion(r#"{"question": "Capital of France?"}"#),
+        Reply::completion("I cannot answer in JSON."),
+    ]);
+    let mut openai = client(&server, &context());
+
+    for _ in 0..2 {
+        assert!(matches!(
+            openai.ask_gpt_json::<FlashCard>("A flash card, in JSON.", ResponseFormat::JsonObject),
+            Err(OpenAiError::SchemaMismatch(_))
+        ));
+    }
+    // the answers stay in the conversation
+    assert_eq!(openai.history().messages().len(), 4);
+}
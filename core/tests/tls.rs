@@ -0,0 +1,202 @@
+//! TLS handshakes of the client against a real server on the loopback interface, with a
+//! self-signed certificate.
+
+use std::{
+    io::{Read as _, Write as _},
+    net::{TcpListener, TcpStream},
+    sync::Arc,
+    thread::{self, JoinHandle},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use embedded_io::{ErrorKind, ErrorType, Read, Write};
+use embedded_tls::TlsClock;
+use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+use rcgen::CertifiedKey;
+use rustls::{
+    pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer},
+    ServerConfig, ServerConnection, StreamOwned,
+};
+use sha2::{Digest, Sha256};
+
+use chat_gpsp_core::openai::{
+    constants::OPENAI_API_HOST,
+    tls::{
+        entropy::EntropyPool,
+        pin::subject_public_key_info,
+        session::{self, is_certificate_error},
+        TrustAnchor,
+    },
+};
+
+/// Size of the record buffers, enough for the largest TLS record.
+const RECORD_BUFFER_SIZE: usize = 16 * 1024 + 256;
+
+/// The time of the host.
+struct HostClock;
+
+impl TlsClock for HostClock {
+    fn now() -> Option<u64> {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|now| now.as_secs())
+    }
+}
+
+/// A TCP connection of the host, as the transport of a session.
+struct Transport(TcpStream);
+
+impl ErrorType for Transport {
+    type Error = ErrorKind;
+}
+
+impl Read for Transport {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, ErrorKind> {
+        self.0.read(buf).map_err(|_| ErrorKind::Other)
+    }
+}
+
+impl Write for Transport {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, ErrorKind> {
+        self.0.write(buf).map_err(|_| ErrorKind::Other)
+    }
+
+    fn flush(&mut self) -> Result<(), ErrorKind> {
+        self.0.flush().map_err(|_| ErrorKind::Other)
+    }
+}
+
+/// A self-signed certificate for the API host.
+fn certificate() -> CertifiedKey<rcgen::KeyPair> {
+    rcgen::generate_simple_self_signed(vec![OPENAI_API_HOST.to_owned()]).unwrap()
+}
+
+/// The SHA-256 digest of the public key of `cert`.
+fn key_pin(cert: &CertifiedKey<rcgen::KeyPair>) -> [u8; 32] {
+    Sha256::digest(subject_public_key_info(cert.cert.der()).unwrap()).into()
+}
+
+/// A server answering one connection with `cert`: it echoes the first line it receives.
+///
+/// # Returns
+/// Its address, and its thread, telling whether the handshake succeeded.
+fn serve(cert: &CertifiedKey<rcgen::KeyPair>) -> (std::net::SocketAddr, JoinHandle<bool>) {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(cert.signing_key.serialize_der()));
+    let config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(vec![CertificateDer::from(cert.cert.der().to_vec())], key)
+        .unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let connection = ServerConnection::new(Arc::new(config)).unwrap();
+        let mut tls = StreamOwned::new(connection, stream);
+
+        let mut line = Vec::new();
+        let mut byte = [0];
+        while !line.ends_with(b"\n") {
+            match tls.read(&mut byte) {
+                Ok(1) => line.push(byte[0]),
+                // the client hung up, e.g. after rejecting the certificate
+                _ => return false,
+            }
+        }
+        tls.write_all(&line).is_ok() && tls.flush().is_ok()
+    });
+
+    (addr, server)
+}
+
+/// Open a session with the server at `addr` trusting `anchor`, and exchange a line with it.
+///
+/// # Returns
+/// The line echoed by the server, or the error of the handshake.
+fn exchange(
+    addr: std::net::SocketAddr,
+    anchor: TrustAnchor,
+) -> Result<Vec<u8>, embedded_tls::TlsError> {
+    let entropy = EntropyPool::new();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    entropy.add(&now.as_nanos().to_le_bytes());
+    let rng = ChaCha20Rng::from_seed(entropy.seed());
+
+    let stream = TcpStream::connect(addr).unwrap();
+    let mut read_buf = vec![0; RECORD_BUFFER_SIZE];
+    let mut write_buf = vec![0; RECORD_BUFFER_SIZE];
+    let mut connection = session::open::<_, HostClock, _>(
+        Transport(stream),
+        OPENAI_API_HOST,
+        Some(anchor),
+        rng,
+        &mut read_buf,
+        &mut write_buf,
+    )?;
+
+    connection.write_all(b"ping\n").unwrap();
+    connection.flush().unwrap();
+    let mut echoed = vec![0; 5];
+    connection.read_exact(&mut echoed).unwrap();
+    Ok(echoed)
+}
+
+#[test]
+fn trusts_the_pinned_certificate() {
+    let cert = certificate();
+    let (addr, server) = serve(&cert);
+
+    let echoed = exchange(addr, TrustAnchor::Certificate(cert.cert.der()));
+    assert_eq!(echoed.unwrap(), b"ping\n");
+    assert!(server.join().unwrap());
+}
+
+#[test]
+fn rejects_another_certificate() {
+    let (cert, other) = (certificate(), certificate());
+    let (addr, server) = serve(&cert);
+
+    let error = exchange(addr, TrustAnchor::Certificate(other.cert.der())).unwrap_err();
+    assert!(is_certificate_error(&error), "{:?}", error);
+    assert!(!server.join().unwrap());
+}
+
+#[test]
+fn trusts_the_pinned_key() {
+    let cert = certificate();
+    let (addr, server) = serve(&cert);
+
+    let echoed = exchange(addr, TrustAnchor::PublicKey(&key_pin(&cert)));
+    assert_eq!(echoed.unwrap(), b"ping\n");
+    assert!(server.join().unwrap());
+}
+
+#[test]
+fn rejects_another_key() {
+    let (cert, other) = (certificate(), certificate());
+    let (addr, server) = serve(&cert);
+
+    let error = exchange(addr, TrustAnchor::PublicKey(&key_pin(&other))).unwrap_err();
+    assert!(is_certificate_error(&error), "{:?}", error);
+    assert!(!server.join().unwrap());
+}
+
+#[test]
+fn pinned_keys_ignore_the_name() {
+    // a certificate for another name is rejected, unless its key is pinned
+    let cert = rcgen::generate_simple_self_signed(vec!["example.com".to_owned()]).unwrap();
+    let (addr, server) = serve(&cert);
+
+    let error = exchange(addr, TrustAnchor::Certificate(cert.cert.der())).unwrap_err();
+    assert!(is_certificate_error(&error), "{:?}", error);
+    assert!(!server.join().unwrap());
+
+    let (addr, server) = serve(&cert);
+    let echoed = exchange(addr, TrustAnchor::PublicKey(&key_pin(&cert)));
+    assert_eq!(echoed.unwrap(), b"ping\n");
+    assert!(server.join().unwrap());
+}
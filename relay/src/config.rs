@@ -0,0 +1,160 @@
+use std::{env, fmt::Display, net::SocketAddr};
+
+/// Address the relay listens on, unless `RELAY_LISTEN` is set.
+pub const DEFAULT_LISTEN: &str = "0.0.0.0:8080";
+/// Chat completions endpoint requests are forwarded to, unless `RELAY_UPSTREAM` is set.
+pub const DEFAULT_UPSTREAM: &str = "https://api.openai.com/v1/chat/completions";
+/// Model asked for, unless `RELAY_MODEL` is set.
+pub const DEFAULT_MODEL: &str = "gpt-3.5-turbo";
+/// Connections served at once, unless `RELAY_MAX_CONNECTIONS` is set.
+pub const DEFAULT_MAX_CONNECTIONS: usize = 8;
+
+/// An error in the configuration of the relay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// A required environment variable is not set, or empty.
+    Missing(&'static str),
+    /// An environment variable has an invalid value.
+    Invalid(&'static str, String),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Missing(name) => write!(f, "{} must be set", name),
+            ConfigError::Invalid(name, value) => write!(f, "invalid {}: {}", name, value),
+        }
+    }
+}
+
+/// The settings of the relay, read from the environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// `RELAY_LISTEN`: the address to listen on.
+    pub listen: SocketAddr,
+    /// `RELAY_SECRET`: the secret the PSP must send with every request.
+    pub secret: String,
+    /// `OPENAI_API_KEY`: the key used to call the upstream provider.
+    pub api_key: String,
+    /// `RELAY_UPSTREAM`: the chat completions endpoint of the upstream provider.
+    pub upstream: String,
+    /// `RELAY_MODEL`: the model asked for.
+    pub model: String,
+    /// `RELAY_MAX_CONNECTIONS`: the connections served at once. Others are refused.
+    pub max_connections: usize,
+}
+
+impl Config {
+    /// Read the configuration from the environment.
+    ///
+    /// # Errors
+    /// A [`ConfigError`] if the secret or the API key is missing, or the listen address or the
+    /// connection cap is invalid.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::from_vars(|name| env::var(name).ok())
+    }
+
+    /// Read the configuration from the variables returned by `var`.
+    ///
+    /// # Errors
+    /// Same as [`Self::from_env`].
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        // an empty variable counts as unset
+        let optional = |name: &str| var(name).filter(|value| !value.is_empty());
+        let required = |name: &'static str| optional(name).ok_or(ConfigError::Missing(name));
+
+        let listen = optional("RELAY_LISTEN").unwrap_or_else(|| DEFAULT_LISTEN.to_owned());
+        let listen = listen
+            .parse()
+            .map_err(|_| ConfigError::Invalid("RELAY_LISTEN", listen))?;
+        let max_connections = match optional("RELAY_MAX_CONNECTIONS") {
+            Some(max) => max
+                .parse()
+                .ok()
+                .filter(|max| *max > 0)
+                .ok_or(ConfigError::Invalid("RELAY_MAX_CONNECTIONS", max))?,
+            None => DEFAULT_MAX_CONNECTIONS,
+        };
+
+        Ok(Self {
+            listen,
+            secret: required("RELAY_SECRET")?,
+            api_key: required("OPENAI_API_KEY")?,
+            upstream: optional("RELAY_UPSTREAM").unwrap_or_else(|| DEFAULT_UPSTREAM.to_owned()),
+            model: optional("RELAY_MODEL").unwrap_or_else(|| DEFAULT_MODEL.to_owned()),
+            max_connections,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(vars: &[(&str, &str)]) -> Result<Config, ConfigError> {
+        Config::from_vars(|name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| (*value).to_owned())
+        })
+    }
+
+    #[test]
+    fn defaults_everything_but_the_secrets() {
+        let config = config(&[("RELAY_SECRET", "s3cret"), ("OPENAI_API_KEY", "sk-test")]).unwrap();
+        assert_eq!(config.listen, DEFAULT_LISTEN.parse().unwrap());
+        assert_eq!(config.secret, "s3cret");
+        assert_eq!(config.api_key, "sk-test");
+        assert_eq!(config.upstream, DEFAULT_UPSTREAM);
+        assert_eq!(config.model, DEFAULT_MODEL);
+        assert_eq!(config.max_connections, DEFAULT_MAX_CONNECTIONS);
+    }
+
+    #[test]
+    fn reads_every_variable() {
+        let config = config(&[
+            ("RELAY_LISTEN", "127.0.0.1:9000"),
+            ("RELAY_SECRET", "s3cret"),
+            ("OPENAI_API_KEY", "sk-test"),
+            (
+                "RELAY_UPSTREAM",
+                "http://localhost:1234/v1/chat/completions",
+            ),
+            ("RELAY_MODEL", "gpt-4o-mini"),
+            ("RELAY_MAX_CONNECTIONS", "2"),
+        ])
+        .unwrap();
+        assert_eq!(config.listen, "127.0.0.1:9000".parse().unwrap());
+        assert_eq!(config.upstream, "http://localhost:1234/v1/chat/completions");
+        assert_eq!(config.model, "gpt-4o-mini");
+        assert_eq!(config.max_connections, 2);
+    }
+
+    #[test]
+    fn empty_secrets_are_missing() {
+        assert_eq!(
+            config(&[("RELAY_SECRET", ""), ("OPENAI_API_KEY", "sk-test")]),
+            Err(ConfigError::Missing("RELAY_SECRET"))
+        );
+        assert_eq!(
+            config(&[("RELAY_SECRET", "s3cret")]),
+            Err(ConfigError::Missing("OPENAI_API_KEY"))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let secrets = [("RELAY_SECRET", "s3cret"), ("OPENAI_API_KEY", "sk-test")];
+        assert_eq!(
+            config(&[secrets[0], secrets[1], ("RELAY_LISTEN", "localhost")]),
+            Err(ConfigError::Invalid("RELAY_LISTEN", "localhost".to_owned()))
+        );
+        assert_eq!(
+            config(&[secrets[0], secrets[1], ("RELAY_MAX_CONNECTIONS", "0")]),
+            Err(ConfigError::Invalid(
+                "RELAY_MAX_CONNECTIONS",
+                "0".to_owned()
+            ))
+        );
+    }
+}
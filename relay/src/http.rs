@@ -0,0 +1,272 @@
+use std::{
+    fmt::Display,
+    io::{self, BufRead, BufReader, Read, Write},
+};
+
+/// Largest request head accepted, in bytes.
+const MAX_HEAD_LENGTH: usize = 16 * 1024;
+/// Largest request body accepted, in bytes.
+const MAX_BODY_LENGTH: usize = 256 * 1024;
+
+/// An error that can occur while reading a request.
+#[derive(Debug)]
+pub enum HttpError {
+    Io(io::Error),
+    /// The request is malformed.
+    BadRequest(String),
+    /// The request head or body is too large.
+    TooLarge,
+    /// The peer sent nothing for longer than the read timeout of the stream.
+    TimedOut,
+}
+
+impl Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HttpError::Io(e) => write!(f, "{}", e),
+            HttpError::BadRequest(message) => write!(f, "bad request: {}", message),
+            HttpError::TooLarge => write!(f, "request too large"),
+            HttpError::TimedOut => write!(f, "request timed out"),
+        }
+    }
+}
+
+impl From<io::Error> for HttpError {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            // a read timeout, depending on the platform
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => HttpError::TimedOut,
+            _ => HttpError::Io(e),
+        }
+    }
+}
+
+/// An HTTP request, with its whole body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// The value of the header `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Read a request from `stream`. The body is read as long as announced by `Content-Length`.
+///
+/// Set a read timeout on `stream`, or a peer that stops sending holds the connection forever.
+///
+/// # Errors
+/// A [`HttpError`] if the stream fails, or the request is malformed or too large.
+pub fn read_request<R: Read>(stream: R) -> Result<Request, HttpError> {
+    let mut reader = BufReader::new(stream);
+    let mut head_length = 0;
+    let mut read_line = |reader: &mut BufReader<R>| -> Result<String, HttpError> {
+        let mut line = String::new();
+        let read = reader
+            .by_ref()
+            .take((MAX_HEAD_LENGTH - head_length) as u64)
+            .read_line(&mut line)?;
+        head_length += read;
+        if !line.ends_with('\n') {
+            return Err(if head_length >= MAX_HEAD_LENGTH {
+                HttpError::TooLarge
+            } else {
+                HttpError::BadRequest("unexpected end of request".to_owned())
+            });
+        }
+        Ok(line.trim_end_matches(['\r', '\n']).to_owned())
+    };
+
+    let request_line = read_line(&mut reader)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path), Some(_version)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(HttpError::BadRequest(format!(
+            "malformed request line: {}",
+            request_line
+        )));
+    };
+    let (method, path) = (method.to_owned(), path.to_owned());
+
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(&mut reader)?;
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| HttpError::BadRequest(format!("malformed header: {}", line)))?;
+        headers.push((name.trim().to_owned(), value.trim().to_owned()));
+    }
+
+    let mut request = Request {
+        method,
+        path,
+        headers,
+        body: Vec::new(),
+    };
+
+    let length = match request.header("Content-Length") {
+        Some(length) => length
+            .parse::<usize>()
+            .map_err(|_| HttpError::BadRequest(format!("invalid Content-Length: {}", length)))?,
+        None => 0,
+    };
+    if length > MAX_BODY_LENGTH {
+        return Err(HttpError::TooLarge);
+    }
+    request.body.resize(length, 0);
+    reader.read_exact(&mut request.body)?;
+
+    Ok(request)
+}
+
+/// Send a whole plain text response, and let the connection close.
+pub fn write_response<W: Write>(stream: &mut W, status: u16, message: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        message.len(),
+        message
+    )?;
+    stream.flush()
+}
+
+/// Start a streamed plain text response. The body is whatever is written next, until the
+/// connection closes.
+pub fn write_stream_head<W: Write>(stream: &mut W) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nConnection: close\r\n\r\n"
+    )?;
+    stream.flush()
+}
+
+/// The reason phrase of the status codes the relay answers with.
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        413 => "Payload Too Large",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        _ => "Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads `data`, then fails as a stream whose read timeout expired.
+    struct Stalling<'a> {
+        data: &'a [u8],
+    }
+
+    impl Read for Stalling<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.data.is_empty() {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            self.data.read(buf)
+        }
+    }
+
+    #[test]
+    fn reads_the_head_and_the_body() {
+        let request = read_request(
+            &b"POST /chat HTTP/1.1\r\nHost: relay\r\nx-relay-secret:  s3cret \r\nContent-Length: 7\r\n\r\nu Hello"[..],
+        )
+        .unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/chat");
+        assert_eq!(request.header("X-Relay-Secret"), Some("s3cret"));
+        assert_eq!(request.header("Accept"), None);
+        assert_eq!(request.body, b"u Hello");
+    }
+
+    #[test]
+    fn bodies_need_a_length() {
+        let request = read_request(&b"GET / HTTP/1.1\n\nignored"[..]).unwrap();
+        assert_eq!(request.method, "GET");
+        assert!(request.body.is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_requests() {
+        let malformed: [&[u8]; 5] = [
+            b"POST /chat\r\n\r\n",
+            b"POST /chat HTTP/1.1\r\nno colon\r\n\r\n",
+            b"POST /chat HTTP/1.1\r\nContent-Length: -1\r\n\r\n",
+            // cut before the end of the head
+            b"POST /chat HTTP/1.1\r\nHost: relay",
+            b"",
+        ];
+        for request in malformed {
+            assert!(matches!(
+                read_request(request),
+                Err(HttpError::BadRequest(_))
+            ));
+        }
+
+        // cut before the end of the body
+        let request = b"POST /chat HTTP/1.1\r\nContent-Length: 10\r\n\r\nu Hi";
+        assert!(matches!(read_request(&request[..]), Err(HttpError::Io(_))));
+    }
+
+    #[test]
+    fn rejects_large_requests() {
+        let mut head = b"POST /chat HTTP/1.1\r\nX-Padding: ".to_vec();
+        head.resize(MAX_HEAD_LENGTH + 1, b'a');
+        assert!(matches!(read_request(&head[..]), Err(HttpError::TooLarge)));
+
+        let request = format!(
+            "POST /chat HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY_LENGTH + 1
+        );
+        assert!(matches!(
+            read_request(request.as_bytes()),
+            Err(HttpError::TooLarge)
+        ));
+    }
+
+    #[test]
+    fn stalled_peers_time_out() {
+        let stalled: [&[u8]; 2] = [
+            b"POST /chat HTTP/1.1\r\nHost",
+            b"POST /chat HTTP/1.1\r\nContent-Length: 7\r\n\r\nu H",
+        ];
+        for data in stalled {
+            assert!(matches!(
+                read_request(Stalling { data }),
+                Err(HttpError::TimedOut)
+            ));
+        }
+    }
+
+    #[test]
+    fn writes_responses() {
+        let mut response = Vec::new();
+        write_response(&mut response, 503, "busy").unwrap();
+        assert_eq!(
+            response,
+            b"HTTP/1.1 503 Service Unavailable\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: 4\r\nConnection: close\r\n\r\nbusy"
+        );
+    }
+}
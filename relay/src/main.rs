@@ -0,0 +1,215 @@
+//! A relay on the local network between the PSP and the OpenAI API.
+//!
+//! The PSP sends its conversation in plain HTTP along with a shared secret, and the relay
+//! forwards it over HTTPS with its own API key, streaming the answer back as plain text. See
+//! the "Relay" section of the README.
+
+use std::{
+    io::Write,
+    net::{TcpListener, TcpStream},
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use config::Config;
+use http::HttpError;
+
+mod config;
+mod http;
+mod upstream;
+
+/// Path chat requests are served on.
+const CHAT_PATH: &str = "/chat";
+/// Header carrying the shared secret.
+const SECRET_HEADER: &str = "X-Relay-Secret";
+/// How long the PSP may stay silent while sending its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(15);
+/// How long writing to the PSP may block, e.g. if it stopped reading the answer.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn main() -> ExitCode {
+    let config = match Config::from_env() {
+        Ok(config) => Arc::new(config),
+        Err(e) => {
+            eprintln!("relay: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let listener = match TcpListener::bind(config.listen) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("relay: cannot listen on {}: {}", config.listen, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    println!(
+        "relay: listening on {}, forwarding to {}",
+        config.listen, config.upstream
+    );
+
+    let active = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => match Slot::take(&active, config.max_connections) {
+                Some(slot) => {
+                    let config = Arc::clone(&config);
+                    thread::spawn(move || {
+                        handle(&config, stream);
+                        drop(slot);
+                    });
+                }
+                None => refuse(stream),
+            },
+            Err(e) => eprintln!("relay: failed to accept a connection: {}", e),
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// One of the connections served at once, counted until dropped.
+struct Slot(Arc<AtomicUsize>);
+
+impl Slot {
+    /// Take a slot from `active`, if less than `max` are taken.
+    fn take(active: &Arc<AtomicUsize>, max: usize) -> Option<Self> {
+        active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |taken| {
+                (taken < max).then_some(taken + 1)
+            })
+            .ok()
+            .map(|_| Slot(Arc::clone(active)))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Turn a connection down, as too many are served already.
+fn refuse(mut stream: TcpStream) {
+    eprintln!("relay: too many connections, refusing one");
+    // the answer fits in the socket buffer, but the accepting thread must never block
+    let _ = stream.set_write_timeout(Some(Duration::from_secs(1)));
+    let _ = http::write_response(&mut stream, 503, "too many connections");
+}
+
+/// Serve one request, then close the connection.
+fn handle(config: &Config, mut stream: TcpStream) {
+    let peer = stream
+        .peer_addr()
+        .map_or_else(|_| "unknown peer".to_owned(), |addr| addr.to_string());
+    if let Err(e) = stream
+        .set_read_timeout(Some(READ_TIMEOUT))
+        .and_then(|()| stream.set_write_timeout(Some(WRITE_TIMEOUT)))
+    {
+        eprintln!("relay: {}: cannot set the timeouts: {}", peer, e);
+        return;
+    }
+
+    if let Err((status, message)) = serve(config, &mut stream) {
+        eprintln!("relay: {}: {} {}", peer, status, message);
+        // the peer may be gone already
+        let _ = http::write_response(&mut stream, status, &message);
+    }
+}
+
+/// Serve a chat request, streaming the answer to `stream`.
+///
+/// # Errors
+/// The status and message to answer with, if the answer did not start.
+fn serve(config: &Config, stream: &mut TcpStream) -> Result<(), (u16, String)> {
+    let request = http::read_request(&*stream).map_err(|e| match e {
+        HttpError::TooLarge => (413, e.to_string()),
+        HttpError::TimedOut => (408, e.to_string()),
+        _ => (400, e.to_string()),
+    })?;
+
+    if request.path != CHAT_PATH {
+        return Err((404, format!("no such path: {}", request.path)));
+    }
+    if request.method != "POST" {
+        return Err((405, format!("method not allowed: {}", request.method)));
+    }
+    let secret = request.header(SECRET_HEADER).unwrap_or_default();
+    if !constant_time_eq(secret.as_bytes(), config.secret.as_bytes()) {
+        return Err((401, "wrong secret".to_owned()));
+    }
+
+    let body = String::from_utf8(request.body).map_err(|e| (400, e.to_string()))?;
+    let messages = upstream::decode_messages(&body).map_err(|e| (400, e))?;
+    if messages.is_empty() {
+        return Err((400, "no messages".to_owned()));
+    }
+
+    let mut started = false;
+    let result = upstream::stream_chat(config, &messages, |text| {
+        if !started {
+            http::write_stream_head(stream)?;
+            started = true;
+        }
+        stream.write_all(text.as_bytes())?;
+        stream.flush()
+    });
+
+    match result {
+        Ok(()) if !started => {
+            // an empty answer still needs a response
+            http::write_stream_head(stream).map_err(|e| (502, e.to_string()))
+        }
+        Ok(()) => Ok(()),
+        Err(e) if !started => Err((502, e.to_string())),
+        Err(e) => {
+            // the status is sent already: closing the connection early is all that is left
+            eprintln!("relay: answer interrupted: {}", e);
+            Ok(())
+        }
+    }
+}
+
+/// Compare `a` and `b` in a time independent of where they differ, so that the secret cannot be
+/// guessed byte by byte.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_secrets() {
+        assert!(constant_time_eq(b"s3cret", b"s3cret"));
+        assert!(constant_time_eq(b"", b""));
+        assert!(!constant_time_eq(b"s3cret", b"s3creT"));
+        assert!(!constant_time_eq(b"S3cret", b"s3cret"));
+        assert!(!constant_time_eq(b"s3cre", b"s3cret"));
+        assert!(!constant_time_eq(b"", b"s3cret"));
+    }
+
+    #[test]
+    fn caps_the_connections() {
+        let active = Arc::new(AtomicUsize::new(0));
+        let first = Slot::take(&active, 2).unwrap();
+        let second = Slot::take(&active, 2).unwrap();
+        assert!(Slot::take(&active, 2).is_none());
+
+        // a finished connection frees its slot
+        drop(first);
+        let third = Slot::take(&active, 2);
+        assert!(third.is_some());
+        drop((second, third));
+        assert_eq!(active.load(Ordering::Acquire), 0);
+    }
+}
@@ -0,0 +1,230 @@
+use std::{
+    fmt::Display,
+    io::{self, BufRead, BufReader},
+};
+
+use serde_json::{json, Value};
+
+use crate::config::Config;
+
+/// A message of a conversation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    /// `user`, `assistant` or `system`.
+    pub role: &'static str,
+    pub content: String,
+}
+
+/// An error that can occur while asking the upstream provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpstreamError {
+    /// The provider could not be reached.
+    Transport(String),
+    /// The provider answered with an error status, and the body of its answer.
+    Status(u16, String),
+    /// The answer of the provider could not be read.
+    Stream(String),
+}
+
+impl Display for UpstreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpstreamError::Transport(message) => write!(f, "upstream unreachable: {}", message),
+            UpstreamError::Status(status, body) => write!(f, "upstream error {}: {}", status, body),
+            UpstreamError::Stream(message) => write!(f, "upstream stream failed: {}", message),
+        }
+    }
+}
+
+/// Decode a request body sent by the PSP: one message per line, as the first letter of the role,
+/// a space, and the content with backslashes and newlines escaped.
+///
+/// # Errors
+/// A description of the first malformed line.
+pub fn decode_messages(body: &str) -> Result<Vec<Message>, String> {
+    body.lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (role, content) = line.split_at_checked(1).unwrap_or((line, ""));
+            let role = match role {
+                "u" => "user",
+                "a" => "assistant",
+                "s" => "system",
+                _ => return Err(format!("unknown role in line: {}", line)),
+            };
+            let content = content
+                .strip_prefix(' ')
+                .ok_or_else(|| format!("malformed line: {}", line))?;
+            Ok(Message {
+                role,
+                content: unescape(content),
+            })
+        })
+        .collect()
+}
+
+/// Undo the escaping of backslashes and newlines.
+fn unescape(content: &str) -> String {
+    let mut unescaped = String::with_capacity(content.len());
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Ask the upstream provider to continue `messages`, calling `on_text` with each piece of the
+/// answer as it streams in.
+///
+/// # Errors
+/// An [`UpstreamError`] if the provider cannot be reached, refuses the request, or the stream
+/// breaks. `on_text` failing stops the stream with [`UpstreamError::Stream`].
+pub fn stream_chat<F>(
+    config: &Config,
+    messages: &[Message],
+    on_text: F,
+) -> Result<(), UpstreamError>
+where
+    F: FnMut(&str) -> io::Result<()>,
+{
+    let messages: Vec<Value> = messages
+        .iter()
+        .map(|message| json!({ "role": message.role, "content": message.content }))
+        .collect();
+    let body = json!({
+        "model": config.model,
+        "messages": messages,
+        "stream": true,
+    });
+
+    let response = ureq::post(&config.upstream)
+        .set("Authorization", &format!("Bearer {}", config.api_key))
+        .send_json(body)
+        .map_err(|e| match e {
+            ureq::Error::Status(status, response) => {
+                UpstreamError::Status(status, response.into_string().unwrap_or_default())
+            }
+            ureq::Error::Transport(transport) => UpstreamError::Transport(transport.to_string()),
+        })?;
+
+    read_events(BufReader::new(response.into_reader()), on_text)
+}
+
+/// Read the server-sent events of a streamed answer from `reader`, one `data:` line per chunk,
+/// calling `on_text` with the text of each chunk.
+///
+/// # Errors
+/// [`UpstreamError::Stream`] if a line cannot be read or parsed, or `on_text` fails.
+fn read_events<R, F>(reader: R, mut on_text: F) -> Result<(), UpstreamError>
+where
+    R: BufRead,
+    F: FnMut(&str) -> io::Result<()>,
+{
+    for line in reader.lines() {
+        let line = line.map_err(|e| UpstreamError::Stream(e.to_string()))?;
+        let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+            continue;
+        };
+        if data == "[DONE]" {
+            break;
+        }
+
+        let chunk: Value =
+            serde_json::from_str(data).map_err(|e| UpstreamError::Stream(e.to_string()))?;
+        if let Some(text) = chunk["choices"][0]["delta"]["content"].as_str() {
+            on_text(text).map_err(|e| UpstreamError::Stream(e.to_string()))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &'static str, content: &str) -> Message {
+        Message {
+            role,
+            content: content.to_owned(),
+        }
+    }
+
+    /// The texts streamed by `events`, or the error it ends with.
+    fn texts(events: &str) -> Result<Vec<String>, UpstreamError> {
+        let mut texts = Vec::new();
+        read_events(events.as_bytes(), |text| {
+            texts.push(text.to_owned());
+            Ok(())
+        })?;
+        Ok(texts)
+    }
+
+    #[test]
+    fn decodes_every_role() {
+        let body = "s Be brief.\nu Capital of France?\na Paris.\n";
+        assert_eq!(
+            decode_messages(body),
+            Ok(vec![
+                message("system", "Be brief."),
+                message("user", "Capital of France?"),
+                message("assistant", "Paris."),
+            ])
+        );
+        assert_eq!(decode_messages(""), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        assert!(decode_messages("x Hello").is_err());
+        assert!(decode_messages("uHello").is_err());
+        // an empty content still needs the space
+        assert_eq!(decode_messages("u "), Ok(vec![message("user", "")]));
+        assert!(decode_messages("u").is_err());
+    }
+
+    #[test]
+    fn unescapes_backslashes_and_newlines() {
+        assert_eq!(unescape(r"one\ntwo"), "one\ntwo");
+        assert_eq!(unescape(r"C:\\dir"), r"C:\dir");
+        assert_eq!(unescape(r"\q"), "q");
+        assert_eq!(unescape("trailing\\"), "trailing\\");
+        assert_eq!(unescape("caf\u{e9} \u{1f600}"), "caf\u{e9} \u{1f600}");
+    }
+
+    #[test]
+    fn streams_the_text_of_each_chunk() {
+        let events = concat!(
+            ": keep-alive\n",
+            "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n",
+            "\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Par\"}}]}\n",
+            "data:{\"choices\":[{\"delta\":{\"content\":\"is.\"}}]}\n",
+            "data: [DONE]\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"ignored\"}}]}\n",
+        );
+        assert_eq!(texts(events), Ok(vec!["Par".to_owned(), "is.".to_owned()]));
+    }
+
+    #[test]
+    fn broken_streams_are_errors() {
+        assert!(matches!(
+            texts("data: {\"choices\":\n"),
+            Err(UpstreamError::Stream(_))
+        ));
+
+        let events = "data: {\"choices\":[{\"delta\":{\"content\":\"Paris.\"}}]}\n";
+        let result = read_events(events.as_bytes(), |_| {
+            Err(io::Error::from(io::ErrorKind::BrokenPipe))
+        });
+        assert!(matches!(result, Err(UpstreamError::Stream(_))));
+    }
+}
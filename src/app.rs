@@ -0,0 +1,310 @@
+use alloc::{boxed::Box, format, string::String, vec::Vec};
+use chat_gpsp_core::{
+    config::Config,
+    stack::{ScreenStack, Status, Transition},
+    templates::Template,
+};
+use core::time::Duration;
+use psp::sys;
+
+use crate::{
+    fs::PspFs,
+    gfx::{color, Renderer},
+    net::{
+        self,
+        dns::{CachingResolver, FallbackResolver, SystemClock, DEFAULT_TTL},
+        monitor::{ConnectivityMonitor, LinkStatus, MonitorEvent},
+    },
+    openai::{self, OpenAiContext, OpenAiError},
+    osk::setup_gu,
+    power::POWER_EVENTS,
+    screens::splash::SplashScreen,
+    text_input::{InputMethod, TextInput},
+    utils::{system_button_map, InputHandler, PspInput},
+    worker::Task,
+    OPENAI_API_KEY,
+};
+
+/// A [`Transition`] between application screens.
+pub type ScreenTransition = Transition<Box<dyn Screen>>;
+
+/// A screen of the application.
+///
+/// Every frame, the screen on top of the stack is first updated, and then rendered.
+pub trait Screen {
+    /// Handle the input of the current frame, and advance the screen state.
+    ///
+    /// # Returns
+    /// The transition to apply to the screen stack.
+    fn update(&mut self, ctx: &mut AppContext) -> ScreenTransition;
+
+    /// Draw the screen. The frame has already been started and cleared.
+    fn render(&self, ctx: &AppContext, renderer: &mut Renderer);
+}
+
+/// A prompt and the answer it got.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exchange {
+    pub prompt: String,
+    pub answer: String,
+}
+
+/// State shared by every screen.
+pub struct AppContext {
+    pub input: InputHandler,
+    /// Uses the input method of the settings.
+    pub text_input: TextInput,
+    pub config: Config,
+    /// Available once the network is connected.
+    pub openai_context: Option<OpenAiContext>,
+    /// Watches the link to the access point, once the network is connected.
+    pub monitor: Option<ConnectivityMonitor>,
+    /// Looks the hosts up again on a worker after the link came back, sharing the cache of
+    /// [`Self::openai_context`].
+    refresh: Option<Task<(OpenAiContext, Result<(), OpenAiError>)>>,
+    /// The persona of the conversations started from now on, if any.
+    pub template: Option<Template>,
+    /// A prompt confirmed in the composer, waiting to be sent by the chat screen.
+    pub composed_prompt: Option<String>,
+    /// Every exchange with GPT of this session, oldest first.
+    pub history: Vec<Exchange>,
+    /// The number of frames since the application started.
+    pub frame: u64,
+    /// The number of times the PSP woke up from sleep. Requests started before the last time
+    /// were cut, and must be sent again.
+    pub resumes: u32,
+}
+
+impl AppContext {
+    /// Change the way the user types text.
+    pub fn set_input_method(&mut self, input_method: InputMethod) {
+        if input_method != self.config.settings.input_method {
+            self.config.settings.input_method = input_method;
+            self.text_input = TextInput::new(input_method, crate::CHAT_MAX_LENGTH_USIZE);
+        }
+    }
+
+    /// Whether the network is connected and usable.
+    pub fn is_online(&self) -> bool {
+        !POWER_EVENTS.is_suspended()
+            && self.openai_context.is_some()
+            && self
+                .monitor
+                .as_ref()
+                .is_some_and(ConnectivityMonitor::is_connected)
+    }
+
+    /// Resolve the OpenAI API host with a new DNS resolver, set up from the configuration,
+    /// replacing the current context.
+    ///
+    /// # Errors
+    /// A message describing the failure.
+    pub fn connect_openai(&mut self) -> Result<(), String> {
+        let servers = FallbackResolver::new(&self.config.dns_servers)
+            .map_err(|e| format!("Failed to create resolver: {:?}", e))?;
+        let ttl = self
+            .config
+            .dns_ttl
+            .map_or(DEFAULT_TTL, |ttl| Duration::from_secs(ttl.into()));
+        let mut resolver = CachingResolver::<_, SystemClock>::new(servers, ttl);
+        for (name, addr) in &self.config.hosts {
+            resolver.add_host(name, *addr);
+        }
+
+        let openai_context = match self.config.relay() {
+            Some(relay) => OpenAiContext::with_relay(Box::new(resolver), relay)
+                .map_err(|e| format!("Failed to resolve the relay: {:?}", e))?,
+            None => {
+                let mut openai_context = OpenAiContext::new(Box::new(resolver), OPENAI_API_KEY)
+                    .map_err(|e| format!("Failed to create OpenAI context: {:?}", e))?;
+                openai_context
+                    .set_proxy(self.config.proxy())
+                    .map_err(|e| format!("Failed to resolve the proxy: {:?}", e))?;
+                let tls = self
+                    .config
+                    .tls_verification(&PspFs)
+                    .map_err(|e| format!("Failed to load the pinned certificate: {}", e))?;
+                openai_context.set_tls_verification(tls);
+                openai_context
+            }
+        };
+
+        self.openai_context = Some(openai_context);
+        Ok(())
+    }
+
+    /// Look the hosts of the OpenAI context up again on a worker, e.g. after reconnecting, in
+    /// case their addresses expired meanwhile.
+    ///
+    /// A refresh still running is cancelled.
+    fn refresh_openai(&mut self) {
+        let Some(mut openai_context) = self.openai_context.clone() else {
+            return;
+        };
+        // the copy shares the resolver, so its lookups are cached for the next requests
+        self.refresh = Task::spawn(move |abort| {
+            let result = openai::wait_for_resolver(&abort, || openai_context.refresh());
+            (openai_context, result)
+        })
+        // the requests look the hosts up themselves if no worker can be started
+        .ok();
+    }
+
+    /// Connect to the access point with profile `network_profile` instead of the current one,
+    /// if connected.
+    ///
+    /// The link is dropped, and brought back up with the new profile by the monitor.
+    pub fn switch_network(&mut self, network_profile: i32) {
+        let Some(monitor) = &self.monitor else {
+            return;
+        };
+        if monitor.access_point() != network_profile {
+            net::disconnect();
+            self.monitor = Some(ConnectivityMonitor::new(network_profile));
+        }
+    }
+
+    /// Poll the link to the access point, reconnecting when it drops.
+    fn monitor_network(&mut self) {
+        let connected = matches!(net::state(), sys::ApctlState::GotIp);
+        if let Some(event) = self.monitor.as_mut().and_then(|m| m.update(connected)) {
+            self.handle_monitor_event(event);
+        }
+
+        if let Some((openai_context, result)) = self.refresh.as_mut().and_then(Task::poll) {
+            self.refresh = None;
+            // on failure, the previous addresses are kept, and the next request looks the hosts
+            // up again
+            if result.is_ok() {
+                self.openai_context = Some(openai_context);
+            }
+        }
+    }
+
+    /// Reconnect if the PSP woke up from sleep since the last frame, which cut the link.
+    ///
+    /// The requests in flight are sent again by their screens, once the link is back.
+    fn handle_resume(&mut self) {
+        let resumes = POWER_EVENTS.resumes();
+        if resumes == self.resumes {
+            return;
+        }
+        self.resumes = resumes;
+
+        if let Some(event) = self
+            .monitor
+            .as_mut()
+            .and_then(ConnectivityMonitor::interrupt)
+        {
+            self.handle_monitor_event(event);
+        }
+    }
+
+    fn handle_monitor_event(&mut self, event: MonitorEvent) {
+        let Some(monitor) = &self.monitor else {
+            return;
+        };
+
+        match event {
+            MonitorEvent::Lost | MonitorEvent::Retry => {
+                net::disconnect();
+                // a failed attempt is retried once the monitor gives up on it
+                let _ = net::connect(monitor.access_point());
+            }
+            MonitorEvent::Restored => self.refresh_openai(),
+        }
+    }
+}
+
+/// The application: a stack of screens, updated and rendered once per frame.
+pub struct App {
+    ctx: AppContext,
+    stack: ScreenStack<Box<dyn Screen>>,
+    renderer: Renderer,
+}
+
+impl App {
+    /// Create the application with the saved configuration, starting from the splash screen.
+    pub fn new() -> Self {
+        unsafe {
+            sys::sceCtrlSetSamplingCycle(0);
+            sys::sceCtrlSetSamplingMode(sys::CtrlMode::Analog);
+        }
+        setup_gu();
+        openai::tls::gather_entropy();
+
+        let config = Config::load(&PspFs);
+        let mut input = InputHandler::default();
+        input.set_map(config.button_map(system_button_map()));
+        input.set_repeat(config.repeat());
+        input.set_analog(config.analog());
+
+        Self {
+            ctx: AppContext {
+                input,
+                text_input: TextInput::new(
+                    config.settings.input_method,
+                    crate::CHAT_MAX_LENGTH_USIZE,
+                ),
+                config,
+                openai_context: None,
+                monitor: None,
+                refresh: None,
+                template: None,
+                composed_prompt: None,
+                history: Vec::new(),
+                frame: 0,
+                resumes: POWER_EVENTS.resumes(),
+            },
+            stack: ScreenStack::new(Box::new(SplashScreen::new())),
+            renderer: Renderer::new(),
+        }
+    }
+
+    /// Show `screen` over the current one.
+    pub fn show(&mut self, screen: Box<dyn Screen>) {
+        self.stack.apply(Transition::Push(screen));
+    }
+
+    /// Run the application until the last screen is closed.
+    pub fn run(mut self) {
+        loop {
+            self.ctx.input.update(&mut PspInput);
+            self.ctx.handle_resume();
+            self.ctx.monitor_network();
+
+            let Some(screen) = self.stack.top_mut() else {
+                break;
+            };
+            let transition = screen.update(&mut self.ctx);
+            if self.stack.apply(transition) == Status::Exited {
+                break;
+            }
+
+            if let Some(screen) = self.stack.top() {
+                // the theme may have changed in the settings
+                let theme = self.ctx.config.settings.theme;
+                self.renderer.set_theme(theme);
+                // ends the frame on the vertical blank, pacing the loop to the display
+                self.renderer.begin(color::BLACK);
+                screen.render(&self.ctx, &mut self.renderer);
+                self.render_link_status();
+                self.renderer.end();
+            }
+            self.ctx.frame += 1;
+        }
+    }
+
+    /// Draw the reconnection status over the current screen, while the link is down.
+    fn render_link_status(&mut self) {
+        let Some(LinkStatus::Reconnecting { attempts, .. }) =
+            self.ctx.monitor.as_ref().map(ConnectivityMonitor::status)
+        else {
+            return;
+        };
+
+        let status = format!("Reconnecting ({})...", attempts);
+        let x = psp::SCREEN_WIDTH as i16 - Renderer::text_width(&status) as i16 - 8;
+        self.renderer.draw_text(x, 4, &status, color::YELLOW);
+    }
+}
@@ -0,0 +1,62 @@
+use alloc::{string::String, vec::Vec};
+
+/// Maximum number of OSK entries a single prompt can be made of.
+pub const MAX_CHUNKS: usize = 16;
+
+/// A prompt draft, made of several OSK entries (chunks).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PromptComposer {
+    chunks: Vec<String>,
+}
+
+impl PromptComposer {
+    #[inline]
+    /// Create an empty draft.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a chunk to the draft.
+    ///
+    /// # Returns
+    /// `false` if `chunk` was not appended, because it is empty or whitespace-only, or because
+    /// the draft already has [`MAX_CHUNKS`] chunks.
+    pub fn push(&mut self, chunk: String) -> bool {
+        if self.is_full() || chunk.trim().is_empty() {
+            return false;
+        }
+        self.chunks.push(chunk);
+        true
+    }
+
+    /// Remove the last chunk of the draft, if any, and return it.
+    pub fn pop(&mut self) -> Option<String> {
+        self.chunks.pop()
+    }
+
+    /// Remove every chunk from the draft.
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    #[inline]
+    /// The number of chunks in the draft.
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.chunks.len() >= MAX_CHUNKS
+    }
+
+    /// The whole draft, with its chunks separated by a space.
+    pub fn draft(&self) -> String {
+        self.chunks.join(" ")
+    }
+}
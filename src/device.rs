@@ -0,0 +1,80 @@
+use core::{ffi::c_void, mem::size_of, ptr};
+
+use chat_gpsp_core::platform::{Battery, DateTime, Device};
+use psp::sys::{self, ScePspDateTime};
+
+/// `sceIoDevctl` command reading the capacity of the Memory Stick.
+const MS_GET_CAPACITY: u32 = 0x0242_5818;
+
+/// The capacity of the Memory Stick, as filled by [`MS_GET_CAPACITY`].
+#[repr(C)]
+#[derive(Debug, Default)]
+struct MsCapacity {
+    max_clusters: u32,
+    free_clusters: u32,
+    max_sectors: u32,
+    sector_size: u32,
+    sectors_per_cluster: u32,
+}
+
+/// The PSP itself: its real-time clock, battery and Memory Stick.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PspDevice;
+
+impl Device for PspDevice {
+    fn local_time(&self) -> Option<DateTime> {
+        let mut time = ScePspDateTime::default();
+        if unsafe { sys::sceRtcGetCurrentClockLocalTime(&mut time) } < 0 {
+            return None;
+        }
+
+        Some(DateTime {
+            year: time.year,
+            month: time.month as u8,
+            day: time.day as u8,
+            hour: time.hour as u8,
+            minute: time.minutes as u8,
+            second: time.seconds as u8,
+        })
+    }
+
+    fn battery(&self) -> Option<Battery> {
+        if unsafe { sys::scePowerIsBatteryExist() } <= 0 {
+            return None;
+        }
+        let percent = unsafe { sys::scePowerGetBatteryLifePercent() };
+        if percent < 0 {
+            return None;
+        }
+
+        Some(Battery {
+            percent: percent.min(100) as u8,
+            charging: unsafe { sys::scePowerIsBatteryCharging() } == 1,
+        })
+    }
+
+    fn free_space(&self) -> Option<u64> {
+        let mut capacity = MsCapacity::default();
+        // the command takes a pointer to the structure to fill
+        let mut arg = &mut capacity as *mut MsCapacity;
+        let res = unsafe {
+            sys::sceIoDevctl(
+                c"ms0:".as_ptr() as *const u8,
+                MS_GET_CAPACITY,
+                &mut arg as *mut *mut MsCapacity as *mut c_void,
+                size_of::<*mut MsCapacity>() as i32,
+                ptr::null_mut(),
+                0,
+            )
+        };
+        if res < 0 {
+            return None;
+        }
+
+        Some(
+            u64::from(capacity.free_clusters)
+                * u64::from(capacity.sectors_per_cluster)
+                * u64::from(capacity.sector_size),
+        )
+    }
+}
@@ -0,0 +1,148 @@
+use alloc::{format, string::String, vec::Vec};
+use core::ffi::c_void;
+
+use chat_gpsp_core::platform::FileSystem;
+use psp::sys::{self, IoOpenFlags};
+
+pub use chat_gpsp_core::fs::{data_path, FsError, DATA_DIR};
+
+/// Size of the chunks files are read in.
+const READ_CHUNK_SIZE: usize = 4096;
+
+/// The Memory Stick, accessed through the firmware.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PspFs;
+
+impl FileSystem for PspFs {
+    #[inline]
+    fn read(&self, path: &str) -> Result<Vec<u8>, FsError> {
+        read(path)
+    }
+
+    #[inline]
+    fn write(&mut self, path: &str, content: &[u8]) -> Result<(), FsError> {
+        write(path, content)
+    }
+
+    #[inline]
+    fn list(&self, dir: &str) -> Result<Vec<String>, FsError> {
+        list(dir)
+    }
+}
+
+/// Read the whole content of the file at `path`.
+///
+/// # Errors
+/// - [`FsError::Open`] if the file cannot be opened, e.g. because it does not exist.
+/// - [`FsError::Read`] if the file cannot be read.
+pub fn read(path: &str) -> Result<Vec<u8>, FsError> {
+    let c_path = format!("{}\0", path);
+    let fd = unsafe { sys::sceIoOpen(c_path.as_ptr(), IoOpenFlags::RD_ONLY, 0o777) };
+    if fd.0 < 0 {
+        return Err(FsError::Open(path.into(), fd.0));
+    }
+
+    let mut content = Vec::new();
+    let mut buf = [0u8; READ_CHUNK_SIZE];
+    let result = loop {
+        let read =
+            unsafe { sys::sceIoRead(fd, buf.as_mut_ptr() as *mut c_void, READ_CHUNK_SIZE as u32) };
+        if read < 0 {
+            break Err(FsError::Read(path.into(), read));
+        }
+        if read == 0 {
+            break Ok(());
+        }
+        content.extend_from_slice(&buf[..read as usize]);
+    };
+
+    unsafe {
+        sys::sceIoClose(fd);
+    }
+
+    result.map(|_| content)
+}
+
+/// List the names of the files in the directory `dir`, leaving out its subdirectories.
+///
+/// # Errors
+/// [`FsError::Open`] if the directory cannot be opened, e.g. because it does not exist.
+pub fn list(dir: &str) -> Result<Vec<String>, FsError> {
+    let c_dir = format!("{}\0", dir);
+    let fd = unsafe { sys::sceIoDopen(c_dir.as_ptr()) };
+    if fd.0 < 0 {
+        return Err(FsError::Open(dir.into(), fd.0));
+    }
+
+    let mut names = Vec::new();
+    let mut entry: sys::SceIoDirent = unsafe { core::mem::zeroed() };
+    // positive while there are entries left
+    while unsafe { sys::sceIoDread(fd, &mut entry) } > 0 {
+        if entry.d_stat.st_mode.contains(sys::IoStatMode::IFDIR) {
+            continue;
+        }
+        let len = entry
+            .d_name
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(entry.d_name.len());
+        names.extend(String::from_utf8(entry.d_name[..len].to_vec()).ok());
+    }
+
+    unsafe {
+        sys::sceIoDclose(fd);
+    }
+
+    Ok(names)
+}
+
+/// Write `content` to the file at `path`, replacing it if it exists.
+///
+/// The [data directory](DATA_DIR) is created if it does not exist.
+///
+/// # Errors
+/// - [`FsError::Open`] if the file cannot be created.
+/// - [`FsError::Write`] if the file cannot be written.
+pub fn write(path: &str, content: &[u8]) -> Result<(), FsError> {
+    // fails harmlessly if the directory already exists
+    let c_dir = format!("{}\0", DATA_DIR);
+    unsafe {
+        sys::sceIoMkdir(c_dir.as_ptr(), 0o777);
+    }
+
+    let c_path = format!("{}\0", path);
+    let fd = unsafe {
+        sys::sceIoOpen(
+            c_path.as_ptr(),
+            IoOpenFlags::WR_ONLY | IoOpenFlags::CREAT | IoOpenFlags::TRUNC,
+            0o777,
+        )
+    };
+    if fd.0 < 0 {
+        return Err(FsError::Open(path.into(), fd.0));
+    }
+
+    let mut written = 0;
+    let result = loop {
+        if written == content.len() {
+            break Ok(());
+        }
+        let res = unsafe {
+            sys::sceIoWrite(
+                fd,
+                content[written..].as_ptr() as *const c_void,
+                content.len() - written,
+            )
+        };
+        if res <= 0 {
+            break Err(FsError::Write(path.into(), res));
+        }
+        written += res as usize;
+    };
+
+    unsafe {
+        sys::sceIoClose(fd);
+    }
+
+    result
+}
@@ -0,0 +1,231 @@
+use core::{ffi::c_void, mem::size_of, ptr::addr_of_mut};
+
+use chat_gpsp_core::platform::Display;
+
+use psp::sys::{
+    self, ClearBuffer, GuContextType, GuPrimitive, GuState, GuSyncBehavior, GuSyncMode,
+    GuTexWrapMode, MipmapLevel, TextureColorComponent, TextureEffect, TextureFilter,
+    TexturePixelFormat, VertexType,
+};
+
+use crate::gfx::font::{FontTexture, GLYPH_SIZE};
+
+pub mod font;
+
+pub use chat_gpsp_core::layout::{color, wrap_text, Theme, SCREEN_COLUMNS};
+
+static mut LIST: psp::Align16<[u32; 65_536]> = psp::Align16([0; 65_536]);
+
+/// A vertex with a color and a 16-bit position, used to draw rectangles.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct ColorVertex {
+    color: u32,
+    x: i16,
+    y: i16,
+    z: i16,
+}
+
+/// A vertex with 16-bit texture coordinates, a color and a 16-bit position, used to draw glyphs.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct TextureVertex {
+    u: u16,
+    v: u16,
+    color: u32,
+    x: i16,
+    y: i16,
+    z: i16,
+}
+
+/// A simple 2D renderer built on the GU, able to draw filled rectangles and text.
+///
+/// The colors of [`color`] are drawn in those of the [theme](Self::set_theme).
+///
+/// The GU must have been set up with [`setup_gu`](crate::osk::setup_gu) before using it.
+///
+/// # Example
+/// ```no_run
+/// let mut renderer = Renderer::new();
+/// renderer.begin(color::BLACK);
+/// renderer.fill_rect(10, 10, 100, 20, color::DARK_GRAY);
+/// renderer.draw_text(12, 16, "Hello", color::WHITE);
+/// renderer.end();
+/// ```
+pub struct Renderer {
+    font: FontTexture,
+    theme: Theme,
+}
+
+impl Renderer {
+    /// Create a new renderer, building its font texture.
+    pub fn new() -> Self {
+        Self {
+            font: FontTexture::new(),
+            theme: Theme::default(),
+        }
+    }
+
+    /// Draw the next frames with the colors of `theme`.
+    #[inline]
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Start a new frame, clearing the screen with `clear_color`.
+    pub fn begin(&mut self, clear_color: u32) {
+        let clear_color = self.theme.color(clear_color);
+        unsafe {
+            sys::sceGuStart(
+                GuContextType::Direct,
+                addr_of_mut!(LIST) as *mut _ as *mut c_void,
+            );
+            sys::sceGuDisable(GuState::DepthTest);
+            sys::sceGuClearColor(clear_color);
+            sys::sceGuClear(ClearBuffer::COLOR_BUFFER_BIT | ClearBuffer::DEPTH_BUFFER_BIT);
+        }
+    }
+
+    /// Finish the frame, wait for the vertical blank and show it.
+    pub fn end(&mut self) {
+        unsafe {
+            sys::sceGuFinish();
+            sys::sceGuSync(GuSyncMode::Finish, GuSyncBehavior::Wait);
+            sys::sceDisplayWaitVblankStart();
+            sys::sceGuSwapBuffers();
+        }
+    }
+
+    /// Draw a `width`x`height` rectangle filled with `color`, with its top left corner at
+    /// (`x`, `y`).
+    pub fn fill_rect(&mut self, x: i16, y: i16, width: i16, height: i16, color: u32) {
+        let color = self.theme.color(color);
+        unsafe {
+            let vertices =
+                sys::sceGuGetMemory((2 * size_of::<ColorVertex>()) as i32) as *mut ColorVertex;
+            *vertices = ColorVertex { color, x, y, z: 0 };
+            *vertices.add(1) = ColorVertex {
+                color,
+                x: x + width,
+                y: y + height,
+                z: 0,
+            };
+
+            sys::sceGuDisable(GuState::Texture2D);
+            sys::sceGuDrawArray(
+                GuPrimitive::Sprites,
+                VertexType::COLOR_8888 | VertexType::VERTEX_16BIT | VertexType::TRANSFORM_2D,
+                2,
+                core::ptr::null(),
+                vertices as *const c_void,
+            );
+        }
+    }
+
+    /// Draw `text` with its top left corner at (`x`, `y`), on a single line.
+    ///
+    /// Characters the font does not have are drawn as `?`.
+    #[inline]
+    pub fn draw_text(&mut self, x: i16, y: i16, text: &str, color: u32) {
+        self.draw_text_scaled(x, y, text, color, 1);
+    }
+
+    /// Draw `text` like [`Self::draw_text`], with glyphs `scale` times as large.
+    pub fn draw_text_scaled(&mut self, x: i16, y: i16, text: &str, color: u32, scale: i16) {
+        let count = text.chars().count();
+        if count == 0 {
+            return;
+        }
+        let color = self.theme.color(color);
+        let size = GLYPH_SIZE as i16 * scale;
+
+        unsafe {
+            let vertices = sys::sceGuGetMemory((2 * count * size_of::<TextureVertex>()) as i32)
+                as *mut TextureVertex;
+
+            for (i, c) in text.chars().enumerate() {
+                let (u, v) = self.font.glyph_origin(c);
+                let glyph_x = x + i as i16 * size;
+
+                *vertices.add(2 * i) = TextureVertex {
+                    u,
+                    v,
+                    color,
+                    x: glyph_x,
+                    y,
+                    z: 0,
+                };
+                *vertices.add(2 * i + 1) = TextureVertex {
+                    u: u + GLYPH_SIZE as u16,
+                    v: v + GLYPH_SIZE as u16,
+                    color,
+                    x: glyph_x + size,
+                    y: y + size,
+                    z: 0,
+                };
+            }
+
+            sys::sceGuEnable(GuState::Texture2D);
+            sys::sceGuTexMode(TexturePixelFormat::Psm8888, 0, 0, 0);
+            sys::sceGuTexImage(
+                MipmapLevel::None,
+                self.font.width(),
+                self.font.height(),
+                self.font.width(),
+                self.font.as_ptr(),
+            );
+            sys::sceGuTexFunc(TextureEffect::Modulate, TextureColorComponent::Rgba);
+            sys::sceGuTexFilter(TextureFilter::Nearest, TextureFilter::Nearest);
+            sys::sceGuTexWrap(GuTexWrapMode::Clamp, GuTexWrapMode::Clamp);
+            sys::sceGuDrawArray(
+                GuPrimitive::Sprites,
+                VertexType::TEXTURE_16BIT
+                    | VertexType::COLOR_8888
+                    | VertexType::VERTEX_16BIT
+                    | VertexType::TRANSFORM_2D,
+                (2 * count) as i32,
+                core::ptr::null(),
+                vertices as *const c_void,
+            );
+        }
+    }
+
+    /// The width, in pixels, `text` takes when drawn with [`Self::draw_text`].
+    #[inline]
+    pub fn text_width(text: &str) -> usize {
+        chat_gpsp_core::layout::text_width(text)
+    }
+}
+
+impl Display for Renderer {
+    #[inline]
+    fn begin(&mut self, clear_color: u32) {
+        Renderer::begin(self, clear_color);
+    }
+
+    #[inline]
+    fn end(&mut self) {
+        Renderer::end(self);
+    }
+
+    #[inline]
+    fn fill_rect(&mut self, x: i16, y: i16, width: i16, height: i16, color: u32) {
+        Renderer::fill_rect(self, x, y, width, height, color);
+    }
+
+    #[inline]
+    fn draw_text(&mut self, x: i16, y: i16, text: &str, color: u32) {
+        Renderer::draw_text(self, x, y, text, color);
+    }
+
+    #[inline]
+    fn draw_text_scaled(&mut self, x: i16, y: i16, text: &str, color: u32, scale: i16) {
+        Renderer::draw_text_scaled(self, x, y, text, color, scale);
+    }
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,89 @@
+use core::{ffi::c_void, ptr::addr_of_mut};
+
+use psp::sys::sceKernelDcacheWritebackAll;
+
+pub use chat_gpsp_core::layout::GLYPH_SIZE;
+
+/// Number of glyphs in the font.
+const GLYPH_COUNT: usize = 256;
+/// Number of glyphs per row of the texture.
+const GLYPHS_PER_ROW: usize = 16;
+
+const TEXTURE_WIDTH: usize = GLYPHS_PER_ROW * GLYPH_SIZE;
+const TEXTURE_HEIGHT: usize = (GLYPH_COUNT / GLYPHS_PER_ROW) * GLYPH_SIZE;
+
+/// The 8x8 MSX font, the same used by the `psp` crate for its debug screen. Each glyph is made of
+/// 8 bytes, one per row, with the most significant bit being the leftmost pixel.
+const MSX_FONT: [u8; GLYPH_COUNT * GLYPH_SIZE] = *include_bytes!("msxfont.bin");
+
+static mut TEXTURE: psp::Align16<[u32; TEXTURE_WIDTH * TEXTURE_HEIGHT]> =
+    psp::Align16([0; TEXTURE_WIDTH * TEXTURE_HEIGHT]);
+
+/// The font texture, with white glyphs on a transparent background, so that they can be tinted by
+/// the vertex color.
+pub struct FontTexture {
+    texture: *mut u32,
+}
+
+impl FontTexture {
+    /// Build the font texture.
+    pub fn new() -> Self {
+        for (glyph, rows) in MSX_FONT.chunks_exact(GLYPH_SIZE).enumerate() {
+            let origin_x = (glyph % GLYPHS_PER_ROW) * GLYPH_SIZE;
+            let origin_y = (glyph / GLYPHS_PER_ROW) * GLYPH_SIZE;
+
+            for (y, row) in rows.iter().enumerate() {
+                for x in 0..GLYPH_SIZE {
+                    let pixel = if row & (0b1000_0000 >> x) != 0 {
+                        0xff_ff_ff_ff
+                    } else {
+                        0
+                    };
+                    unsafe {
+                        let texture = addr_of_mut!(TEXTURE) as *mut u32;
+                        *texture.add((origin_y + y) * TEXTURE_WIDTH + origin_x + x) = pixel;
+                    }
+                }
+            }
+        }
+
+        // the GU reads the texture from memory, bypassing the cache
+        let texture = unsafe {
+            sceKernelDcacheWritebackAll();
+            addr_of_mut!(TEXTURE) as *mut u32
+        };
+
+        Self { texture }
+    }
+
+    /// The texture coordinates of the top left corner of the glyph for `c`.
+    ///
+    /// Only printable ASCII characters are supported: any other character is mapped to `?`.
+    pub fn glyph_origin(&self, c: char) -> (u16, u16) {
+        let glyph = if c.is_ascii() && !c.is_ascii_control() {
+            c as usize
+        } else {
+            '?' as usize
+        };
+
+        (
+            ((glyph % GLYPHS_PER_ROW) * GLYPH_SIZE) as u16,
+            ((glyph / GLYPHS_PER_ROW) * GLYPH_SIZE) as u16,
+        )
+    }
+
+    #[inline]
+    pub fn width(&self) -> i32 {
+        TEXTURE_WIDTH as i32
+    }
+
+    #[inline]
+    pub fn height(&self) -> i32 {
+        TEXTURE_HEIGHT as i32
+    }
+
+    #[inline]
+    pub fn as_ptr(&self) -> *const c_void {
+        self.texture as *const c_void
+    }
+}
@@ -0,0 +1,113 @@
+//! The drawing of the in-app [`Keyboard`], whose state lives in the core crate.
+
+use alloc::{format, string::String, vec::Vec};
+
+use chat_gpsp_core::keyboard::{layout::CELLS, Keyboard, MAX_SUGGESTIONS};
+
+use crate::{
+    gfx::{color, Renderer},
+    utils::{Action, InputHandler},
+};
+
+/// Number of characters that fit on a line of the text box.
+const TEXT_BOX_COLUMNS: usize = 58;
+/// Number of lines of the text box.
+const TEXT_BOX_LINES: usize = 4;
+/// Side, in pixels, of a cell of the keyboard grid.
+const CELL_SIZE: i16 = 44;
+const GRID_X: i16 = (psp::SCREEN_WIDTH as i16 - 3 * CELL_SIZE) / 2;
+const GRID_Y: i16 = 100;
+
+/// Draw `keyboard`, typing the text of `description`, with the buttons of the actions of
+/// `input` in the hints.
+pub fn render(
+    keyboard: &Keyboard,
+    description: &str,
+    input: &InputHandler,
+    renderer: &mut Renderer,
+) {
+    let suggestions = keyboard.suggestions();
+    let selected = keyboard.suggestion();
+
+    // text box, showing the last lines of the text followed by the cursor
+    renderer.draw_text(8, 6, description, color::GRAY);
+    renderer.fill_rect(4, 18, 472, 44, color::DARK_GRAY);
+    let mut text: Vec<char> = keyboard.text().chars().collect();
+    text.push('_');
+    let lines: Vec<&[char]> = text.chunks(TEXT_BOX_COLUMNS).collect();
+    let first_line = lines.len().saturating_sub(TEXT_BOX_LINES);
+    for (i, line) in lines[first_line..].iter().enumerate() {
+        let line: String = line.iter().collect();
+        renderer.draw_text(8, 22 + 10 * i as i16, &line, color::WHITE);
+    }
+
+    // suggestions, scrolled so that the selected one is visible
+    let first_suggestion = selected - selected % MAX_SUGGESTIONS;
+    let mut x = 8;
+    let complete = format!("{}:", input.label(Action::Complete));
+    renderer.draw_text(x, 72, &complete, color::GRAY);
+    x += (Renderer::text_width(&complete) + 8) as i16;
+    for (i, suggestion) in suggestions
+        .iter()
+        .enumerate()
+        .skip(first_suggestion)
+        .take(MAX_SUGGESTIONS)
+    {
+        let color = if i == selected {
+            color::YELLOW
+        } else {
+            color::WHITE
+        };
+        renderer.draw_text(x, 72, suggestion, color);
+        x += (Renderer::text_width(suggestion) + 16) as i16;
+    }
+
+    // keyboard grid
+    let layer = keyboard.layer().layer();
+    for (cell, chars) in layer.iter().enumerate().take(CELLS) {
+        let cell_x = GRID_X + (cell % 3) as i16 * CELL_SIZE;
+        let cell_y = GRID_Y + (cell / 3) as i16 * CELL_SIZE;
+        let background = if cell == keyboard.cell() {
+            color::HIGHLIGHT
+        } else {
+            color::DARK_GRAY
+        };
+        renderer.fill_rect(
+            cell_x + 1,
+            cell_y + 1,
+            CELL_SIZE - 2,
+            CELL_SIZE - 2,
+            background,
+        );
+
+        let positions = [(18, 4), (4, 18), (32, 18), (18, 32)];
+        for (c, (dx, dy)) in chars.iter().zip(positions) {
+            let mut buf = [0u8; 4];
+            renderer.draw_text(
+                cell_x + dx,
+                cell_y + dy,
+                c.encode_utf8(&mut buf),
+                color::WHITE,
+            );
+        }
+    }
+
+    let layer = format!("{}:", input.label(Action::Layer));
+    renderer.draw_text(8, GRID_Y, &layer, color::GRAY);
+    let x = 8 + (Renderer::text_width(&layer) + 8) as i16;
+    renderer.draw_text(x, GRID_Y, keyboard.layer().name(), color::WHITE);
+    let hint = format!(
+        "{}: delete  {}: space  {}/{}: suggestion",
+        input.label(Action::Delete),
+        input.label(Action::Space),
+        input.label(Action::Up),
+        input.label(Action::Down)
+    );
+    renderer.draw_text(8, 244, &hint, color::GRAY);
+    let hint = format!(
+        "{}: done  {}: cancel",
+        input.label(Action::Menu),
+        input.label(Action::Discard)
+    );
+    renderer.draw_text(8, 256, &hint, color::GRAY);
+}
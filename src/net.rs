@@ -0,0 +1,110 @@
+use alloc::{format, string::String, vec::Vec};
+use core::ffi::CStr;
+
+use psp::sys::{self, ApctlState, NetParam, UtilityNetData};
+use psp_net::utils::NetError;
+
+pub mod dns;
+
+pub use chat_gpsp_core::net::monitor;
+
+/// Highest id of an access point connection profile: the system stores up to 10 of them.
+const MAX_ACCESS_POINTS: i32 = 10;
+
+/// An access point connection profile stored in the system settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessPoint {
+    /// The profile id, from 1.
+    pub id: i32,
+    pub name: String,
+    pub ssid: String,
+    /// A human readable description, e.g. `1: Home (my-hotspot)`.
+    pub label: String,
+}
+
+/// Read a string parameter of the access point connection profile `id`.
+fn net_param_string(id: i32, param: NetParam) -> Option<String> {
+    let mut data = UtilityNetData { as_uint: 0 };
+    let res = unsafe { sys::sceUtilityGetNetParam(id, param, &mut data) };
+    if res != 0 {
+        return None;
+    }
+
+    let bytes = unsafe { &data.as_string };
+    let string = CStr::from_bytes_until_nul(bytes).ok()?;
+    Some(String::from_utf8_lossy(string.to_bytes()).into_owned())
+}
+
+/// The access point connection profiles stored in the system settings, by id.
+pub fn access_points() -> Vec<AccessPoint> {
+    (1..=MAX_ACCESS_POINTS)
+        .filter(|&id| unsafe { sys::sceUtilityCheckNetParam(id) } == 0)
+        .map(|id| {
+            let name = net_param_string(id, NetParam::Name).unwrap_or_default();
+            let ssid = net_param_string(id, NetParam::Ssid).unwrap_or_default();
+            let label = format!("{}: {} ({})", id, name, ssid);
+            AccessPoint {
+                id,
+                name,
+                ssid,
+                label,
+            }
+        })
+        .collect()
+}
+
+/// Load the network modules and initialize the network.
+///
+/// # Errors
+/// A [`NetError`] if a module cannot be loaded or the network cannot be initialized.
+pub fn init() -> Result<(), NetError> {
+    psp_net::utils::load_net_modules()?;
+    psp_net::utils::net_init()
+}
+
+/// Start connecting to the access point with profile `id`.
+///
+/// The connection is not established when this function returns: poll [`state`] to know when it
+/// is.
+///
+/// # Errors
+/// A [`NetError`] if the connection cannot be started.
+pub fn connect(id: i32) -> Result<(), NetError> {
+    psp_net::utils::init_connection_to_access_point(id)
+}
+
+/// Drop the connection to the access point, if any.
+pub fn disconnect() {
+    unsafe {
+        sys::sceNetApctlDisconnect();
+    }
+}
+
+/// The state of the connection to the access point.
+pub fn state() -> ApctlState {
+    let mut state = ApctlState::Disconnected;
+    unsafe {
+        sys::sceNetApctlGetState(&mut state);
+    }
+    state
+}
+
+/// Whether the connection to the access point is established.
+#[inline]
+pub fn is_connected() -> bool {
+    matches!(state(), ApctlState::GotIp)
+}
+
+#[inline]
+/// A human readable name of an access point state.
+pub fn state_name(state: ApctlState) -> &'static str {
+    match state {
+        ApctlState::Disconnected => "Disconnected",
+        ApctlState::Scanning => "Scanning",
+        ApctlState::Joining => "Joining",
+        ApctlState::GettingIp => "Getting IP address",
+        ApctlState::GotIp => "Connected",
+        ApctlState::EapAuth => "Authenticating",
+        ApctlState::KeyExchange => "Exchanging keys",
+    }
+}
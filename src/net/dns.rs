@@ -0,0 +1,77 @@
+use alloc::{format, string::String, vec::Vec};
+use core::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+
+use chat_gpsp_core::platform::Clock;
+use psp::sys;
+use psp_net::{dns::DnsResolver, traits::dns::ResolveHostname as _};
+
+pub use chat_gpsp_core::net::dns::{CachingResolver, ResolveError, ResolveHostname, DEFAULT_TTL};
+
+/// Port DNS servers listen on.
+const DNS_PORT: u16 = 53;
+
+/// The system clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> i64 {
+        unsafe { sys::sceKernelGetSystemTimeWide() }
+    }
+}
+
+/// A resolver querying several DNS servers in turn, until one answers.
+///
+/// It is shared with the worker threads sending the requests, which look the API host up.
+pub struct FallbackResolver {
+    resolvers: Vec<DnsResolver>,
+}
+
+// the sockets of psp-net count the references to their descriptor with an `Rc`, which keeps them
+// from being sent. Each socket of the resolvers is the only one holding its descriptor, so the
+// count is never touched by two threads at once
+unsafe impl Send for FallbackResolver {}
+
+impl FallbackResolver {
+    /// Create a resolver querying `servers`, in order. With no servers, the default one of
+    /// [`DnsResolver`] is used.
+    ///
+    /// # Errors
+    /// [`ResolveError::NoResolver`] if no resolver could be created for any of the servers.
+    pub fn new(servers: &[Ipv4Addr]) -> Result<Self, ResolveError> {
+        let resolvers: Vec<DnsResolver> = if servers.is_empty() {
+            DnsResolver::try_default().into_iter().collect()
+        } else {
+            servers
+                .iter()
+                .filter_map(|server| {
+                    DnsResolver::new(SocketAddr::V4(SocketAddrV4::new(*server, DNS_PORT))).ok()
+                })
+                .collect()
+        };
+
+        if resolvers.is_empty() {
+            return Err(ResolveError::NoResolver(format!(
+                "Failed to create a resolver for {:?}",
+                servers
+            )));
+        }
+        Ok(Self { resolvers })
+    }
+}
+
+impl ResolveHostname for FallbackResolver {
+    type Error = ResolveError;
+
+    fn resolve_hostname(&mut self, hostname: &str) -> Result<SocketAddr, ResolveError> {
+        let mut last_error = String::new();
+        for resolver in &mut self.resolvers {
+            match resolver.resolve_hostname(hostname) {
+                Ok(addr) => return Ok(addr),
+                Err(e) => last_error = format!("{:?}", e),
+            }
+        }
+        Err(ResolveError::Failed(last_error))
+    }
+}
@@ -0,0 +1,41 @@
+//! The OpenAI client, running on the [network](network::PspNetwork) of the PSP.
+
+pub use chat_gpsp_core::openai::{OpenAiContext, OpenAiError};
+
+use chat_gpsp_core::net::abort::Abort;
+use psp::sys;
+
+use crate::net::dns::SystemClock;
+
+use self::network::PspNetwork;
+
+pub mod network;
+pub mod tls;
+
+/// Microseconds to sleep between two attempts to use a resolver in use.
+const RESOLVER_POLL: u32 = 10_000;
+
+/// The OpenAI client of the PSP.
+pub type OpenAi = chat_gpsp_core::openai::OpenAi<PspNetwork, SystemClock>;
+
+/// Run `lookup`, e.g. [`OpenAiContext::refresh`], again while a copy of the context is using
+/// the resolver, until `abort` is triggered.
+///
+/// Sleeping between the attempts lets the thread holding the resolver run, even at the same
+/// priority, so this belongs on a worker thread.
+///
+/// # Errors
+/// The error of the last attempt, which is [`OpenAiError::ResolverBusy`] if it was aborted.
+pub fn wait_for_resolver<F>(abort: &Abort, mut lookup: F) -> Result<(), OpenAiError>
+where
+    F: FnMut() -> Result<(), OpenAiError>,
+{
+    loop {
+        match lookup() {
+            Err(OpenAiError::ResolverBusy) if !abort.is_aborted() => unsafe {
+                sys::sceKernelDelayThread(RESOLVER_POLL);
+            },
+            result => return result,
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use alloc::{format, string::String, sync::Arc};
+use core::{ffi::c_void, net::SocketAddr, time::Duration};
+
+use psp::sys;
+use psp_net::{
+    socket::{state::Connected, tcp::TcpSocket, tls::TlsSocket},
+    types::SocketRecvFlags,
+};
+use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+use chat_gpsp_core::{
+    net::abort::Abort,
+    openai::tls::{
+        session::{self, Connection},
+        TrustAnchor,
+    },
+    platform::{HandshakeError, Network, Socket},
+};
+
+use super::tls::{self, RtcClock, Transport, ENTROPY};
+
+const SOL_SOCKET: i32 = 0xffff;
+const SO_SNDTIMEO: i32 = 0x1005;
+const SO_RCVTIMEO: i32 = 0x1006;
+const SHUT_RDWR: i32 = 2;
+
+/// Bound how long the blocking calls on the socket `fd` may wait.
+///
+/// The send timeout also bounds `connect`.
+fn set_socket_timeouts(fd: i32, timeout: Duration) -> Result<(), String> {
+    // the PSP network stack expects the timeout in microseconds
+    let micros = timeout.as_micros().min(u32::MAX as u128) as u32;
+
+    for option in [SO_SNDTIMEO, SO_RCVTIMEO] {
+        let res = unsafe {
+            sys::sceNetInetSetsockopt(
+                fd,
+                SOL_SOCKET,
+                option,
+                &micros as *const u32 as *const c_void,
+                size_of::<u32>() as u32,
+            )
+        };
+        if res < 0 {
+            let errno = unsafe { sys::sceNetInetGetErrno() };
+            return Err(format!("Failed to set socket timeout: errno {}", errno));
+        }
+    }
+
+    Ok(())
+}
+
+/// Abort the request of `abort`, from another thread.
+///
+/// The socket the request is using is shut down, so that a read or write blocked on it returns
+/// at once, and the request opens no other one.
+pub fn abort(abort: &Abort) {
+    if let Some(fd) = abort.abort() {
+        unsafe {
+            sys::sceNetInetShutdown(fd, SHUT_RDWR);
+        }
+    }
+}
+
+/// A socket registered with the [`Abort`] of its request, until dropped.
+struct Registration {
+    abort: Arc<Abort>,
+    fd: i32,
+}
+
+impl Registration {
+    fn new(abort: &Arc<Abort>, fd: i32) -> Result<Self, String> {
+        if !abort.register(fd) {
+            return Err("Cancelled".into());
+        }
+        Ok(Self {
+            abort: abort.clone(),
+            fd,
+        })
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.abort.unregister(self.fd);
+    }
+}
+
+/// The network stack of the PSP, with TLS sessions from `embedded-tls`.
+#[derive(Debug, Default, Clone)]
+pub struct PspNetwork {
+    /// Lets another thread stop the request, if set.
+    abort: Option<Arc<Abort>>,
+}
+
+impl PspNetwork {
+    /// A network whose requests `abort` can stop, see [`abort`].
+    pub fn abortable(abort: Arc<Abort>) -> Self {
+        Self { abort: Some(abort) }
+    }
+}
+
+impl Network for PspNetwork {
+    type Socket = PspSocket;
+
+    fn connect(&mut self, addr: SocketAddr, timeout: Duration) -> Result<PspSocket, String> {
+        let socket = TcpSocket::new().map_err(|e| format!("{:?}", e))?;
+        let registration = match &self.abort {
+            Some(abort) => Some(Registration::new(abort, socket.fd())?),
+            None => None,
+        };
+        set_socket_timeouts(socket.fd(), timeout)?;
+        let start = unsafe { sys::sceKernelGetSystemTimeLow() };
+        let socket = socket.connect(addr).map_err(|e| format!("{:?}", e))?;
+        // how long the access point and the server took to answer is hard to guess
+        let elapsed = unsafe { sys::sceKernelGetSystemTimeLow() }.wrapping_sub(start);
+        ENTROPY.add(&elapsed.to_le_bytes());
+        Ok(PspSocket {
+            registration,
+            socket,
+        })
+    }
+
+    fn with_tls<R, F>(
+        &mut self,
+        mut socket: PspSocket,
+        host: &str,
+        trust_anchor: Option<TrustAnchor>,
+        exchange: F,
+    ) -> Result<R, HandshakeError>
+    where
+        F: FnOnce(&mut dyn Socket) -> R,
+    {
+        let fd = socket.socket.fd();
+        socket.socket.set_recv_flags(SocketRecvFlags::MSG_PEEK);
+        let PspSocket {
+            registration,
+            socket,
+        } = socket;
+        let mut transport = Transport(socket);
+        // declared after the transport, so that the socket is unregistered before it is closed,
+        // including when the handshake fails
+        let _registration = registration;
+        let mut read_buf = TlsSocket::new_buffer();
+        let mut write_buf = TlsSocket::new_buffer();
+        let connection = session::open::<_, RtcClock, _>(
+            &mut transport,
+            host,
+            trust_anchor,
+            ChaCha20Rng::from_seed(tls::seed()),
+            &mut read_buf,
+            &mut write_buf,
+        )
+        .map_err(|e| {
+            if session::is_certificate_error(&e) {
+                HandshakeError::Rejected(format!("{:?}", e))
+            } else {
+                HandshakeError::Failed(format!("{:?}", e))
+            }
+        })?;
+
+        Ok(exchange(&mut TlsSession { connection, fd }))
+    }
+}
+
+/// A connected TCP socket.
+pub struct PspSocket {
+    // unregistered before the socket is closed, as fields are dropped in order
+    registration: Option<Registration>,
+    socket: TcpSocket<Connected>,
+}
+
+impl Socket for PspSocket {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, String> {
+        self.socket
+            .internal_read(buf)
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    fn write(&mut self, buf: &[u8]) -> Result<usize, String> {
+        self.socket
+            .internal_write(buf)
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        tls::flush_socket(&mut self.socket).map_err(|e| format!("{:?}", e))
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), String> {
+        set_socket_timeouts(self.socket.fd(), timeout)
+    }
+}
+
+/// A TLS session, keeping the descriptor of its socket to set the timeouts.
+struct TlsSession<'a> {
+    connection: Connection<'a, &'a mut Transport>,
+    fd: i32,
+}
+
+impl Socket for TlsSession<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, String> {
+        match self.connection.read(buf) {
+            Err(embedded_tls::TlsError::ConnectionClosed) => Ok(0),
+            result => result.map_err(|e| format!("{:?}", e)),
+        }
+    }
+
+    fn write(&mut self, buf: &[u8]) -> Result<usize, String> {
+        self.connection.write(buf).map_err(|e| format!("{:?}", e))
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        self.connection.flush().map_err(|e| format!("{:?}", e))
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), String> {
+        set_socket_timeouts(self.fd, timeout)
+    }
+}
@@ -0,0 +1,94 @@
+use chat_gpsp_core::openai::tls::entropy::EntropyPool;
+use embedded_io::{ErrorKind, ErrorType, Read, Write};
+use embedded_tls::TlsClock;
+use psp::sys;
+use psp_net::{
+    socket::{state::Connected, tcp::TcpSocket},
+    traits::io::EasySocket,
+};
+
+/// Number of timer readings around short sleeps mixed into the pool at startup.
+const JITTER_SAMPLES: usize = 64;
+/// Seconds between the start of the RTC ticks (year 1) and the Unix epoch.
+const UNIX_EPOCH_SECONDS: u64 = 62_135_596_800;
+
+/// The current time, from the real-time clock, for checking certificate validity periods.
+pub struct RtcClock;
+
+impl TlsClock for RtcClock {
+    fn now() -> Option<u64> {
+        let mut tick = 0u64;
+        let res = unsafe { sys::sceRtcGetCurrentTick(&mut tick) };
+        if res < 0 {
+            return None;
+        }
+        // ticks are microseconds
+        (tick / 1_000_000).checked_sub(UNIX_EPOCH_SECONDS)
+    }
+}
+
+/// A TCP socket, as the transport of a TLS [`Connection`](chat_gpsp_core::openai::tls::session::Connection).
+pub struct Transport(pub TcpSocket<Connected>);
+
+impl ErrorType for Transport {
+    type Error = ErrorKind;
+}
+
+impl Read for Transport {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, ErrorKind> {
+        self.0.internal_read(buf).map_err(|_| ErrorKind::Other)
+    }
+}
+
+impl Write for Transport {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, ErrorKind> {
+        self.0.internal_write(buf).map_err(|_| ErrorKind::Other)
+    }
+
+    fn flush(&mut self) -> Result<(), ErrorKind> {
+        flush_socket(&mut self.0)
+    }
+}
+
+/// Send what `socket` still buffers.
+pub(crate) fn flush_socket<S: EasySocket>(socket: &mut S) -> Result<(), ErrorKind> {
+    socket.flush().map_err(|_| ErrorKind::Other)
+}
+
+/// The samples the seeds of the TLS handshakes are derived from.
+pub static ENTROPY: EntropyPool = EntropyPool::new();
+
+/// Mix what little the PSP offers that is hard to guess into [`ENTROPY`]: the clocks, the MAC
+/// address, and the jitter of the scheduler around short sleeps.
+///
+/// `sceKernelUtilsMt19937` adds nothing: it only stretches the seed it is given, so the samples
+/// are hashed into the pool directly. The timings of the connections are added as they are made.
+pub fn gather_entropy() {
+    let mut tick = 0u64;
+    unsafe { sys::sceRtcGetCurrentTick(&mut tick) };
+    ENTROPY.add(&tick.to_le_bytes());
+    ENTROPY.add(&unsafe { sys::sceKernelGetSystemTimeWide() }.to_le_bytes());
+
+    let mut mac = [0u8; 8];
+    if unsafe { sys::sceWlanGetEtherAddr(mac.as_mut_ptr()) } >= 0 {
+        ENTROPY.add(&mac);
+    }
+
+    // the wake-up time after a sleep varies by a few microseconds
+    let mut jitter = [0u8; JITTER_SAMPLES];
+    for sample in jitter.iter_mut() {
+        let before = unsafe { sys::sceKernelGetSystemTimeLow() };
+        unsafe { sys::sceKernelDelayThread(1) };
+        let after = unsafe { sys::sceKernelGetSystemTimeLow() };
+        *sample = after.wrapping_sub(before) as u8;
+    }
+    ENTROPY.add(&jitter);
+}
+
+/// A seed for the random number generator of a TLS handshake.
+///
+/// The current time is mixed in first, so that each seed also depends on when it is drawn.
+pub fn seed() -> [u8; 32] {
+    ENTROPY.add(&unsafe { sys::sceKernelGetSystemTimeWide() }.to_le_bytes());
+    ENTROPY.seed()
+}
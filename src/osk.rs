@@ -1,4 +1,4 @@
-use core::{ffi::c_void, ptr::addr_of_mut};
+use core::{ffi::c_void, fmt::Display, ptr::addr_of_mut};
 
 use alloc::string::String;
 
@@ -12,9 +12,13 @@ use psp::{
     SCREEN_HEIGHT, SCREEN_WIDTH,
 };
 
+use chat_gpsp_core::utf16::decode_ptr;
+
 use crate::osk::osk_state::OskState;
 use crate::utils::*;
 
+pub mod builder;
+pub mod language;
 pub mod osk_state;
 pub mod prelude;
 
@@ -91,6 +95,26 @@ pub fn setup_gu() {
     }
 }
 
+/// An error that can occur while running an OSK dialog.
+///
+/// Each variant carries the error code returned by the firmware.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OskError {
+    Init(i32),
+    Update(i32),
+    Shutdown(i32),
+}
+
+impl Display for OskError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            OskError::Init(code) => write!(f, "cannot init osk ({:#x})", code),
+            OskError::Update(code) => write!(f, "cannot update osk ({:#x})", code),
+            OskError::Shutdown(code) => write!(f, "cannot shutdown osk ({:#x})", code),
+        }
+    }
+}
+
 #[inline]
 /// Initialize an OSK (On-Screen Keyboard) dialog.
 /// Call once to initialize an OSK dialog.
@@ -100,14 +124,13 @@ pub fn setup_gu() {
 ///
 /// # Returns
 /// - `Ok(())` if the OSK was initialized.
-/// - `Err(&str)` if the OSK was not initialized.
-pub fn start_osk(params: &mut SceUtilityOskParams) -> Result<(), &str> {
-    unsafe {
-        if sceUtilityOskInitStart(params as *mut SceUtilityOskParams) == 0 {
-            Ok(())
-        } else {
-            Err("cannot init osk")
-        }
+/// - `Err(OskError::Init)` if the OSK was not initialized.
+pub fn start_osk(params: &mut SceUtilityOskParams) -> Result<(), OskError> {
+    let res = unsafe { sceUtilityOskInitStart(params as *mut SceUtilityOskParams) };
+    if res == 0 {
+        Ok(())
+    } else {
+        Err(OskError::Init(res))
     }
 }
 
@@ -118,13 +141,13 @@ pub fn start_osk(params: &mut SceUtilityOskParams) -> Result<(), &str> {
 /// - `params`: A mutable reference to a [`SceUtilityOskParams`] struct.
 ///
 /// # Returns
-/// - `None` if the OSK was cancelled.
-/// - `Some(String)` if the OSK was not cancelled.
-///
-/// # Panics
-/// Panics if the OSK cannot be updated or shutdown.
-pub fn read_from_osk(params: &mut SceUtilityOskParams) -> Option<String> {
+/// - `Ok(None)` if the OSK was cancelled.
+/// - `Ok(Some(String))` if the OSK was not cancelled.
+/// - `Err(OskError)` if the OSK could not be updated or shutdown. If the update fails, the OSK
+///   is shut down before returning, and [`OskError::Shutdown`] is returned if that fails too.
+pub fn read_from_osk(params: &mut SceUtilityOskParams) -> Result<Option<String>, OskError> {
     let mut done = false;
+    let mut error = None;
     let mut osk_state = OskState::new();
 
     unsafe {
@@ -144,13 +167,20 @@ pub fn read_from_osk(params: &mut SceUtilityOskParams) -> Option<String> {
             match osk_state.get() {
                 PspUtilityDialogState::None => done = true,
                 PspUtilityDialogState::Visible => {
-                    if sceUtilityOskUpdate(1).is_negative() {
-                        panic!("cannot update osk");
+                    let res = sceUtilityOskUpdate(1);
+                    if res.is_negative() {
+                        error = Some(OskError::Update(res));
+                        // a dialog left up would make every later one fail to start
+                        let res = sceUtilityOskShutdownStart();
+                        if res.is_negative() {
+                            return Err(OskError::Shutdown(res));
+                        }
                     }
                 }
                 PspUtilityDialogState::Quit => {
-                    if sceUtilityOskShutdownStart().is_negative() {
-                        panic!("cannot shutdown osk");
+                    let res = sceUtilityOskShutdownStart();
+                    if res.is_negative() {
+                        return Err(OskError::Shutdown(res));
                     }
                 }
                 _ => (),
@@ -160,18 +190,18 @@ pub fn read_from_osk(params: &mut SceUtilityOskParams) -> Option<String> {
         }
     }
 
+    if let Some(error) = error {
+        return Err(error);
+    }
+
     let osk_data: &SceUtilityOskData = unsafe { params.data.as_ref().unwrap() };
 
     match osk_data.result {
-        sys::SceUtilityOskResult::Cancelled => None,
+        sys::SceUtilityOskResult::Cancelled => Ok(None),
         _ => {
-            let out_text = unsafe {
-                mut_ptr_u16_to_vec_char(osk_data.outtext, osk_data.outtextlength as usize)
-            };
-
-            let out_text = String::from_iter(out_text);
+            let out_text = unsafe { decode_ptr(osk_data.outtext, osk_data.outtextlength as usize) };
 
-            Some(out_text)
+            Ok(Some(out_text))
         }
     }
 }
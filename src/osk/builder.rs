@@ -0,0 +1,176 @@
+use alloc::string::String;
+use chat_gpsp_core::utf16::Utf16String;
+use psp::sys::{
+    sceKernelDcacheWritebackAll, SceUtilityOskInputLanguage, SceUtilityOskInputType,
+    SystemParamLanguage, UtilityDialogButtonAccept,
+};
+
+use crate::osk::{
+    language::{osk_language, system_language},
+    prelude::{default_osk_data, default_osk_params},
+    read_from_osk, start_osk, OskError,
+};
+use crate::utils::{system_button_map, Action, Buttons};
+
+/// Default max length of the text that can be entered into the osk, in UTF-16 units.
+pub const DEFAULT_MAX_LENGTH: usize = 128;
+
+/// Builder for an [`Osk`].
+///
+/// # Example
+/// ```no_run
+/// let mut osk = OskBuilder::new("Ask GPT")
+///     .language(SceUtilityOskInputLanguage::Japanese)
+///     .lines(3)
+///     .max_length(256)
+///     .build();
+/// let text = osk.read()?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct OskBuilder {
+    description: String,
+    initial_text: String,
+    max_length: usize,
+    lines: u8,
+    input_type: SceUtilityOskInputType,
+    language: SceUtilityOskInputLanguage,
+    dialog_language: SystemParamLanguage,
+    button_accept: UtilityDialogButtonAccept,
+}
+
+impl OskBuilder {
+    /// Create a new builder, with the given description.
+    ///
+    /// By default, the osk has a single line, accepts all input types, has an empty initial text,
+    /// a max length of [`DEFAULT_MAX_LENGTH`], and uses the console's system language and confirm
+    /// button.
+    pub fn new(description: &str) -> Self {
+        let dialog_language = system_language();
+
+        Self {
+            description: description.into(),
+            initial_text: String::new(),
+            max_length: DEFAULT_MAX_LENGTH,
+            lines: 1,
+            input_type: SceUtilityOskInputType::All,
+            language: osk_language(dialog_language),
+            dialog_language,
+            button_accept: button_accept(system_button_map().buttons(Action::Confirm)),
+        }
+    }
+
+    #[allow(unused)]
+    /// Set the input language of the osk.
+    pub fn language(mut self, language: SceUtilityOskInputLanguage) -> Self {
+        self.language = language;
+        self
+    }
+
+    #[allow(unused)]
+    /// Set the language of the osk dialog itself (its buttons and messages).
+    pub fn dialog_language(mut self, language: SystemParamLanguage) -> Self {
+        self.dialog_language = language;
+        self
+    }
+
+    #[allow(unused)]
+    /// Set the input type of the osk.
+    pub fn input_type(mut self, input_type: SceUtilityOskInputType) -> Self {
+        self.input_type = input_type;
+        self
+    }
+
+    #[allow(unused)]
+    /// Set the number of lines of the osk. It is at least 1.
+    pub fn lines(mut self, lines: u8) -> Self {
+        self.lines = lines.max(1);
+        self
+    }
+
+    #[allow(unused)]
+    /// Set the text the osk starts with.
+    pub fn initial_text(mut self, text: &str) -> Self {
+        self.initial_text = text.into();
+        self
+    }
+
+    /// Set the button that accepts the text, Circle if `confirm` has it, Cross otherwise.
+    pub fn confirm_button(mut self, confirm: Buttons) -> Self {
+        self.button_accept = button_accept(confirm);
+        self
+    }
+
+    /// Set the max length of the text that can be entered, in UTF-16 units.
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Build the [`Osk`].
+    pub fn build(self) -> Osk {
+        Osk {
+            description: Utf16String::from(self.description.as_str()),
+            in_text: Utf16String::from(self.initial_text.as_str()),
+            out_text: Utf16String::zeroed(self.max_length),
+            lines: self.lines,
+            input_type: self.input_type,
+            language: self.language,
+            dialog_language: self.dialog_language,
+            button_accept: self.button_accept,
+        }
+    }
+}
+
+/// The button of the dialogs matching the `confirm` buttons of the application: the dialogs
+/// only accept with Circle or Cross.
+fn button_accept(confirm: Buttons) -> UtilityDialogButtonAccept {
+    if confirm.contains(Buttons::CIRCLE) {
+        UtilityDialogButtonAccept::Circle
+    } else {
+        UtilityDialogButtonAccept::Cross
+    }
+}
+
+/// An OSK (On-Screen Keyboard) ready to be shown, built with an [`OskBuilder`].
+///
+/// It owns the buffers the OSK dialog reads from and writes to.
+#[derive(Debug, Clone)]
+pub struct Osk {
+    description: Utf16String,
+    in_text: Utf16String,
+    out_text: Utf16String,
+    lines: u8,
+    input_type: SceUtilityOskInputType,
+    language: SceUtilityOskInputLanguage,
+    dialog_language: SystemParamLanguage,
+    button_accept: UtilityDialogButtonAccept,
+}
+
+impl Osk {
+    /// Show the osk, and wait for the user to close it.
+    ///
+    /// # Returns
+    /// - `Ok(None)` if the osk was cancelled.
+    /// - `Ok(Some(String))` with the entered text otherwise.
+    /// - `Err(OskError)` if the osk could not be started, updated or shutdown.
+    pub fn read(&mut self) -> Result<Option<String>, OskError> {
+        self.out_text = Utf16String::zeroed(self.out_text.capacity());
+
+        let mut osk_data =
+            default_osk_data(&mut self.description, &mut self.in_text, &mut self.out_text);
+        osk_data.language = self.language;
+        osk_data.inputtype = self.input_type;
+        osk_data.lines = self.lines as i32;
+
+        let params = &mut default_osk_params(&mut osk_data, self.button_accept);
+        params.base.language = self.dialog_language;
+
+        unsafe {
+            sceKernelDcacheWritebackAll();
+        }
+
+        start_osk(params)?;
+
+        read_from_osk(params)
+    }
+}
@@ -0,0 +1,40 @@
+use psp::sys::{
+    sceUtilityGetSystemParamInt, SceUtilityOskInputLanguage, SystemParamId, SystemParamLanguage,
+};
+
+#[inline]
+/// Get the language the console is set to, reading it from the system parameters.
+///
+/// # Returns
+/// The system language, or [`SystemParamLanguage::English`] if it cannot be read.
+pub fn system_language() -> SystemParamLanguage {
+    let mut value = 0i32;
+    let res = unsafe { sceUtilityGetSystemParamInt(SystemParamId::Language, &mut value) };
+    if res != 0 {
+        return SystemParamLanguage::English;
+    }
+
+    SystemParamLanguage::try_from(value as u32).unwrap_or(SystemParamLanguage::English)
+}
+
+#[inline]
+/// Get the OSK input language matching a system language.
+///
+/// Chinese has no dedicated OSK layout, so [`SceUtilityOskInputLanguage::Default`] is used for it.
+pub fn osk_language(language: SystemParamLanguage) -> SceUtilityOskInputLanguage {
+    match language {
+        SystemParamLanguage::Japanese => SceUtilityOskInputLanguage::Japanese,
+        SystemParamLanguage::English => SceUtilityOskInputLanguage::English,
+        SystemParamLanguage::French => SceUtilityOskInputLanguage::French,
+        SystemParamLanguage::Spanish => SceUtilityOskInputLanguage::Spanish,
+        SystemParamLanguage::German => SceUtilityOskInputLanguage::German,
+        SystemParamLanguage::Italian => SceUtilityOskInputLanguage::Italian,
+        SystemParamLanguage::Dutch => SceUtilityOskInputLanguage::Dutch,
+        SystemParamLanguage::Portugese => SceUtilityOskInputLanguage::Portugese,
+        SystemParamLanguage::Russian => SceUtilityOskInputLanguage::Russian,
+        SystemParamLanguage::Korean => SceUtilityOskInputLanguage::Korean,
+        SystemParamLanguage::ChineseTraditional | SystemParamLanguage::ChineseSimplified => {
+            SceUtilityOskInputLanguage::Default
+        }
+    }
+}
@@ -5,30 +5,31 @@ use psp::sys::{
     SceUtilityOskParams, UtilityDialogCommon,
 };
 
-use crate::utils::str_to_u16_mut_ptr;
+use chat_gpsp_core::utf16::Utf16String;
 
 #[inline]
 /// Create a [`SceUtilityOskData`] with default values.
-/// By default, the osk will be in English, the initial text will be empty, and the description and
-/// max text length will be the ones provided.
+/// By default, the osk will be in English, and the description, initial text and max text length
+/// will be the ones provided.
 ///
 /// # Parameters
-/// * `description` - A mutable pointer to a u16 array containing the description of the osk.
-/// * `max_text_length` - The maximum length of the text that can be entered into the osk.
-/// * `out_text` - A mutable pointer to a u16 array that will contain the text entered into the osk.
+/// * `description` - The description of the osk.
+/// * `in_text` - The initial text of the osk.
+/// * `out_text` - The buffer that will contain the text entered into the osk. The max text length
+///   is its [capacity](Utf16String::capacity).
 ///
 /// # Returns
 /// A [`SceUtilityOskData`].
 ///
 /// # Safety
-/// Please ensure that `description` and `out_text` are valid pointers, and that `max_text_length`
-/// is correct. To be correct, `max_text_length` must be the length of the array `out_text` points to.
+/// The returned struct points into `description`, `in_text` and `out_text`: please ensure that
+/// they outlive it, and that they are not moved while the osk is running.
 pub fn default_osk_data(
-    description: *mut u16,
-    max_text_length: i32,
-    out_text: *mut u16,
+    description: &mut Utf16String,
+    in_text: &mut Utf16String,
+    out_text: &mut Utf16String,
 ) -> SceUtilityOskData {
-    let in_text = str_to_u16_mut_ptr("\0");
+    let max_text_length = out_text.capacity() as i32;
 
     SceUtilityOskData {
         unk_00: 0,
@@ -38,24 +39,27 @@ pub fn default_osk_data(
         inputtype: SceUtilityOskInputType::All,
         lines: 1,
         unk_24: 0,
-        desc: description,
-        intext: in_text,
+        desc: description.as_mut_ptr(),
+        intext: in_text.as_mut_ptr(),
         outtextlength: max_text_length,
-        outtext: out_text,
+        outtext: out_text.as_mut_ptr(),
         result: sys::SceUtilityOskResult::Unchanged,
         outtextlimit: max_text_length,
     }
 }
 
 #[inline]
-/// Create a [`SceUtilityOskParams`] with default values.
-pub fn default_osk_params(data: &mut SceUtilityOskData) -> SceUtilityOskParams {
+/// Create a [`SceUtilityOskParams`] with default values, confirming with `button_accept`.
+pub fn default_osk_params(
+    data: &mut SceUtilityOskData,
+    button_accept: sys::UtilityDialogButtonAccept,
+) -> SceUtilityOskParams {
     SceUtilityOskParams {
         base: UtilityDialogCommon {
             // size of data
             size: size_of::<SceUtilityOskParams>() as u32,
             language: sys::SystemParamLanguage::English,
-            button_accept: sys::UtilityDialogButtonAccept::Cross,
+            button_accept,
             graphics_thread: 0x11,
             access_thread: 0x13,
             font_thread: 0x12,
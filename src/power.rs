@@ -0,0 +1,149 @@
+//! The power callback, telling when the PSP goes to sleep and wakes up.
+
+use core::{
+    ffi::c_void,
+    fmt::Display,
+    ptr,
+    sync::atomic::{AtomicI32, Ordering},
+};
+
+use chat_gpsp_core::{
+    net::abort::AbortSlot,
+    power::{PowerEvents, SUSPENDING},
+};
+use psp::sys::{self, ThreadAttributes};
+
+use crate::openai::network;
+
+/// Priority of the callback thread. Higher than the main thread, so that events are recorded as
+/// they happen.
+const CALLBACK_PRIORITY: i32 = 0x11;
+/// Stack size of the callback thread.
+const CALLBACK_STACK_SIZE: i32 = 0x1000;
+/// The value of [`REGISTRATION`] until the callback thread registered the callback.
+const PENDING: i32 = 1;
+/// How long to wait between two checks of [`REGISTRATION`], in microseconds.
+const REGISTRATION_POLL: u32 = 1000;
+
+/// The suspends and resumes since the application started.
+pub static POWER_EVENTS: PowerEvents = PowerEvents::new();
+
+/// The request in flight, aborted as the PSP goes to sleep: the sleep cuts its connection, and
+/// its worker would otherwise stay blocked on it until woken up.
+pub static IN_FLIGHT: AbortSlot = AbortSlot::new();
+
+/// The result of the registration of the callback, by the callback thread: 0, or the error code
+/// of the call that failed.
+static REGISTRATION: AtomicI32 = AtomicI32::new(PENDING);
+
+/// Errors that can occur when starting the callback thread.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PowerError {
+    CreateThread(i32),
+    StartThread(i32),
+    RegisterCallback(i32),
+}
+
+impl Display for PowerError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PowerError::CreateThread(code) => {
+                write!(
+                    f,
+                    "Failed to create power callback thread (error {:#x})",
+                    code
+                )
+            }
+            PowerError::StartThread(code) => {
+                write!(
+                    f,
+                    "Failed to start power callback thread (error {:#x})",
+                    code
+                )
+            }
+            PowerError::RegisterCallback(code) => {
+                write!(f, "Failed to register power callback (error {:#x})", code)
+            }
+        }
+    }
+}
+
+/// Record the power events in [`POWER_EVENTS`], from a thread waiting for the power callbacks.
+///
+/// # Errors
+/// A [`PowerError`] if the thread cannot be started, or the callback cannot be registered.
+pub fn register_callback() -> Result<(), PowerError> {
+    let thread = unsafe {
+        sys::sceKernelCreateThread(
+            c"power_thread".as_ptr() as *const u8,
+            callback_thread,
+            CALLBACK_PRIORITY,
+            CALLBACK_STACK_SIZE,
+            ThreadAttributes::USER,
+            ptr::null_mut(),
+        )
+    };
+    if thread.0 < 0 {
+        return Err(PowerError::CreateThread(thread.0));
+    }
+
+    let res = unsafe { sys::sceKernelStartThread(thread, 0, ptr::null_mut()) };
+    if res < 0 {
+        unsafe {
+            sys::sceKernelDeleteThread(thread);
+        }
+        return Err(PowerError::StartThread(res));
+    }
+
+    // the thread has a higher priority, so it is usually done by now
+    let res = loop {
+        match REGISTRATION.load(Ordering::Acquire) {
+            PENDING => unsafe {
+                sys::sceKernelDelayThread(REGISTRATION_POLL);
+            },
+            res => break res,
+        }
+    };
+    if res < 0 {
+        unsafe {
+            sys::sceKernelWaitThreadEnd(thread, ptr::null_mut());
+            sys::sceKernelDeleteThread(thread);
+        }
+        return Err(PowerError::RegisterCallback(res));
+    }
+    Ok(())
+}
+
+/// Entry point of the callback thread: callbacks only run while their thread sleeps.
+unsafe extern "C" fn callback_thread(_args: usize, _argp: *mut c_void) -> i32 {
+    let callback = sys::sceKernelCreateCallback(
+        c"power_callback".as_ptr() as *const u8,
+        power_callback,
+        ptr::null_mut(),
+    );
+    if callback.0 < 0 {
+        REGISTRATION.store(callback.0, Ordering::Release);
+        return callback.0;
+    }
+    // -1 lets the system choose a free slot, whose number is returned
+    let res = sys::scePowerRegisterCallback(-1, callback);
+    if res < 0 {
+        sys::sceKernelDeleteCallback(callback);
+        REGISTRATION.store(res, Ordering::Release);
+        return res;
+    }
+
+    REGISTRATION.store(0, Ordering::Release);
+    sys::sceKernelSleepThreadCB();
+    0
+}
+
+unsafe extern "C" fn power_callback(_count: i32, power_info: i32, _arg: *mut c_void) -> i32 {
+    POWER_EVENTS.record(power_info as u32);
+    if power_info as u32 & SUSPENDING != 0 {
+        if let Some(abort) = IN_FLIGHT.take() {
+            network::abort(&abort);
+        }
+    }
+    0
+}
@@ -0,0 +1,15 @@
+pub use chat_gpsp_core::layout::{
+    action_hint, draw_hint, draw_lines, draw_lines_at_size, draw_title, scroll_hint,
+    scroll_lines_at_size, Menu, CONTENT_LINES, HINT_Y,
+};
+
+pub mod calibration;
+pub mod chat;
+pub mod composer;
+pub mod error;
+pub mod history;
+pub mod menu;
+pub mod network;
+pub mod settings;
+pub mod splash;
+pub mod templates;
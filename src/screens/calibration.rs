@@ -0,0 +1,133 @@
+use alloc::format;
+
+use chat_gpsp_core::input::analog::Calibrator;
+
+use crate::{
+    app::{AppContext, Screen, ScreenTransition},
+    fs::PspFs,
+    gfx::{color, Renderer},
+    screens::{action_hint, draw_hint, draw_lines, draw_title, HINT_Y},
+    utils::{Action, Stick},
+};
+
+/// Number of frames the stick is sampled at rest.
+const REST_FRAMES: u32 = 30;
+/// Side of the square showing the position of the stick, in pixels.
+const PAD_SIZE: i16 = 64;
+/// Top left corner of the square showing the position of the stick.
+const PAD_X: i16 = 400;
+const PAD_Y: i16 = 32;
+
+/// The steps of the calibration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    /// Waiting for the user to let go of the stick.
+    Ready,
+    /// Sampling the stick at rest, for a number of frames already.
+    Rest(u32),
+    /// Sampling the stick swept around its edges.
+    Sweep,
+}
+
+/// The calibration of the analog stick: its position at rest, then its travel.
+///
+/// The calibration is applied and saved to the configuration once measured.
+pub struct CalibrationScreen {
+    step: Step,
+    calibrator: Calibrator,
+    /// Whether the last sweep did not reach far enough.
+    failed: bool,
+}
+
+impl CalibrationScreen {
+    pub fn new() -> Self {
+        Self {
+            step: Step::Ready,
+            calibrator: Calibrator::default(),
+            failed: false,
+        }
+    }
+}
+
+impl Screen for CalibrationScreen {
+    fn update(&mut self, ctx: &mut AppContext) -> ScreenTransition {
+        if ctx.input.is_action_pressed(Action::Cancel) {
+            return ScreenTransition::Pop;
+        }
+
+        let stick = ctx.input.stick();
+        match self.step {
+            Step::Ready => {
+                if ctx.input.is_action_pressed(Action::Confirm) {
+                    self.step = Step::Rest(0);
+                }
+            }
+            Step::Rest(frames) => {
+                self.calibrator.sample_rest(stick);
+                self.step = if frames + 1 >= REST_FRAMES {
+                    Step::Sweep
+                } else {
+                    Step::Rest(frames + 1)
+                };
+            }
+            Step::Sweep => {
+                self.calibrator.sample_sweep(stick);
+                if ctx.input.is_action_pressed(Action::Confirm) {
+                    let Some(calibration) = self.calibrator.calibration() else {
+                        self.failed = true;
+                        return ScreenTransition::Stay;
+                    };
+                    ctx.config.stick_calibration = Some(calibration);
+                    ctx.input.set_analog(ctx.config.analog());
+                    // the calibration still applies to this session
+                    let _ = ctx.config.save(&mut PspFs);
+                    return ScreenTransition::Pop;
+                }
+            }
+        }
+
+        ScreenTransition::Stay
+    }
+
+    fn render(&self, ctx: &AppContext, renderer: &mut Renderer) {
+        draw_title(renderer, "Calibrate the stick");
+
+        let confirm = ctx.input.label(Action::Confirm);
+        let instructions = match self.step {
+            Step::Ready => format!("Let go of the stick, then press {}.", confirm),
+            Step::Rest(_) => "Measuring the stick at rest...".into(),
+            Step::Sweep if self.failed => format!(
+                "Not far enough: sweep the whole edge, then press {}.",
+                confirm
+            ),
+            Step::Sweep => format!("Sweep the stick around its edge, then press {}.", confirm),
+        };
+        draw_lines(renderer, &[instructions], 0);
+
+        let stick = ctx.input.stick();
+        let (x, y) = ctx.input.tilt();
+        let position = format!("Position {},{}  tilt {},{}", stick.x, stick.y, x, y);
+        renderer.draw_text(8, HINT_Y - 12, &position, color::GRAY);
+
+        self.render_pad(renderer, stick);
+        draw_hint(
+            renderer,
+            &action_hint(&ctx.input, &[(Action::Cancel, "back")]),
+        );
+    }
+}
+
+impl CalibrationScreen {
+    /// Draw the travel of the stick as a square, with its current position and, once measured,
+    /// its position at rest.
+    fn render_pad(&self, renderer: &mut Renderer, stick: Stick) {
+        let scale = |position: u8| position as i16 * (PAD_SIZE - 1) / 255;
+        renderer.fill_rect(PAD_X, PAD_Y, PAD_SIZE, PAD_SIZE, color::DARK_GRAY);
+        if let Some(center) = self.calibrator.center() {
+            let (x, y) = (PAD_X + scale(center.x), PAD_Y + scale(center.y));
+            renderer.fill_rect(x - 1, y - 1, 3, 3, color::GRAY);
+        }
+        let (x, y) = (PAD_X + scale(stick.x), PAD_Y + scale(stick.y));
+        renderer.fill_rect(x - 1, y - 1, 3, 3, color::YELLOW);
+    }
+}
@@ -0,0 +1,306 @@
+use alloc::{boxed::Box, format, string::String, vec::Vec};
+
+use chat_gpsp_core::{layout::TextSize, openai::tools::ToolRegistry, templates::Template};
+
+use crate::{
+    app::{AppContext, Exchange, Screen, ScreenTransition},
+    device::PspDevice,
+    fs::PspFs,
+    gfx::{color, wrap_text, Renderer, SCREEN_COLUMNS},
+    net,
+    openai::{self, network::PspNetwork, OpenAi, OpenAiError},
+    power::IN_FLIGHT,
+    screens::{
+        action_hint, composer::ComposerScreen, draw_hint, draw_lines_at_size, draw_title,
+        error::ErrorScreen, scroll_hint, scroll_lines_at_size, HINT_Y,
+    },
+    utils::{Action, Buttons},
+    worker::Task,
+};
+
+/// Frames of the spinner shown while waiting for an answer.
+const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+/// Number of frames each spinner frame is shown for.
+const SPINNER_FRAME_DURATION: u64 = 8;
+
+/// A prompt of the user.
+#[derive(Debug, Clone, Default)]
+struct Prompt {
+    /// What the user asked, as shown in the chat and the history.
+    shown: String,
+    /// What is sent to GPT: a typed prompt wrapped in the template, or a quick reply as is.
+    sent: String,
+}
+
+/// A request to GPT running on a worker thread.
+///
+/// The worker owns a copy of the client, so that a cancelled request leaves the conversation
+/// untouched. The updated client is sent back with the answer.
+struct PendingRequest {
+    prompt: Prompt,
+    task: Task<(OpenAi, Result<String, OpenAiError>)>,
+    /// The number of times the PSP had woken up from sleep when the request was sent.
+    resumes: u32,
+}
+
+/// A conversation with GPT.
+///
+/// The conversation keeps its history, so that every prompt is answered in the context of the
+/// previous ones. Prompts asked while the network is down are queued, and sent once it is back,
+/// as are those cut by a sleep of the PSP.
+pub struct ChatScreen {
+    openai: OpenAi,
+    /// The persona of the conversation, wrapping every prompt, if any.
+    template: Option<Template>,
+    /// The prompts sent with a button, without typing them.
+    quick_replies: Vec<(Buttons, String)>,
+    /// The size of the text, as set when the conversation started.
+    text_size: TextSize,
+    lines: Vec<String>,
+    first_line: usize,
+    /// A prompt waiting for the network to be back.
+    queued: Option<Prompt>,
+    pending: Option<PendingRequest>,
+}
+
+impl ChatScreen {
+    /// Start a new conversation.
+    ///
+    /// # Errors
+    /// An [`ErrorScreen`] to show, if the network is not connected or the client cannot be
+    /// created.
+    pub fn new(ctx: &AppContext) -> Result<Self, ErrorScreen> {
+        let openai_context = ctx
+            .openai_context
+            .as_ref()
+            .ok_or_else(|| ErrorScreen::new("The network is not connected."))?;
+        let mut openai = OpenAi::new(openai_context)
+            .map_err(|e| ErrorScreen::new(&format!("Failed to create OpenAI client: {:?}", e)))?;
+        let settings = &ctx.config.settings;
+        openai.set_model(settings.model.clone(), settings.temperature());
+        let template = ctx.template.clone();
+        openai.set_system_prompt(template.as_ref().and_then(|t| t.system.clone()));
+
+        Ok(Self {
+            openai,
+            template,
+            quick_replies: ctx.config.quick_replies(),
+            text_size: settings.text_size,
+            lines: Vec::new(),
+            first_line: 0,
+            queued: None,
+            pending: None,
+        })
+    }
+
+    fn push_message(&mut self, author: &str, content: &str) {
+        let message = format!("{}: {}", author, content);
+        self.lines
+            .extend(wrap_text(&message, self.text_size.columns() - 2));
+        self.lines.push(String::new());
+        self.first_line = self.lines.len().saturating_sub(self.text_size.lines());
+    }
+
+    fn scroll(&mut self, ctx: &AppContext) {
+        let len = self.lines.len();
+        scroll_lines_at_size(&ctx.input, &mut self.first_line, len, self.text_size);
+    }
+
+    /// Show `prompt`, and queue it to be sent as `sent`.
+    fn queue(&mut self, prompt: String, sent: String) {
+        self.push_message("You", &prompt);
+        self.queued = Some(Prompt {
+            shown: prompt,
+            sent,
+        });
+    }
+
+    /// Send `prompt` on a worker thread, using the current OpenAI context.
+    fn send(&mut self, ctx: &mut AppContext, prompt: Prompt) -> Result<(), ErrorScreen> {
+        let mut openai = self.openai.clone();
+        // the copy shares the resolver, so the lookups on the worker are cached for the next ones
+        let mut openai_context = ctx.openai_context.clone();
+        let request = prompt.sent.clone();
+        let task = Task::spawn(move |abort| {
+            // cancelling the task, or the PSP going to sleep, shuts the connection down, ending
+            // the request at once; an abort during the lookup keeps it from connecting at all
+            IN_FLIGHT.set(&abort);
+            if let Some(openai_context) = &mut openai_context {
+                // looks the host up again if its address expired; the previous one is kept if
+                // that fails, and the request tells whether it still works
+                let _ = openai::wait_for_resolver(&abort, || openai_context.refresh());
+                openai.update_context(openai_context);
+            }
+
+            openai.set_network(PspNetwork::abortable(abort.clone()));
+            let mut tools = ToolRegistry::on_device(PspDevice, PspFs);
+            let answer = openai.ask_gpt_with_tools(&request, &mut tools);
+            openai.set_network(PspNetwork::default());
+            IN_FLIGHT.clear(&abort);
+
+            if let (Err(OpenAiError::TlsError(_)), Some(openai_context)) =
+                (&answer, &mut openai_context)
+            {
+                // the host may have moved to another address: look it up for the next request
+                let _ = openai::wait_for_resolver(&abort, || openai_context.re_resolve());
+            }
+            (openai, answer)
+        })
+        .map_err(|e| ErrorScreen::new(&format!("Failed to send the prompt: {}", e)))?;
+
+        self.pending = Some(PendingRequest {
+            prompt,
+            task,
+            resumes: ctx.resumes,
+        });
+        Ok(())
+    }
+
+    /// Handle the input and the answer while a request is in flight.
+    fn update_pending(&mut self, ctx: &mut AppContext) -> ScreenTransition {
+        let Some(pending) = &mut self.pending else {
+            return ScreenTransition::Stay;
+        };
+
+        if pending.resumes != ctx.resumes {
+            // the connection died with the sleep: send the prompt again once the link is back
+            self.queued = Some(core::mem::take(&mut pending.prompt));
+            self.pending = None;
+            return ScreenTransition::Stay;
+        }
+
+        if let Some((openai, answer)) = pending.task.poll() {
+            let prompt = core::mem::take(&mut pending.prompt);
+            self.pending = None;
+
+            return match answer {
+                Ok(answer) => {
+                    // a failed request leaves the conversation as it was before it
+                    self.openai = openai;
+                    self.push_message("GPT", &answer);
+                    ctx.history.push(Exchange {
+                        prompt: prompt.shown,
+                        answer,
+                    });
+                    ScreenTransition::Stay
+                }
+                // the link dropped during the request: send it again once it is back
+                Err(_) if !ctx.is_online() || !net::is_connected() => {
+                    self.queued = Some(prompt);
+                    ScreenTransition::Stay
+                }
+                Err(e) => {
+                    let message = format!("Failed to get an answer from OpenAI: {:?}", e);
+                    ScreenTransition::Push(Box::new(ErrorScreen::new(&message)))
+                }
+            };
+        }
+
+        if ctx.input.is_action_pressed(Action::Cancel) {
+            // dropping the task aborts the request, and discards its answer
+            self.pending = None;
+            self.push_message("GPT", "(cancelled)");
+        }
+        self.scroll(ctx);
+
+        ScreenTransition::Stay
+    }
+}
+
+impl Screen for ChatScreen {
+    fn update(&mut self, ctx: &mut AppContext) -> ScreenTransition {
+        if self.pending.is_some() {
+            return self.update_pending(ctx);
+        }
+
+        if let Some(prompt) = ctx.composed_prompt.take() {
+            let sent = match &self.template {
+                Some(template) => template.apply(&prompt),
+                None => prompt.clone(),
+            };
+            self.queue(prompt, sent);
+        }
+
+        if self.queued.is_some() {
+            if ctx.is_online() {
+                let prompt = self.queued.take().unwrap_or_default();
+                if let Err(error) = self.send(ctx, prompt) {
+                    return ScreenTransition::Push(Box::new(error));
+                }
+            } else if ctx.input.is_action_pressed(Action::Cancel) {
+                self.queued = None;
+                self.push_message("GPT", "(cancelled)");
+            }
+            self.scroll(ctx);
+            return ScreenTransition::Stay;
+        }
+
+        self.scroll(ctx);
+
+        let quick_reply = self
+            .quick_replies
+            .iter()
+            .find(|(buttons, _)| ctx.input.is_chord_pressed(*buttons));
+        if let Some((_, prompt)) = quick_reply {
+            // follows up on the answer, so the template does not apply
+            self.queue(prompt.clone(), prompt.clone());
+            return ScreenTransition::Stay;
+        }
+
+        if ctx.input.is_action_pressed(Action::Confirm) {
+            return ScreenTransition::Push(Box::new(ComposerScreen::new()));
+        }
+        if ctx.input.is_action_pressed(Action::Cancel) {
+            return ScreenTransition::Pop;
+        }
+
+        ScreenTransition::Stay
+    }
+
+    fn render(&self, ctx: &AppContext, renderer: &mut Renderer) {
+        match &self.template {
+            Some(template) => draw_title(renderer, &format!("Chat: {}", template.name)),
+            None => draw_title(renderer, "Chat"),
+        }
+        draw_lines_at_size(renderer, &self.lines, self.first_line, self.text_size);
+
+        let cancel_hint = format!(
+            "{}  {}",
+            action_hint(&ctx.input, &[(Action::Cancel, "cancel")]),
+            scroll_hint(&ctx.input)
+        );
+        if let Some(pending) = &self.pending {
+            let spinner = SPINNER[(ctx.frame / SPINNER_FRAME_DURATION) as usize % SPINNER.len()];
+            let elapsed = pending.task.elapsed_ms();
+            let status = format!(
+                "{} Waiting for GPT... {}.{}s",
+                spinner,
+                elapsed / 1000,
+                elapsed % 1000 / 100
+            );
+            renderer.draw_text(8, HINT_Y - 12, &status, color::YELLOW);
+            draw_hint(renderer, &cancel_hint);
+        } else if self.queued.is_some() {
+            let status = "Waiting for the network to send the prompt...";
+            renderer.draw_text(8, HINT_Y - 12, status, color::YELLOW);
+            draw_hint(renderer, &cancel_hint);
+        } else {
+            let replies: Vec<String> = self
+                .quick_replies
+                .iter()
+                .map(|(buttons, prompt)| format!("{}: {}", buttons.label(), prompt))
+                .collect();
+            let replies: String = replies
+                .join("  ")
+                .chars()
+                .take(SCREEN_COLUMNS - 2)
+                .collect();
+            renderer.draw_text(8, HINT_Y - 12, &replies, color::GRAY);
+            let hint = action_hint(
+                &ctx.input,
+                &[(Action::Confirm, "ask"), (Action::Cancel, "back")],
+            );
+            draw_hint(renderer, &format!("{}  {}", hint, scroll_hint(&ctx.input)));
+        }
+    }
+}
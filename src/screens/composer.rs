@@ -0,0 +1,123 @@
+use alloc::{format, string::String, vec::Vec};
+
+use crate::{
+    app::{AppContext, Screen, ScreenTransition},
+    composer::{PromptComposer, MAX_CHUNKS},
+    gfx::{color, wrap_text, Renderer, SCREEN_COLUMNS},
+    screens::{draw_hint, draw_lines, draw_title, CONTENT_LINES, HINT_Y},
+    utils::{Action, Buttons},
+};
+
+/// The prompt composer screen, letting the user build a prompt out of several entries.
+///
+/// Entries that are cancelled, empty or whitespace-only are not added to the draft, and an
+/// empty draft cannot be sent. A sent draft is left in
+/// [`AppContext::composed_prompt`](crate::app::AppContext::composed_prompt).
+///
+/// # Controls
+/// - Confirm (X by default): append a new entry, typed with the current input method
+/// - Square: delete the last entry
+/// - Triangle: clear the draft
+/// - Menu (Start by default): send the draft
+/// - Cancel (Circle by default): cancel
+pub struct ComposerScreen {
+    composer: PromptComposer,
+    lines: Vec<String>,
+    message: Option<String>,
+}
+
+impl ComposerScreen {
+    pub fn new() -> Self {
+        Self {
+            composer: PromptComposer::new(),
+            lines: Vec::new(),
+            message: None,
+        }
+    }
+
+    fn update_lines(&mut self) {
+        self.lines = wrap_text(&self.composer.draft(), SCREEN_COLUMNS - 2);
+    }
+}
+
+impl Screen for ComposerScreen {
+    fn update(&mut self, ctx: &mut AppContext) -> ScreenTransition {
+        if ctx.text_input.is_open() {
+            match ctx.text_input.update(&mut ctx.input) {
+                None => return ScreenTransition::Stay,
+                Some(Ok(Some(chunk))) => {
+                    self.composer.push(chunk);
+                }
+                Some(Ok(None)) => (),
+                Some(Err(e)) => self.message = Some(format!("Error: {}. Please try again.", e)),
+            }
+            self.update_lines();
+            return ScreenTransition::Stay;
+        }
+        let input = &ctx.input;
+
+        if input.is_action_pressed(Action::Confirm) {
+            self.message = None;
+            if self.composer.is_full() {
+                self.message = Some(format!(
+                    "The prompt cannot have more than {} parts.",
+                    MAX_CHUNKS
+                ));
+                return ScreenTransition::Stay;
+            }
+
+            ctx.text_input.open("Ask GPT");
+        } else if input.is_pressed(Buttons::SQUARE) {
+            self.composer.pop();
+            self.update_lines();
+        } else if input.is_pressed(Buttons::TRIANGLE) {
+            self.composer.clear();
+            self.update_lines();
+        } else if input.is_action_pressed(Action::Menu) {
+            if self.composer.is_empty() {
+                self.message = Some("The prompt is empty, nothing to send.".into());
+                return ScreenTransition::Stay;
+            }
+            ctx.composed_prompt = Some(self.composer.draft());
+            return ScreenTransition::Pop;
+        } else if input.is_action_pressed(Action::Cancel) {
+            return ScreenTransition::Pop;
+        }
+
+        ScreenTransition::Stay
+    }
+
+    fn render(&self, ctx: &AppContext, renderer: &mut Renderer) {
+        if ctx.text_input.render(&ctx.input, renderer) {
+            return;
+        }
+        let title = format!(
+            "Prompt draft ({}/{} parts)",
+            self.composer.len(),
+            MAX_CHUNKS
+        );
+        draw_title(renderer, &title);
+
+        if self.composer.is_empty() {
+            draw_lines(renderer, &["(empty)"], 0);
+        } else {
+            // keep the end of the draft visible
+            let first = self.lines.len().saturating_sub(CONTENT_LINES - 1);
+            draw_lines(renderer, &self.lines, first);
+        }
+        if let Some(message) = &self.message {
+            renderer.draw_text(8, HINT_Y - 12, message, color::YELLOW);
+        }
+
+        let input = &ctx.input;
+        let hint = format!(
+            "{}: add  {}: delete last  {}: clear  {}: send  {}: cancel",
+            input.label(Action::Confirm),
+            Buttons::SQUARE.label(),
+            Buttons::TRIANGLE.label(),
+            input.label(Action::Menu),
+            input.label(Action::Cancel)
+        );
+        draw_hint(renderer, &hint);
+    }
+}
@@ -0,0 +1,67 @@
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+    app::{AppContext, Screen, ScreenTransition},
+    gfx::{wrap_text, Renderer, SCREEN_COLUMNS},
+    screens::{action_hint, draw_hint, draw_lines, draw_title},
+    utils::Action,
+};
+
+/// A screen showing an error message.
+///
+/// A recoverable error goes back to the previous screen when dismissed, while a fatal one exits
+/// the application.
+pub struct ErrorScreen {
+    lines: Vec<String>,
+    fatal: bool,
+}
+
+impl ErrorScreen {
+    /// Create an error screen going back to the previous screen when dismissed.
+    pub fn new(message: &str) -> Self {
+        Self {
+            lines: wrap_text(message, SCREEN_COLUMNS - 2),
+            fatal: false,
+        }
+    }
+
+    /// Create an error screen exiting the application when dismissed.
+    pub fn fatal(message: &str) -> Self {
+        Self {
+            lines: wrap_text(message, SCREEN_COLUMNS - 2),
+            fatal: true,
+        }
+    }
+}
+
+impl Screen for ErrorScreen {
+    fn update(&mut self, ctx: &mut AppContext) -> ScreenTransition {
+        if !ctx.input.is_action_pressed(Action::Confirm)
+            && !ctx.input.is_action_pressed(Action::Cancel)
+        {
+            return ScreenTransition::Stay;
+        }
+
+        if self.fatal {
+            ScreenTransition::Exit
+        } else {
+            ScreenTransition::Pop
+        }
+    }
+
+    fn render(&self, ctx: &AppContext, renderer: &mut Renderer) {
+        draw_title(renderer, "Error");
+        draw_lines(renderer, &self.lines, 0);
+        if self.fatal {
+            draw_hint(
+                renderer,
+                &action_hint(&ctx.input, &[(Action::Confirm, "exit")]),
+            );
+        } else {
+            draw_hint(
+                renderer,
+                &action_hint(&ctx.input, &[(Action::Confirm, "back")]),
+            );
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use alloc::{format, string::String, vec::Vec};
+
+use crate::{
+    app::{AppContext, Screen, ScreenTransition},
+    gfx::{wrap_text, Renderer, SCREEN_COLUMNS},
+    screens::{
+        action_hint, draw_hint, draw_lines, draw_lines_at_size, draw_title, scroll_hint,
+        scroll_lines_at_size, Menu,
+    },
+    utils::Action,
+};
+
+/// Number of characters of a prompt shown in the list of exchanges.
+const PREVIEW_LENGTH: usize = SCREEN_COLUMNS - 4;
+
+/// The history browser, listing the exchanges of this session and showing them in full.
+pub struct HistoryScreen {
+    /// The index of each exchange, with a preview of its prompt.
+    menu: Option<Menu<(usize, String)>>,
+    /// The lines of the exchange being shown, if any.
+    lines: Option<Vec<String>>,
+    first_line: usize,
+}
+
+impl HistoryScreen {
+    pub fn new() -> Self {
+        Self {
+            menu: None,
+            lines: None,
+            first_line: 0,
+        }
+    }
+}
+
+impl Screen for HistoryScreen {
+    fn update(&mut self, ctx: &mut AppContext) -> ScreenTransition {
+        // most recent first
+        let menu = self.menu.get_or_insert_with(|| {
+            Menu::new(
+                ctx.history
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(i, exchange)| {
+                        (i, exchange.prompt.chars().take(PREVIEW_LENGTH).collect())
+                    })
+                    .collect(),
+            )
+        });
+
+        if let Some(lines) = &self.lines {
+            let size = ctx.config.settings.text_size;
+            scroll_lines_at_size(&ctx.input, &mut self.first_line, lines.len(), size);
+            if ctx.input.is_action_pressed(Action::Cancel) {
+                self.lines = None;
+            }
+            return ScreenTransition::Stay;
+        }
+
+        menu.update(&ctx.input);
+        if ctx.input.is_action_pressed(Action::Confirm) {
+            if let Some(exchange) = menu.selected().and_then(|(i, _)| ctx.history.get(*i)) {
+                let columns = ctx.config.settings.text_size.columns() - 2;
+                let mut lines = wrap_text(&exchange.prompt, columns);
+                lines.push(String::new());
+                lines.extend(wrap_text(&exchange.answer, columns));
+                self.lines = Some(lines);
+                self.first_line = 0;
+            }
+        }
+        if ctx.input.is_action_pressed(Action::Cancel) {
+            return ScreenTransition::Pop;
+        }
+
+        ScreenTransition::Stay
+    }
+
+    fn render(&self, ctx: &AppContext, renderer: &mut Renderer) {
+        draw_title(renderer, "History");
+
+        if let Some(lines) = &self.lines {
+            let size = ctx.config.settings.text_size;
+            draw_lines_at_size(renderer, lines, self.first_line, size);
+            let hint = action_hint(&ctx.input, &[(Action::Cancel, "back")]);
+            draw_hint(renderer, &format!("{}  {}", hint, scroll_hint(&ctx.input)));
+            return;
+        }
+
+        match &self.menu {
+            Some(menu) if !menu.items().is_empty() => {
+                menu.render(renderer, |(_, preview)| preview.as_str());
+                let actions = [(Action::Confirm, "open"), (Action::Cancel, "back")];
+                draw_hint(renderer, &action_hint(&ctx.input, &actions));
+            }
+            _ => {
+                draw_lines(renderer, &["No exchanges yet."], 0);
+                draw_hint(
+                    renderer,
+                    &action_hint(&ctx.input, &[(Action::Cancel, "back")]),
+                );
+            }
+        }
+    }
+}
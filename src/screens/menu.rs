@@ -0,0 +1,87 @@
+use alloc::{boxed::Box, vec};
+
+use crate::{
+    app::{AppContext, Screen, ScreenTransition},
+    gfx::Renderer,
+    screens::{
+        action_hint, chat::ChatScreen, draw_hint, draw_title, history::HistoryScreen,
+        settings::SettingsScreen, templates::TemplatesScreen, Menu,
+    },
+    utils::Action,
+};
+
+/// The entries of the main menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MainMenuItem {
+    Chat,
+    Persona,
+    History,
+    Settings,
+    Exit,
+}
+
+impl MainMenuItem {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MainMenuItem::Chat => "New chat",
+            MainMenuItem::Persona => "Persona",
+            MainMenuItem::History => "History",
+            MainMenuItem::Settings => "Settings",
+            MainMenuItem::Exit => "Exit",
+        }
+    }
+}
+
+/// The main menu, shown once the network is connected.
+pub struct MainMenuScreen {
+    menu: Menu<MainMenuItem>,
+}
+
+impl MainMenuScreen {
+    pub fn new() -> Self {
+        Self {
+            menu: Menu::new(vec![
+                MainMenuItem::Chat,
+                MainMenuItem::Persona,
+                MainMenuItem::History,
+                MainMenuItem::Settings,
+                MainMenuItem::Exit,
+            ]),
+        }
+    }
+}
+
+impl Screen for MainMenuScreen {
+    fn update(&mut self, ctx: &mut AppContext) -> ScreenTransition {
+        self.menu.update(&ctx.input);
+
+        if !ctx.input.is_action_pressed(Action::Confirm) {
+            return ScreenTransition::Stay;
+        }
+
+        match self.menu.selected() {
+            Some(MainMenuItem::Chat) => match ChatScreen::new(ctx) {
+                Ok(screen) => ScreenTransition::Push(Box::new(screen)),
+                Err(error) => ScreenTransition::Push(Box::new(error)),
+            },
+            Some(MainMenuItem::Persona) => {
+                ScreenTransition::Push(Box::new(TemplatesScreen::new(ctx)))
+            }
+            Some(MainMenuItem::History) => ScreenTransition::Push(Box::new(HistoryScreen::new())),
+            Some(MainMenuItem::Settings) => {
+                ScreenTransition::Push(Box::new(SettingsScreen::new(ctx)))
+            }
+            Some(MainMenuItem::Exit) => ScreenTransition::Exit,
+            None => ScreenTransition::Stay,
+        }
+    }
+
+    fn render(&self, ctx: &AppContext, renderer: &mut Renderer) {
+        draw_title(renderer, "ChatGPSP");
+        self.menu.render(renderer, |item| item.label());
+        draw_hint(
+            renderer,
+            &action_hint(&ctx.input, &[(Action::Confirm, "select")]),
+        );
+    }
+}
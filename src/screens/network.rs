@@ -0,0 +1,197 @@
+use alloc::{boxed::Box, format, string::String, vec::Vec};
+use psp::sys::ApctlState;
+
+use crate::{
+    app::{AppContext, Screen, ScreenTransition},
+    fs::PspFs,
+    gfx::{color, Renderer},
+    net::{self, monitor::ConnectivityMonitor, AccessPoint},
+    screens::{
+        action_hint, draw_hint, draw_lines, draw_title, error::ErrorScreen, menu::MainMenuScreen,
+        Menu, HINT_Y,
+    },
+    utils::Action,
+};
+
+/// Number of frames to wait for the connection, before giving up (about 30 seconds).
+const CONNECT_TIMEOUT_FRAMES: u32 = 30 * 60;
+
+/// A connection attempt to an access point.
+struct Connection {
+    access_point: AccessPoint,
+    frames: u32,
+    state: ApctlState,
+    /// The names of the states the connection went through, oldest first.
+    states: Vec<&'static str>,
+    /// The number of times the PSP had woken up from sleep when the attempt started.
+    resumes: u32,
+}
+
+/// The screen connecting to the network, shown at startup.
+///
+/// It lists the access point connection profiles stored in the system settings, with the one
+/// used last selected, and connects to the chosen one. Once connected, it remembers the profile,
+/// resolves the OpenAI API host and moves on to the main menu.
+pub struct NetworkScreen {
+    initialized: bool,
+    access_points: Menu<AccessPoint>,
+    connection: Option<Connection>,
+}
+
+impl NetworkScreen {
+    pub fn new(ctx: &AppContext) -> Self {
+        let mut access_points = Menu::new(net::access_points());
+        if let Some(last) = ctx.config.settings.network_profile {
+            access_points.select_first(|access_point| access_point.id == last);
+        }
+
+        Self {
+            initialized: false,
+            access_points,
+            connection: None,
+        }
+    }
+
+    fn update_connection(&mut self, ctx: &mut AppContext) -> ScreenTransition {
+        let Some(connection) = &mut self.connection else {
+            return ScreenTransition::Stay;
+        };
+
+        if connection.resumes != ctx.resumes {
+            // the attempt did not survive the sleep: start over
+            net::disconnect();
+            if let Err(e) = net::connect(connection.access_point.id) {
+                let message = format!(
+                    "Failed to connect to {}: {:?}",
+                    connection.access_point.label, e
+                );
+                self.connection = None;
+                return ScreenTransition::Push(Box::new(ErrorScreen::new(&message)));
+            }
+            connection.frames = 0;
+            connection.states.clear();
+            connection.resumes = ctx.resumes;
+        }
+
+        connection.state = net::state();
+        let name = net::state_name(connection.state);
+        if connection.states.last() != Some(&name) {
+            connection.states.push(name);
+        }
+
+        if let ApctlState::GotIp = connection.state {
+            ctx.config.settings.network_profile = Some(connection.access_point.id);
+            // not remembering the profile is not worth bothering the user
+            let _ = ctx.config.save(&mut PspFs);
+
+            return match ctx.connect_openai() {
+                Ok(()) => {
+                    ctx.monitor = Some(ConnectivityMonitor::new(connection.access_point.id));
+                    ScreenTransition::Reset(Box::new(MainMenuScreen::new()))
+                }
+                Err(message) => ScreenTransition::Replace(Box::new(ErrorScreen::fatal(&message))),
+            };
+        }
+
+        if ctx.input.is_action_pressed(Action::Cancel) {
+            net::disconnect();
+            self.connection = None;
+            return ScreenTransition::Stay;
+        }
+
+        connection.frames += 1;
+        if connection.frames >= CONNECT_TIMEOUT_FRAMES {
+            let message = format!(
+                "Timed out connecting to access point {}.",
+                connection.access_point.label
+            );
+            net::disconnect();
+            self.connection = None;
+            return ScreenTransition::Push(Box::new(ErrorScreen::new(&message)));
+        }
+
+        ScreenTransition::Stay
+    }
+}
+
+impl Screen for NetworkScreen {
+    fn update(&mut self, ctx: &mut AppContext) -> ScreenTransition {
+        if self.connection.is_some() {
+            return self.update_connection(ctx);
+        }
+
+        self.access_points.update(&ctx.input);
+        if ctx.input.is_action_pressed(Action::Cancel) {
+            return ScreenTransition::Exit;
+        }
+        if !ctx.input.is_action_pressed(Action::Confirm) {
+            return ScreenTransition::Stay;
+        }
+        let Some(access_point) = self.access_points.selected().cloned() else {
+            return ScreenTransition::Stay;
+        };
+
+        if !self.initialized {
+            if let Err(e) = net::init() {
+                let message = format!("Failed to initialize network: {:?}", e);
+                return ScreenTransition::Replace(Box::new(ErrorScreen::fatal(&message)));
+            }
+            self.initialized = true;
+        }
+
+        if let Err(e) = net::connect(access_point.id) {
+            let message = format!("Failed to connect to {}: {:?}", access_point.label, e);
+            return ScreenTransition::Push(Box::new(ErrorScreen::new(&message)));
+        }
+        self.connection = Some(Connection {
+            access_point,
+            frames: 0,
+            state: ApctlState::Disconnected,
+            states: Vec::new(),
+            resumes: ctx.resumes,
+        });
+
+        ScreenTransition::Stay
+    }
+
+    fn render(&self, ctx: &AppContext, renderer: &mut Renderer) {
+        if let Some(connection) = &self.connection {
+            draw_title(renderer, "Connecting");
+            let mut lines = Vec::with_capacity(connection.states.len() + 2);
+            lines.push(format!("Access point {}", connection.access_point.label));
+            lines.push(String::new());
+            lines.extend(connection.states.iter().map(|state| format!("- {}", state)));
+            draw_lines(renderer, &lines, 0);
+
+            let seconds_left = (CONNECT_TIMEOUT_FRAMES - connection.frames) / 60;
+            let timeout = format!("Giving up in {}s", seconds_left);
+            renderer.draw_text(8, HINT_Y - 12, &timeout, color::GRAY);
+            draw_hint(
+                renderer,
+                &action_hint(&ctx.input, &[(Action::Cancel, "cancel")]),
+            );
+            return;
+        }
+
+        draw_title(renderer, "Choose an access point");
+        if self.access_points.items().is_empty() {
+            draw_lines(
+                renderer,
+                &[
+                    "No access point is set up.",
+                    "Add one in Settings > Network Settings.",
+                ],
+                0,
+            );
+            draw_hint(
+                renderer,
+                &action_hint(&ctx.input, &[(Action::Cancel, "exit")]),
+            );
+        } else {
+            self.access_points
+                .render(renderer, |access_point| access_point.label.as_str());
+            let actions = [(Action::Confirm, "connect"), (Action::Cancel, "exit")];
+            draw_hint(renderer, &action_hint(&ctx.input, &actions));
+        }
+    }
+}
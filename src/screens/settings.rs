@@ -0,0 +1,253 @@
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
+
+use chat_gpsp_core::{
+    layout::{TextSize, Theme, CONTENT_Y, LINE_HEIGHT},
+    settings::{format_temperature, Settings},
+};
+
+use crate::{
+    app::{AppContext, Screen, ScreenTransition},
+    fs::PspFs,
+    gfx::{color, Renderer},
+    net::{self, AccessPoint},
+    screens::{
+        action_hint, calibration::CalibrationScreen, draw_hint, draw_title, error::ErrorScreen,
+        Menu, CONTENT_LINES, HINT_Y,
+    },
+    text_input::InputMethod,
+    utils::{Action, Buttons},
+};
+
+/// X coordinate of the values of the settings.
+const VALUE_X: i16 = 160;
+
+/// The entries of the settings screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SettingsItem {
+    Model,
+    Temperature,
+    NetworkProfile,
+    TextSize,
+    Theme,
+    InputMethod,
+    Calibration,
+}
+
+impl SettingsItem {
+    fn label(&self) -> &'static str {
+        match self {
+            SettingsItem::Model => "Model",
+            SettingsItem::Temperature => "Temperature",
+            SettingsItem::NetworkProfile => "Network",
+            SettingsItem::TextSize => "Text size",
+            SettingsItem::Theme => "Colors",
+            SettingsItem::InputMethod => "Keyboard",
+            SettingsItem::Calibration => "Calibrate the stick",
+        }
+    }
+}
+
+/// The item of `items` `step` places away from `current`, wrapping around. The first item if
+/// `current` is not one of them.
+fn cycle<T: Copy + PartialEq>(items: &[T], current: T, step: isize) -> T {
+    let len = items.len() as isize;
+    let index = items
+        .iter()
+        .position(|item| *item == current)
+        .map_or(0, |index| (index as isize + step).rem_euclid(len));
+    items[index as usize]
+}
+
+/// The settings screen.
+///
+/// Changes apply as they are made, except for the network profile, which is switched to when
+/// leaving the screen, as the settings are saved to the configuration file.
+pub struct SettingsScreen {
+    menu: Menu<SettingsItem>,
+    /// The access point connection profiles to choose from.
+    access_points: Vec<AccessPoint>,
+    /// The settings when the screen was opened, to know whether to save them.
+    saved: Settings,
+    /// The outcome of the last change, if it needs to be told.
+    message: Option<String>,
+}
+
+impl SettingsScreen {
+    pub fn new(ctx: &AppContext) -> Self {
+        Self {
+            menu: Menu::new(vec![
+                SettingsItem::Model,
+                SettingsItem::Temperature,
+                SettingsItem::NetworkProfile,
+                SettingsItem::TextSize,
+                SettingsItem::Theme,
+                SettingsItem::InputMethod,
+                SettingsItem::Calibration,
+            ]),
+            access_points: net::access_points(),
+            saved: ctx.config.settings.clone(),
+            message: None,
+        }
+    }
+
+    /// Use the model name typed, once the user is done typing it.
+    fn edit_model(&mut self, ctx: &mut AppContext) {
+        let Some(text) = ctx.text_input.update(&mut ctx.input) else {
+            return;
+        };
+        self.message = match text {
+            Ok(Some(model)) => ctx
+                .config
+                .settings
+                .set_model(&model)
+                .err()
+                .map(|e| format!("Not changed: {}.", e)),
+            Ok(None) => None,
+            Err(e) => Some(format!("Error: {}. Please try again.", e)),
+        };
+    }
+
+    /// Change the value of `item` by `step`: the next value if positive, the previous one if
+    /// negative.
+    fn adjust(&mut self, ctx: &mut AppContext, item: SettingsItem, step: isize) {
+        let settings = &mut ctx.config.settings;
+        match item {
+            SettingsItem::Temperature => settings.adjust_temperature(step as i8),
+            SettingsItem::NetworkProfile => {
+                let ids: Vec<i32> = self.access_points.iter().map(|ap| ap.id).collect();
+                if ids.is_empty() {
+                    return;
+                }
+                let profile = match settings.network_profile {
+                    Some(current) => cycle(&ids, current, step),
+                    None => ids[0],
+                };
+                // connecting to each profile scrolled past would drop the link every time
+                settings.network_profile = Some(profile);
+            }
+            SettingsItem::TextSize => {
+                settings.text_size = cycle(&TextSize::ALL, settings.text_size, step)
+            }
+            SettingsItem::Theme => settings.theme = cycle(&Theme::ALL, settings.theme, step),
+            SettingsItem::InputMethod => {
+                let input_method = cycle(&InputMethod::ALL, settings.input_method, step);
+                ctx.set_input_method(input_method);
+            }
+            SettingsItem::Model | SettingsItem::Calibration => (),
+        }
+    }
+
+    /// The current value of `item`, as shown.
+    fn value(&self, ctx: &AppContext, item: SettingsItem) -> String {
+        let settings = &ctx.config.settings;
+        match item {
+            SettingsItem::Model => settings.model.clone(),
+            SettingsItem::Temperature => format_temperature(settings.temperature),
+            SettingsItem::NetworkProfile => {
+                settings.network_profile.map_or("None".into(), |profile| {
+                    self.access_points
+                        .iter()
+                        .find(|ap| ap.id == profile)
+                        .map_or(format!("{}: (removed)", profile), |ap| ap.label.clone())
+                })
+            }
+            SettingsItem::TextSize => match settings.text_size {
+                TextSize::Normal => "Normal".into(),
+                TextSize::Large => "Large".into(),
+            },
+            SettingsItem::Theme => match settings.theme {
+                Theme::Dark => "Dark".into(),
+                Theme::Light => "Light".into(),
+                Theme::Amber => "Amber".into(),
+            },
+            SettingsItem::InputMethod => match settings.input_method {
+                InputMethod::Osk => "System keyboard".into(),
+                InputMethod::Keyboard => "In-app keyboard".into(),
+            },
+            SettingsItem::Calibration => String::new(),
+        }
+    }
+
+    /// Switch to the network profile chosen, save the settings if they changed, and leave the
+    /// screen.
+    fn leave(&mut self, ctx: &mut AppContext) -> ScreenTransition {
+        if let Some(profile) = ctx.config.settings.network_profile {
+            if Some(profile) != self.saved.network_profile {
+                ctx.switch_network(profile);
+            }
+        }
+        if ctx.config.settings == self.saved {
+            return ScreenTransition::Pop;
+        }
+        if let Err(e) = ctx.config.settings.validate() {
+            let message = format!("Invalid settings, kept for this session: {}.", e);
+            return ScreenTransition::Replace(Box::new(ErrorScreen::new(&message)));
+        }
+        match ctx.config.save(&mut PspFs) {
+            Ok(()) => ScreenTransition::Pop,
+            Err(e) => {
+                let message = format!("Failed to save the settings: {}", e);
+                ScreenTransition::Replace(Box::new(ErrorScreen::new(&message)))
+            }
+        }
+    }
+}
+
+impl Screen for SettingsScreen {
+    fn update(&mut self, ctx: &mut AppContext) -> ScreenTransition {
+        if ctx.text_input.is_open() {
+            self.edit_model(ctx);
+            return ScreenTransition::Stay;
+        }
+        self.menu.update(&ctx.input);
+        let Some(item) = self.menu.selected().copied() else {
+            return ScreenTransition::Stay;
+        };
+
+        if ctx.input.is_action_pressed(Action::Confirm) {
+            self.message = None;
+            match item {
+                SettingsItem::Model => ctx.text_input.open("Model"),
+                SettingsItem::Calibration => {
+                    return ScreenTransition::Push(Box::new(CalibrationScreen::new()))
+                }
+                item => self.adjust(ctx, item, 1),
+            }
+        } else if ctx.input.is_repeated(Buttons::RIGHT) {
+            self.adjust(ctx, item, 1);
+        } else if ctx.input.is_repeated(Buttons::LEFT) {
+            self.adjust(ctx, item, -1);
+        } else if ctx.input.is_action_pressed(Action::Cancel) {
+            return self.leave(ctx);
+        }
+
+        ScreenTransition::Stay
+    }
+
+    fn render(&self, ctx: &AppContext, renderer: &mut Renderer) {
+        if ctx.text_input.render(&ctx.input, renderer) {
+            return;
+        }
+        draw_title(renderer, "Settings");
+        self.menu.render(renderer, |item| item.label());
+        // every item fits on the screen, so the menu does not scroll
+        for (i, item) in self.menu.items().iter().take(CONTENT_LINES).enumerate() {
+            let y = CONTENT_Y + i as i16 * LINE_HEIGHT;
+            renderer.draw_text(VALUE_X, y, &self.value(ctx, *item), color::WHITE);
+        }
+
+        if let Some(message) = &self.message {
+            renderer.draw_text(8, HINT_Y - 12, message, color::YELLOW);
+        }
+        let hint = action_hint(
+            &ctx.input,
+            &[(Action::Confirm, "change"), (Action::Cancel, "save")],
+        );
+        let adjust = format!(
+            "{}/{}: adjust",
+            Buttons::LEFT.label(),
+            Buttons::RIGHT.label()
+        );
+        draw_hint(renderer, &format!("{}  {}", hint, adjust));
+    }
+}
@@ -0,0 +1,43 @@
+use alloc::boxed::Box;
+
+use crate::{
+    app::{AppContext, Screen, ScreenTransition},
+    gfx::{color, Renderer},
+    screens::network::NetworkScreen,
+    utils::Buttons,
+};
+
+/// Number of frames the splash screen is shown for, unless a button is pressed.
+const SPLASH_FRAMES: u32 = 120;
+
+/// The first screen, showing the application name while it starts.
+pub struct SplashScreen {
+    frames: u32,
+}
+
+impl SplashScreen {
+    pub fn new() -> Self {
+        Self { frames: 0 }
+    }
+}
+
+impl Screen for SplashScreen {
+    fn update(&mut self, ctx: &mut AppContext) -> ScreenTransition {
+        self.frames += 1;
+        if self.frames >= SPLASH_FRAMES || ctx.input.is_pressed(Buttons::all()) {
+            return ScreenTransition::Replace(Box::new(NetworkScreen::new(ctx)));
+        }
+
+        ScreenTransition::Stay
+    }
+
+    fn render(&self, _ctx: &AppContext, renderer: &mut Renderer) {
+        renderer.draw_text(200, 120, "ChatGPSP", color::WHITE);
+        renderer.draw_text(
+            164,
+            136,
+            concat!("version ", env!("CARGO_PKG_VERSION")),
+            color::GRAY,
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use alloc::{format, string::String, vec};
+
+use chat_gpsp_core::templates::{load_templates, Template, TEMPLATES_DIR};
+
+use crate::{
+    app::{AppContext, Screen, ScreenTransition},
+    fs::{data_path, PspFs},
+    gfx::{color, Renderer},
+    screens::{action_hint, draw_hint, draw_title, Menu, HINT_Y},
+    utils::Action,
+};
+
+/// Label of the entry chatting without a template.
+const NO_TEMPLATE: &str = "None";
+
+/// The persona picker, listing the templates found on the Memory Stick.
+///
+/// The chosen template applies to the conversations started afterwards.
+pub struct TemplatesScreen {
+    /// The templates, after the entry for none.
+    menu: Menu<Option<Template>>,
+}
+
+impl TemplatesScreen {
+    pub fn new(ctx: &AppContext) -> Self {
+        let mut items = vec![None];
+        items.extend(load_templates(&PspFs).into_iter().map(Some));
+        let mut menu = Menu::new(items);
+
+        let current: Option<&String> = ctx.template.as_ref().map(|template| &template.name);
+        menu.select_first(|item| item.as_ref().map(|template| &template.name) == current);
+
+        Self { menu }
+    }
+}
+
+impl Screen for TemplatesScreen {
+    fn update(&mut self, ctx: &mut AppContext) -> ScreenTransition {
+        self.menu.update(&ctx.input);
+
+        if ctx.input.is_action_pressed(Action::Confirm) {
+            if let Some(template) = self.menu.selected() {
+                ctx.template = template.clone();
+            }
+            return ScreenTransition::Pop;
+        }
+        if ctx.input.is_action_pressed(Action::Cancel) {
+            return ScreenTransition::Pop;
+        }
+
+        ScreenTransition::Stay
+    }
+
+    fn render(&self, ctx: &AppContext, renderer: &mut Renderer) {
+        draw_title(renderer, "Persona");
+        self.menu.render(renderer, |item| {
+            item.as_ref()
+                .map_or(NO_TEMPLATE, |template| template.name.as_str())
+        });
+        if self.menu.items().len() == 1 {
+            let status = format!("No template in {}", data_path(TEMPLATES_DIR));
+            renderer.draw_text(8, HINT_Y - 12, &status, color::GRAY);
+        }
+        let actions = [(Action::Confirm, "choose"), (Action::Cancel, "back")];
+        draw_hint(renderer, &action_hint(&ctx.input, &actions));
+    }
+}
@@ -0,0 +1,104 @@
+use alloc::string::String;
+
+pub use chat_gpsp_core::keyboard::InputMethod;
+
+use chat_gpsp_core::keyboard::{Keyboard, KeyboardStatus};
+
+use crate::{
+    fs::PspFs,
+    gfx::Renderer,
+    keyboard,
+    osk::{builder::OskBuilder, OskError},
+    utils::{Action, InputHandler, PspInput},
+};
+
+/// The editor of a [`TextInput`].
+enum Editor {
+    Osk { max_length: usize },
+    Keyboard(Keyboard),
+}
+
+/// A source of text typed by the user, using one of the [`InputMethod`]s.
+///
+/// A screen [opens](Self::open) it, then [updates](Self::update) and [renders](Self::render) it
+/// in its own place every frame until the user is done.
+pub struct TextInput {
+    editor: Editor,
+    /// What the text is for, while it is being typed.
+    description: Option<String>,
+}
+
+impl TextInput {
+    /// Create a text input using `method`, accepting up to `max_length` characters.
+    pub fn new(method: InputMethod, max_length: usize) -> Self {
+        let editor = match method {
+            InputMethod::Osk => Editor::Osk { max_length },
+            InputMethod::Keyboard => Editor::Keyboard(Keyboard::load(&PspFs, max_length)),
+        };
+        Self {
+            editor,
+            description: None,
+        }
+    }
+
+    /// Let the user type some text, for `description`.
+    pub fn open(&mut self, description: &str) {
+        if let Editor::Keyboard(keyboard) = &mut self.editor {
+            keyboard.clear();
+        }
+        self.description = Some(description.into());
+    }
+
+    /// Whether the user is typing some text.
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.description.is_some()
+    }
+
+    /// Handle the input of the current frame, with the buttons of the actions of `input`.
+    ///
+    /// The system OSK takes over the screen until it is closed, after which `input` is resynced,
+    /// so that the button that closed it does not act on the screen too.
+    ///
+    /// # Returns
+    /// - `None` while the user is typing, or if the text input is not open.
+    /// - `Some(Ok(None))` if the user cancelled.
+    /// - `Some(Ok(Some(String)))` with the entered text otherwise.
+    /// - `Some(Err(OskError))` if the system OSK failed.
+    pub fn update(&mut self, input: &mut InputHandler) -> Option<Result<Option<String>, OskError>> {
+        let description = self.description.as_deref()?;
+        let text = match &mut self.editor {
+            Editor::Osk { max_length } => {
+                let text = OskBuilder::new(description)
+                    .max_length(*max_length)
+                    .confirm_button(input.map().buttons(Action::Confirm))
+                    .build()
+                    .read();
+                input.resync(&mut PspInput);
+                text
+            }
+            Editor::Keyboard(keyboard) => match keyboard.update(input) {
+                KeyboardStatus::Typing => return None,
+                KeyboardStatus::Done(text) => Ok(Some(text)),
+                KeyboardStatus::Cancelled => Ok(None),
+            },
+        };
+
+        self.description = None;
+        Some(text)
+    }
+
+    /// Draw the in-app keyboard, while open. The system OSK draws itself.
+    ///
+    /// # Returns
+    /// Whether the keyboard was drawn, taking the whole screen.
+    pub fn render(&self, input: &InputHandler, renderer: &mut Renderer) -> bool {
+        match (&self.editor, &self.description) {
+            (Editor::Keyboard(keyboard), Some(description)) => {
+                keyboard::render(keyboard, description, input, renderer);
+                true
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use alloc::{boxed::Box, sync::Arc};
+use core::{
+    ffi::c_void,
+    fmt::Display,
+    mem::size_of,
+    ptr::{self, addr_of_mut},
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use chat_gpsp_core::net::abort::Abort;
+use psp::sys::{self, SceUid, ThreadAttributes};
+
+use crate::openai::network;
+
+/// Priority of the worker threads. Lower than the main thread, so that the UI stays responsive.
+const WORKER_PRIORITY: i32 = 0x30;
+/// Stack size of the worker threads. TLS keeps its record buffers on the stack.
+const WORKER_STACK_SIZE: i32 = 256 * 1024;
+/// The user memory partition, where the message pipes are allocated.
+const USER_PARTITION: i32 = 2;
+
+/// The worker is still running.
+const RUNNING: u8 = 0;
+/// The worker sent its result, and leaves the pipe to the task.
+const DONE: u8 = 1;
+/// The task was dropped, and leaves the pipe to the worker.
+const DROPPED: u8 = 2;
+
+/// Errors that can occur when starting a [`Task`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkerError {
+    CreatePipe(i32),
+    CreateThread(i32),
+    StartThread(i32),
+}
+
+impl Display for WorkerError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            WorkerError::CreatePipe(code) => {
+                write!(f, "Failed to create message pipe (error {:#x})", code)
+            }
+            WorkerError::CreateThread(code) => {
+                write!(f, "Failed to create worker thread (error {:#x})", code)
+            }
+            WorkerError::StartThread(code) => {
+                write!(f, "Failed to start worker thread (error {:#x})", code)
+            }
+        }
+    }
+}
+
+/// The work to do on the worker thread, and where to send its result.
+struct Job<T> {
+    pipe: SceUid,
+    /// [`RUNNING`], [`DONE`] or [`DROPPED`]: whoever of the task and the worker is left last
+    /// deletes the pipe.
+    state: Arc<AtomicU8>,
+    abort: Arc<Abort>,
+    work: Box<dyn FnOnce(Arc<Abort>) -> T + Send>,
+}
+
+/// A computation running on a kernel worker thread.
+///
+/// The worker sends its result back through a message pipe, which the UI polls every frame with
+/// [`Self::poll`], so that blocking calls like socket reads do not freeze the screen.
+///
+/// Dropping a task before it is done cancels it: its [`Abort`] is triggered, shutting down the
+/// socket the work is blocked on, and the result, whenever it comes, is freed unread.
+pub struct Task<T> {
+    pipe: SceUid,
+    state: Arc<AtomicU8>,
+    abort: Arc<Abort>,
+    /// System time, in microseconds, when the task was started.
+    started: i64,
+    result: Option<T>,
+}
+
+impl<T: Send + 'static> Task<T> {
+    /// Run `work` on a new worker thread.
+    ///
+    /// `work` is given the [`Abort`] of the task, to stop early once it is cancelled, e.g. by
+    /// sending its requests through [`PspNetwork::abortable`](network::PspNetwork::abortable).
+    ///
+    /// # Errors
+    /// A [`WorkerError`] if the message pipe or the thread cannot be created.
+    pub fn spawn<F>(work: F) -> Result<Self, WorkerError>
+    where
+        F: FnOnce(Arc<Abort>) -> T + Send + 'static,
+    {
+        // the pipe buffers a single message: the pointer to the result
+        let pipe = unsafe {
+            sys::sceKernelCreateMsgPipe(
+                c"worker_pipe".as_ptr() as *const u8,
+                USER_PARTITION,
+                0,
+                size_of::<usize>() as *mut c_void,
+                ptr::null_mut(),
+            )
+        };
+        if pipe.0 < 0 {
+            return Err(WorkerError::CreatePipe(pipe.0));
+        }
+
+        let thread = unsafe {
+            sys::sceKernelCreateThread(
+                c"worker".as_ptr() as *const u8,
+                run::<T>,
+                WORKER_PRIORITY,
+                WORKER_STACK_SIZE,
+                ThreadAttributes::USER,
+                ptr::null_mut(),
+            )
+        };
+        if thread.0 < 0 {
+            unsafe {
+                sys::sceKernelDeleteMsgPipe(pipe);
+            }
+            return Err(WorkerError::CreateThread(thread.0));
+        }
+
+        // the thread receives a copy of the pointer to the job, and takes ownership of it
+        let state = Arc::new(AtomicU8::new(RUNNING));
+        let abort = Arc::new(Abort::new());
+        let mut job = Box::into_raw(Box::new(Job {
+            pipe,
+            state: state.clone(),
+            abort: abort.clone(),
+            work: Box::new(work),
+        }));
+        let res = unsafe {
+            sys::sceKernelStartThread(
+                thread,
+                size_of::<*mut Job<T>>(),
+                addr_of_mut!(job) as *mut c_void,
+            )
+        };
+        if res < 0 {
+            unsafe {
+                drop(Box::from_raw(job));
+                sys::sceKernelDeleteThread(thread);
+                sys::sceKernelDeleteMsgPipe(pipe);
+            }
+            return Err(WorkerError::StartThread(res));
+        }
+
+        Ok(Self {
+            pipe,
+            state,
+            abort,
+            started: unsafe { sys::sceKernelGetSystemTimeWide() },
+            result: None,
+        })
+    }
+}
+
+impl<T> Task<T> {
+    /// Check whether the worker is done, without blocking.
+    ///
+    /// # Returns
+    /// - `None` if the worker is still running, or if the result was already taken.
+    /// - `Some(T)` with the result of the work otherwise, only once.
+    pub fn poll(&mut self) -> Option<T> {
+        self.receive();
+        self.result.take()
+    }
+
+    /// The time elapsed since the task was started, in milliseconds.
+    pub fn elapsed_ms(&self) -> u64 {
+        let now = unsafe { sys::sceKernelGetSystemTimeWide() };
+        ((now - self.started) / 1000) as u64
+    }
+
+    fn receive(&mut self) {
+        if self.result.is_none() {
+            self.result = unsafe { try_receive(self.pipe) };
+        }
+    }
+}
+
+impl<T> Drop for Task<T> {
+    fn drop(&mut self) {
+        network::abort(&self.abort);
+        if self.state.swap(DROPPED, Ordering::AcqRel) == DONE {
+            // take a result not polled yet, so that it is freed. A worker still running frees
+            // its result and deletes the pipe itself, once done
+            self.receive();
+            unsafe {
+                sys::sceKernelDeleteMsgPipe(self.pipe);
+            }
+        }
+    }
+}
+
+/// Take the result waiting in `pipe`, if any.
+///
+/// # Safety
+/// The messages of `pipe` must be pointers to a `T` created with [`Box::into_raw`].
+unsafe fn try_receive<T>(pipe: SceUid) -> Option<T> {
+    let mut message: *mut T = ptr::null_mut();
+    let res = sys::sceKernelTryReceiveMsgPipe(
+        pipe,
+        addr_of_mut!(message) as *mut c_void,
+        size_of::<*mut T>() as u32,
+        0,
+        ptr::null_mut(),
+    );
+    (res >= 0 && !message.is_null()).then(|| *Box::from_raw(message))
+}
+
+/// Entry point of the worker threads.
+///
+/// # Safety
+/// `argp` must point to a pointer to a [`Job<T>`] created with [`Box::into_raw`].
+unsafe extern "C" fn run<T>(_args: usize, argp: *mut c_void) -> i32 {
+    let Job {
+        pipe,
+        state,
+        abort,
+        work,
+    } = *Box::from_raw(*(argp as *mut *mut Job<T>));
+    let mut result = Box::into_raw(Box::new(work(abort)));
+
+    let res = sys::sceKernelSendMsgPipe(
+        pipe,
+        addr_of_mut!(result) as *mut c_void,
+        size_of::<*mut T>() as u32,
+        0,
+        ptr::null_mut(),
+        ptr::null_mut(),
+    );
+    if res < 0 {
+        drop(Box::from_raw(result));
+    }
+    if state.swap(DONE, Ordering::AcqRel) == DROPPED {
+        // the task was dropped, nobody is waiting for the result
+        drop(try_receive::<T>(pipe));
+        sys::sceKernelDeleteMsgPipe(pipe);
+    }
+
+    // exiting the thread does not run the destructors
+    drop(state);
+    sys::sceKernelExitDeleteThread(0)
+}